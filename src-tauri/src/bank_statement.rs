@@ -0,0 +1,331 @@
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use chrono::NaiveDate;
+use regex::Regex;
+use crate::error::{AppError, AppResult};
+use crate::i18n::tr;
+use crate::money::{parse_amount_number, Amount, Currency};
+
+/// 账单格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StatementFormat {
+    /// 银行或信用卡导出的通用CSV
+    Csv,
+    /// OFX/QFX
+    Ofx,
+    /// 支付宝交易明细CSV
+    Alipay,
+    /// 微信支付账单CSV
+    WechatPay,
+}
+
+/// 账单中的一笔支出
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatementLine {
+    pub date: NaiveDate,
+    /// 支出金额（正数）
+    pub amount: Amount,
+    pub currency: Currency,
+    /// 交易对方
+    pub counterparty: String,
+    pub description: String,
+    /// 交易流水号
+    pub reference: Option<String>,
+}
+
+/// 导入的账单
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Statement {
+    pub format: StatementFormat,
+    pub lines: Vec<StatementLine>,
+}
+
+/// CSV 各字段可能使用的列名
+const DATE_COLUMNS: &[&str] = &["交易时间", "交易日期", "记账日期", "交易创建时间", "日期", "date", "transaction date", "posting date", "posted date"];
+const AMOUNT_COLUMNS: &[&str] = &["金额(元)", "金额（元）", "交易金额", "入账金额", "消费金额", "金额", "amount"];
+const DEBIT_COLUMNS: &[&str] = &["支出", "支出金额", "借方金额", "debit", "withdrawal"];
+const CREDIT_COLUMNS: &[&str] = &["收入", "收入金额", "贷方金额", "credit", "deposit"];
+const DIRECTION_COLUMNS: &[&str] = &["收/支", "收支"];
+const COUNTERPARTY_COLUMNS: &[&str] = &["交易对方", "对方户名", "对方名称", "商户名称", "payee", "merchant", "name"];
+const DESCRIPTION_COLUMNS: &[&str] = &["商品说明", "商品名称", "商品", "摘要", "交易摘要", "用途", "memo", "details", "description"];
+const REFERENCE_COLUMNS: &[&str] = &["交易单号", "交易订单号", "交易号", "交易流水号", "流水号", "reference", "transaction id"];
+const CURRENCY_COLUMNS: &[&str] = &["币种", "currency"];
+const STATUS_COLUMNS: &[&str] = &["交易状态", "当前状态", "status"];
+
+/// 表头及之前的内容中出现这些词时按信用卡账单处理：正数为消费，负数为还款或退款
+const CARD_STATEMENT_MARKERS: &[&str] = &["信用卡", "卡号末四位", "卡号后四位", "入账金额", "消费金额", "credit card", "card member", "card number"];
+
+/// 交易状态中包含这些词的记录不计入
+const EXCLUDED_STATUS_WORDS: &[&str] = &["关闭", "失败", "退款", "撤销"];
+
+/// 账单导入器
+pub struct StatementImporter {
+    date_pattern: Regex,
+    ofx_transaction_pattern: Regex,
+    ofx_field_pattern: Regex,
+}
+
+impl StatementImporter {
+    pub fn new() -> Self {
+        StatementImporter {
+            date_pattern: Regex::new(r"(\d{4})[-/.年]?(\d{1,2})[-/.月]?(\d{1,2})").unwrap(),
+            ofx_transaction_pattern: Regex::new(r"(?is)<STMTTRN>(.*?)</STMTTRN>").unwrap(),
+            ofx_field_pattern: Regex::new(r"(?i)<([A-Z]+)>([^<\r\n]*)").unwrap(),
+        }
+    }
+
+    /// 读取账单文件，`format` 为空时自动识别格式
+    pub fn import(&self, path: &Path, format: Option<StatementFormat>) -> AppResult<Statement> {
+        let bytes = std::fs::read(path)
+            .map_err(|e| AppError::io_error_at(e, tr("读取账单失败", "Failed to read statement"), path))?;
+        let content = Self::decode(&bytes);
+        let format = format.unwrap_or_else(|| Self::detect_format(&content));
+
+        let lines = match format {
+            StatementFormat::Ofx => self.parse_ofx(&content),
+            _ => self.parse_csv(&content).map_err(|e| e.with_path(path))?,
+        };
+        Ok(Statement { format, lines })
+    }
+
+    /// 支付宝等导出的CSV常为GBK编码
+    fn decode(bytes: &[u8]) -> String {
+        let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+        match std::str::from_utf8(bytes) {
+            Ok(text) => text.to_string(),
+            Err(_) => encoding_rs::GBK.decode(bytes).0.into_owned(),
+        }
+    }
+
+    fn detect_format(content: &str) -> StatementFormat {
+        let head: String = content.chars().take(2000).collect();
+        if head.contains("OFXHEADER") || head.to_uppercase().contains("<OFX>") {
+            StatementFormat::Ofx
+        } else if head.contains("微信支付") {
+            StatementFormat::WechatPay
+        } else if head.contains("支付宝") {
+            StatementFormat::Alipay
+        } else {
+            StatementFormat::Csv
+        }
+    }
+
+    /// 解析CSV账单：先找到表头行，再按列名取值
+    fn parse_csv(&self, content: &str) -> AppResult<Vec<StatementLine>> {
+        let records: Vec<Vec<String>> = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(content.as_bytes())
+            .records()
+            .filter_map(|record| record.ok())
+            .map(|record| record.iter().map(|cell| cell.trim().trim_matches('\t').trim().to_string()).collect())
+            .collect();
+
+        let header_index = records.iter()
+            .position(|record| {
+                Self::column(record, DATE_COLUMNS).is_some()
+                    && (Self::column(record, AMOUNT_COLUMNS).is_some() || Self::column(record, DEBIT_COLUMNS).is_some())
+            })
+            .ok_or_else(|| AppError::validation_error(tr("无法识别账单表头", "Unable to recognize the statement header")))?;
+        let header = &records[header_index];
+
+        let date_column = Self::column(header, DATE_COLUMNS);
+        let amount_column = Self::column(header, AMOUNT_COLUMNS);
+        let debit_column = Self::column(header, DEBIT_COLUMNS);
+        let credit_column = Self::column(header, CREDIT_COLUMNS);
+        let direction_column = Self::column(header, DIRECTION_COLUMNS);
+        let counterparty_column = Self::column(header, COUNTERPARTY_COLUMNS);
+        let description_column = Self::column(header, DESCRIPTION_COLUMNS)
+            .filter(|column| Some(*column) != counterparty_column);
+        let reference_column = Self::column(header, REFERENCE_COLUMNS);
+        let currency_column = Self::column(header, CURRENCY_COLUMNS);
+        let status_column = Self::column(header, STATUS_COLUMNS);
+
+        // 单一金额列的符号含义：银行流水负数为支出，信用卡账单正数为消费
+        let preamble = records[..=header_index].iter()
+            .flatten()
+            .map(|cell| cell.to_lowercase())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let card_statement = CARD_STATEMENT_MARKERS.iter().any(|marker| preamble.contains(marker));
+
+        let cell = |record: &[String], column: Option<usize>| -> String {
+            column.and_then(|index| record.get(index)).cloned().unwrap_or_default()
+        };
+
+        let mut lines = Vec::new();
+        for record in &records[header_index + 1..] {
+            let Some(date) = self.parse_date(&cell(record, date_column)) else {
+                continue;
+            };
+            let status = cell(record, status_column);
+            if EXCLUDED_STATUS_WORDS.iter().any(|word| status.contains(word)) {
+                continue;
+            }
+
+            let signed = if direction_column.is_some() {
+                let amount = Self::parse_signed_amount(&cell(record, amount_column));
+                match cell(record, direction_column).as_str() {
+                    "支出" => amount.map(|amount| Amount::ZERO - Self::abs(amount)),
+                    _ => None,
+                }
+            } else if let Some(debit) = Self::parse_signed_amount(&cell(record, debit_column)).filter(|a| *a != Amount::ZERO) {
+                Some(Amount::ZERO - Self::abs(debit))
+            } else if Self::parse_signed_amount(&cell(record, credit_column)).is_some_and(|a| a != Amount::ZERO) {
+                None
+            } else {
+                Self::parse_signed_amount(&cell(record, amount_column))
+                    .map(|amount| if card_statement { Amount::ZERO - amount } else { amount })
+            };
+            // 带符号金额中负数为支出
+            let Some(signed) = signed.filter(|amount| *amount < Amount::ZERO) else {
+                continue;
+            };
+
+            let currency = Currency::from_marker(&cell(record, currency_column)).unwrap_or_default();
+            let reference = cell(record, reference_column);
+            lines.push(StatementLine {
+                date,
+                amount: Self::abs(signed),
+                currency,
+                counterparty: cell(record, counterparty_column),
+                description: cell(record, description_column),
+                reference: if reference.is_empty() { None } else { Some(reference) },
+            });
+        }
+        Ok(lines)
+    }
+
+    /// 解析OFX账单中的支出记录
+    fn parse_ofx(&self, content: &str) -> Vec<StatementLine> {
+        let currency = self.ofx_field_pattern.captures_iter(content)
+            .find(|caps| caps[1].eq_ignore_ascii_case("CURDEF"))
+            .and_then(|caps| Currency::from_marker(caps[2].trim()))
+            .unwrap_or_default();
+
+        self.ofx_transaction_pattern.captures_iter(content)
+            .filter_map(|transaction| {
+                let field = |name: &str| {
+                    self.ofx_field_pattern.captures_iter(&transaction[1])
+                        .find(|caps| caps[1].eq_ignore_ascii_case(name))
+                        .map(|caps| caps[2].trim().to_string())
+                        .unwrap_or_default()
+                };
+                let amount = Self::parse_signed_amount(&field("TRNAMT"))?;
+                if amount >= Amount::ZERO {
+                    return None;
+                }
+                let reference = field("FITID");
+                Some(StatementLine {
+                    date: self.parse_date(&field("DTPOSTED"))?,
+                    amount: Self::abs(amount),
+                    currency,
+                    counterparty: field("NAME"),
+                    description: field("MEMO"),
+                    reference: if reference.is_empty() { None } else { Some(reference) },
+                })
+            })
+            .collect()
+    }
+
+    /// 按列名找到列号（忽略大小写和首尾空白）
+    fn column(header: &[String], names: &[&str]) -> Option<usize> {
+        names.iter().find_map(|name| {
+            header.iter().position(|cell| cell.trim().eq_ignore_ascii_case(name))
+        })
+    }
+
+    fn parse_date(&self, value: &str) -> Option<NaiveDate> {
+        let caps = self.date_pattern.captures(value)?;
+        NaiveDate::from_ymd_opt(caps[1].parse().ok()?, caps[2].parse().ok()?, caps[3].parse().ok()?)
+    }
+
+    /// 解析带符号的金额，支持 ¥、负号和括号表示的负数
+    fn parse_signed_amount(value: &str) -> Option<Amount> {
+        let value = value.trim();
+        if value.is_empty() {
+            return None;
+        }
+        let negative = value.starts_with('-') || value.starts_with('(') || value.contains("-¥") || value.contains("¥-");
+        let digits: String = value.chars().filter(|c| c.is_ascii_digit() || *c == '.' || *c == ',').collect();
+        let amount = parse_amount_number(&digits)?;
+        Some(if negative { Amount::ZERO - amount } else { amount })
+    }
+
+    fn abs(amount: Amount) -> Amount {
+        if amount < Amount::ZERO { Amount::ZERO - amount } else { amount }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_wechat_csv() {
+        let importer = StatementImporter::new();
+        let content = "微信支付账单明细\n微信昵称：[test]\n\n交易时间,交易类型,交易对方,商品,收/支,金额(元),支付方式,当前状态,交易单号,商户单号,备注\n\
+2024-03-01 12:30:00,商户消费,星巴克,咖啡,支出,¥317.60,零钱,支付成功,4200001\t,10001\t,/\n\
+2024-03-02 09:00:00,转账,张三,/,收入,¥50.00,/,已存入零钱,4200002\t,/,/\n";
+
+        let lines = importer.parse_csv(content).unwrap();
+        assert_eq!(StatementImporter::detect_format(content), StatementFormat::WechatPay);
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].amount, Amount::from_cents(31760));
+        assert_eq!(lines[0].counterparty, "星巴克");
+        assert_eq!(lines[0].reference.as_deref(), Some("4200001"));
+    }
+
+    #[test]
+    fn test_parse_card_csv_with_repayment() {
+        let importer = StatementImporter::new();
+        let content = "信用卡账单明细\n交易日期,记账日期,交易摘要,卡号末四位,入账金额\n\
+2024-03-01,2024-03-02,某某酒店,1234,1234.50\n\
+2024-03-05,2024-03-05,还款,1234,-5000.00\n\
+2024-03-06,2024-03-07,滴滴出行,1234,35.20\n";
+
+        let lines = importer.parse_csv(content).unwrap();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].amount, Amount::from_cents(123450));
+        assert_eq!(lines[1].amount, Amount::from_cents(3520));
+        assert_eq!(lines[1].description, "滴滴出行");
+    }
+
+    #[test]
+    fn test_parse_bank_csv_keeps_only_outflows() {
+        let importer = StatementImporter::new();
+        let content = "交易日期,交易金额,对方户名,摘要\n\
+2024-03-01,-1234.50,某某酒店,住宿\n\
+2024-03-02,8000.00,某某公司,工资\n";
+
+        let lines = importer.parse_csv(content).unwrap();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].amount, Amount::from_cents(123450));
+        assert_eq!(lines[0].counterparty, "某某酒店");
+    }
+
+    #[test]
+    fn test_parse_ofx() {
+        let importer = StatementImporter::new();
+        let content = "OFXHEADER:100\n<OFX><BANKMSGSRSV1><STMTTRNRS><STMTRS><CURDEF>CNY\n<BANKTRANLIST>\n\
+<STMTTRN><TRNTYPE>DEBIT<DTPOSTED>20240301120000<TRNAMT>-1234.50<FITID>A1<NAME>某某酒店</STMTTRN>\n\
+<STMTTRN><TRNTYPE>CREDIT<DTPOSTED>20240302<TRNAMT>800.00<FITID>A2<NAME>工资</STMTTRN>\n\
+</BANKTRANLIST></STMTRS></STMTTRNRS></BANKMSGSRSV1></OFX>";
+
+        let lines = importer.parse_ofx(content);
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].amount, Amount::from_cents(123450));
+        assert_eq!(lines[0].date, NaiveDate::from_ymd_opt(2024, 3, 1).unwrap());
+        assert_eq!(lines[0].counterparty, "某某酒店");
+    }
+
+    #[test]
+    fn test_import_missing_file_keeps_error_kind() {
+        let importer = StatementImporter::new();
+        let path = std::env::temp_dir().join(format!("invoicepilot-statement-missing-{}.csv", std::process::id()));
+
+        let err = importer.import(&path, None).unwrap_err();
+        assert!(err.is_retryable());
+        assert_eq!(err.path(), Some(path.to_string_lossy().as_ref()));
+    }
+}
@@ -1,10 +1,26 @@
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use serde_json::{json, Map, Value};
+use std::path::{Path, PathBuf};
+use chrono::Utc;
 use crate::error::{AppError, AppResult};
+use crate::i18n::tr;
+use crate::logger;
+use crate::money::Amount;
+use crate::document_classifier::DocumentType;
+use crate::expense_category::CategoryRule;
+
+/// 当前配置格式版本，修改配置结构时递增并在 MIGRATIONS 中添加迁移步骤
+pub const CONFIG_SCHEMA_VERSION: u32 = 1;
 
 /// 应用程序配置
+///
+/// 缺少的字段取默认值，旧版本的配置文件不会因新增字段而无法加载
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AppConfig {
+    /// 配置格式版本，没有版本号的旧配置为 0
+    #[serde(default)]
+    pub schema_version: u32,
     /// 默认扫描目录
     pub default_scan_directory: Option<String>,
     /// 金额识别配置
@@ -15,10 +31,32 @@ pub struct AppConfig {
     pub rename_strategy: RenameStrategyConfig,
     /// 界面配置
     pub ui_config: UiConfig,
+    /// 多发票PDF拆分配置
+    #[serde(default)]
+    pub pdf_split: PdfSplitConfig,
+    /// 归档整理配置
+    #[serde(default)]
+    pub organize: OrganizeConfig,
+    /// 文件夹监控配置（界面配置中的 auto_refresh 为总开关）
+    #[serde(default)]
+    pub watch: WatchConfig,
+    /// 邮件发票导入配置
+    #[serde(default)]
+    pub mail_ingest: MailIngestConfig,
+    /// 加密PDF的打开密码
+    #[serde(default)]
+    pub pdf_passwords: PdfPasswordConfig,
+    /// 公司抬头（购买方）配置
+    #[serde(default)]
+    pub company: CompanyConfig,
+    /// 费用类别配置
+    #[serde(default)]
+    pub categories: CategoryConfig,
 }
 
 /// 金额识别配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AmountRecognitionConfig {
     /// 是否启用中文数字识别
     pub enable_chinese_digits: bool,
@@ -27,13 +65,14 @@ pub struct AmountRecognitionConfig {
     /// 是否启用货币符号识别
     pub enable_currency_symbols: bool,
     /// 金额识别的最小值
-    pub min_amount: f64,
+    pub min_amount: Amount,
     /// 金额识别的最大值
-    pub max_amount: f64,
+    pub max_amount: Amount,
 }
 
 /// 文件过滤配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct FileFilterConfig {
     /// 支持的文件扩展名
     pub supported_extensions: Vec<String>,
@@ -47,6 +86,7 @@ pub struct FileFilterConfig {
 
 /// 重命名策略配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct RenameStrategyConfig {
     /// 文件名格式模板
     pub filename_template: String,
@@ -59,7 +99,7 @@ pub struct RenameStrategyConfig {
 }
 
 /// 重名文件处理方式
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ConflictResolution {
     /// 添加序号后缀
     AddSuffix,
@@ -71,8 +111,221 @@ pub enum ConflictResolution {
     Ask,
 }
 
+/// 多发票PDF拆分配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PdfSplitConfig {
+    /// 拆分后原文件的处理方式
+    pub source_handling: SourceHandling,
+    /// 归档原文件的子目录名
+    pub archive_directory: String,
+}
+
+/// 拆分后原文件的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SourceHandling {
+    /// 保留在原位置
+    Keep,
+    /// 移动到归档子目录
+    Archive,
+}
+
+/// 归档整理配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OrganizeConfig {
+    /// 归档根目录
+    pub archive_root: Option<String>,
+    /// 子目录模板，支持 {年}、{月}、{日}、{销售方}、{类型}、{类别}、{币种}
+    pub directory_template: String,
+}
+
+/// 文件夹监控配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchConfig {
+    /// 监控的文件夹
+    pub folders: Vec<WatchFolder>,
+    /// 文件停止变化多久后再处理（毫秒）
+    pub debounce_ms: u64,
+}
+
+/// 单个监控文件夹
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchFolder {
+    pub path: String,
+    /// 新文件的处理方式
+    pub mode: WatchMode,
+    /// 是否同时处理图片（支付凭证）
+    pub include_images: bool,
+    /// 是否监控子目录
+    pub recursive: bool,
+}
+
+/// 监控到新文件后的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WatchMode {
+    /// 加入待确认队列，由用户一键确认重命名
+    Queue,
+    /// 按建议文件名自动重命名
+    AutoRename,
+}
+
+/// 邮件发票导入配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MailIngestConfig {
+    /// 附件和下载文件的暂存目录
+    pub staging_directory: Option<String>,
+    /// 邮件来源
+    pub sources: Vec<MailSourceConfig>,
+    /// 正文中需要下载的发票平台链接域名（包含子域名）
+    pub link_domains: Vec<String>,
+}
+
+/// 邮件来源
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum MailSourceConfig {
+    /// 本地 Maildir 目录
+    Maildir { path: String },
+    /// 本地 mbox 文件
+    Mbox { path: String },
+    /// IMAP 服务器
+    Imap(ImapConfig),
+}
+
+/// IMAP 服务器配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImapConfig {
+    pub host: String,
+    pub port: u16,
+    /// 是否使用 TLS（993 端口）
+    pub use_tls: bool,
+    pub username: String,
+    pub password: String,
+    /// 邮箱文件夹，默认 INBOX
+    pub mailbox: String,
+    /// 只读取最近多少天的邮件，0 表示不限
+    pub since_days: u32,
+}
+
+/// 加密PDF的打开密码，按发件人保存（银行、航空公司的电子账单等）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PdfPasswordConfig {
+    pub entries: Vec<PdfPasswordEntry>,
+}
+
+/// 一个发件人的PDF打开密码
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PdfPasswordEntry {
+    /// 发件人邮箱（如 "bill@bank.com"）或域名（如 "bank.com"，包含子域名），为空时适用于所有文件
+    pub sender: String,
+    pub password: String,
+}
+
+impl PdfPasswordEntry {
+    /// 是否适用于该发件人（邮件 From 头，如 "某银行 <bill@bank.com>"）
+    pub fn matches(&self, sender: &str) -> bool {
+        let pattern = self.sender.trim().to_lowercase();
+        if pattern.is_empty() {
+            return true;
+        }
+        let address = sender_address(sender);
+        if pattern.contains('@') {
+            return address == pattern;
+        }
+        let domain = address.rsplit('@').next().unwrap_or_default();
+        domain == pattern || domain.ends_with(&format!(".{}", pattern))
+    }
+}
+
+impl PdfPasswordConfig {
+    /// 按尝试顺序排列的密码：适用于该发件人的在前，其余在后（发件人未知的文件也能尝试）
+    pub fn passwords_for(&self, sender: Option<&str>) -> Vec<String> {
+        let (mut matched, others): (Vec<&PdfPasswordEntry>, Vec<&PdfPasswordEntry>) = self.entries.iter()
+            .partition(|entry| sender.is_some_and(|sender| entry.matches(sender)));
+        matched.extend(others);
+        let mut passwords: Vec<String> = Vec::new();
+        for entry in matched {
+            if !passwords.contains(&entry.password) {
+                passwords.push(entry.password.clone());
+            }
+        }
+        passwords
+    }
+
+    /// 保存发件人的密码（只保存邮箱地址），已有该发件人时替换
+    pub fn remember(&mut self, sender: &str, password: &str) {
+        let sender = sender_address(sender);
+        self.entries.retain(|entry| entry.sender.trim().to_lowercase() != sender);
+        self.entries.push(PdfPasswordEntry {
+            sender,
+            password: password.to_string(),
+        });
+    }
+}
+
+/// 邮件 From 头中的邮箱地址（小写）
+fn sender_address(sender: &str) -> String {
+    let sender = sender.trim().to_lowercase();
+    match (sender.rfind('<'), sender.rfind('>')) {
+        (Some(start), Some(end)) if start < end => sender[start + 1..end].trim().to_string(),
+        _ => sender,
+    }
+}
+
+/// 公司抬头配置，同一用户可能为多个法人主体报销
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CompanyConfig {
+    pub profiles: Vec<CompanyProfile>,
+    /// 当前使用的公司（profiles 中的 id）
+    pub active_profile: Option<String>,
+}
+
+impl CompanyConfig {
+    /// 当前使用的公司抬头
+    pub fn active(&self) -> Option<&CompanyProfile> {
+        let id = self.active_profile.as_deref()?;
+        self.profiles.iter().find(|profile| profile.id == id)
+    }
+}
+
+/// 公司抬头（开票信息）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CompanyProfile {
+    pub id: String,
+    /// 公司名称（发票购买方名称）
+    pub name: String,
+    /// 纳税人识别号
+    pub tax_id: String,
+    #[serde(default)]
+    pub address: String,
+    #[serde(default)]
+    pub phone: String,
+    /// 开户银行
+    #[serde(default)]
+    pub bank_name: String,
+    #[serde(default)]
+    pub bank_account: String,
+}
+
+/// 费用类别配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CategoryConfig {
+    /// 可选的费用类别
+    pub categories: Vec<String>,
+    /// 按顺序匹配的类别规则
+    pub rules: Vec<CategoryRule>,
+}
+
 /// 界面配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct UiConfig {
     /// 主题
     pub theme: Theme,
@@ -93,7 +346,7 @@ pub enum Theme {
 }
 
 /// 语言
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Language {
     Chinese,
     English,
@@ -102,11 +355,19 @@ pub enum Language {
 impl Default for AppConfig {
     fn default() -> Self {
         AppConfig {
+            schema_version: CONFIG_SCHEMA_VERSION,
             default_scan_directory: None,
             amount_recognition: AmountRecognitionConfig::default(),
             file_filter: FileFilterConfig::default(),
             rename_strategy: RenameStrategyConfig::default(),
             ui_config: UiConfig::default(),
+            pdf_split: PdfSplitConfig::default(),
+            organize: OrganizeConfig::default(),
+            watch: WatchConfig::default(),
+            mail_ingest: MailIngestConfig::default(),
+            pdf_passwords: PdfPasswordConfig::default(),
+            company: CompanyConfig::default(),
+            categories: CategoryConfig::default(),
         }
     }
 }
@@ -117,8 +378,8 @@ impl Default for AmountRecognitionConfig {
             enable_chinese_digits: true,
             enable_arabic_digits: true,
             enable_currency_symbols: true,
-            min_amount: 0.01,
-            max_amount: 1000000.0,
+            min_amount: Amount::from_cents(1),
+            max_amount: Amount::from_units(1_000_000),
         }
     }
 }
@@ -149,6 +410,92 @@ impl Default for RenameStrategyConfig {
     }
 }
 
+impl Default for PdfSplitConfig {
+    fn default() -> Self {
+        PdfSplitConfig {
+            source_handling: SourceHandling::Archive,
+            archive_directory: "已拆分原件".to_string(),
+        }
+    }
+}
+
+impl Default for OrganizeConfig {
+    fn default() -> Self {
+        OrganizeConfig {
+            archive_root: None,
+            directory_template: "{年}/{月}/{销售方}/".to_string(),
+        }
+    }
+}
+
+impl Default for WatchConfig {
+    fn default() -> Self {
+        WatchConfig {
+            folders: Vec::new(),
+            debounce_ms: 2000,
+        }
+    }
+}
+
+impl Default for ImapConfig {
+    fn default() -> Self {
+        ImapConfig {
+            host: String::new(),
+            port: 993,
+            use_tls: true,
+            username: String::new(),
+            password: String::new(),
+            mailbox: "INBOX".to_string(),
+            since_days: 90,
+        }
+    }
+}
+
+impl Default for MailIngestConfig {
+    fn default() -> Self {
+        MailIngestConfig {
+            staging_directory: None,
+            sources: Vec::new(),
+            link_domains: vec![
+                "chinatax.gov.cn".to_string(),
+                "nuonuo.com".to_string(),
+                "jss.com.cn".to_string(),
+                "baiwang.com".to_string(),
+                "51fapiao.cn".to_string(),
+                "fapiao.com".to_string(),
+            ],
+        }
+    }
+}
+
+impl Default for CategoryConfig {
+    fn default() -> Self {
+        let rule = |category: &str, seller_keywords: &[&str], item_keywords: &[&str], document_types: &[DocumentType]| CategoryRule {
+            category: category.to_string(),
+            seller_keywords: seller_keywords.iter().map(|s| s.to_string()).collect(),
+            item_keywords: item_keywords.iter().map(|s| s.to_string()).collect(),
+            document_types: document_types.to_vec(),
+            min_amount: None,
+            max_amount: None,
+        };
+        CategoryConfig {
+            categories: ["差旅-交通", "差旅-住宿", "餐饮", "办公用品", "通讯"]
+                .iter().map(|s| s.to_string()).collect(),
+            rules: vec![
+                rule("差旅-交通", &[], &[], &[DocumentType::RailwayTicket, DocumentType::FlightItinerary, DocumentType::TaxiReceipt]),
+                rule("差旅-住宿", &[], &[], &[DocumentType::HotelFolio]),
+                rule("差旅-交通", &[], &["运输服务", "客运", "通行费"], &[]),
+                rule("差旅-住宿", &[], &["住宿"], &[]),
+                rule("餐饮", &[], &["餐饮", "餐费"], &[]),
+                rule("餐饮", &["餐饮", "餐厅", "饭店", "咖啡"], &[], &[]),
+                rule("通讯", &[], &["电信服务", "通信", "话费", "宽带"], &[]),
+                rule("通讯", &["移动通信", "联通", "电信"], &[], &[]),
+                rule("办公用品", &[], &["办公用品", "文具", "纸制品", "耗材"], &[]),
+            ],
+        }
+    }
+}
+
 impl Default for UiConfig {
     fn default() -> Self {
         UiConfig {
@@ -160,10 +507,34 @@ impl Default for UiConfig {
     }
 }
 
+/// 配置迁移步骤，MIGRATIONS[n] 把版本 n 的配置升级到版本 n + 1
+const MIGRATIONS: [fn(&mut Map<String, Value>); CONFIG_SCHEMA_VERSION as usize] = [
+    migrate_validation_to_company,
+];
+
+/// 0 → 1：早期的 validation.buyer_tax_id 改为公司抬头
+///
+/// 旧配置只有纳税人识别号没有公司名称，迁移后的抬头直接启用，名称为空时只核对纳税人识别号
+fn migrate_validation_to_company(config: &mut Map<String, Value>) {
+    let Some(validation) = config.remove("validation") else {
+        return;
+    };
+    let Some(tax_id) = validation.get("buyer_tax_id").and_then(Value::as_str).filter(|id| !id.trim().is_empty()) else {
+        return;
+    };
+    if !config.contains_key("company") {
+        config.insert("company".to_string(), json!({
+            "profiles": [{ "id": tax_id, "name": "", "tax_id": tax_id }],
+            "active_profile": tax_id,
+        }));
+    }
+}
+
 /// 配置管理器
 pub struct ConfigManager {
     config: AppConfig,
-    config_path: PathBuf,
+    /// 配置文件路径，无法获取配置目录时为 None（只在内存中使用默认配置）
+    config_path: Option<PathBuf>,
 }
 
 impl ConfigManager {
@@ -174,45 +545,113 @@ impl ConfigManager {
         
         Ok(ConfigManager {
             config,
-            config_path,
+            config_path: Some(config_path),
         })
     }
 
-    /// 获取配置文件路径
-    fn get_config_path() -> AppResult<PathBuf> {
+    /// 无法获取配置目录时使用默认配置启动，修改不会保存
+    pub fn unsaved_default() -> Self {
+        ConfigManager {
+            config: AppConfig::default(),
+            config_path: None,
+        }
+    }
+
+    /// 获取应用配置目录（不存在时自动创建）
+    pub fn get_config_dir() -> AppResult<PathBuf> {
         if let Some(config_dir) = dirs::config_dir() {
             let app_config_dir = config_dir.join("InvoicePilot");
             std::fs::create_dir_all(&app_config_dir)?;
-            Ok(app_config_dir.join("config.json"))
+            Ok(app_config_dir)
         } else {
-            Err(AppError::file_system_error("无法获取配置目录"))
+            Err(AppError::file_system_error(tr("无法获取配置目录", "Unable to locate the config directory")))
         }
     }
 
+    /// 获取配置文件路径
+    fn get_config_path() -> AppResult<PathBuf> {
+        Ok(Self::get_config_dir()?.join("config.json"))
+    }
+
     /// 加载配置
-    fn load_config(config_path: &PathBuf) -> AppResult<AppConfig> {
-        if config_path.exists() {
-            let content = std::fs::read_to_string(config_path)?;
-            let config: AppConfig = serde_json::from_str(&content)
-                .map_err(|e| AppError::validation_error(&format!("配置文件格式错误: {}", e)))?;
-            Ok(config)
-        } else {
-            // 如果配置文件不存在，使用默认配置
+    ///
+    /// 旧版本的配置迁移到当前版本后写回（原文件另存备份）；
+    /// 无法解析的配置文件改名备份，使用默认配置继续运行
+    fn load_config(config_path: &Path) -> AppResult<AppConfig> {
+        if !config_path.exists() {
+            // 如果配置文件不存在，使用默认配置并保存到文件
             let default_config = AppConfig::default();
-            // 保存默认配置到文件
-            let content = serde_json::to_string_pretty(&default_config)
-                .map_err(|e| AppError::validation_error(&format!("序列化配置失败: {}", e)))?;
-            std::fs::write(config_path, content)?;
-            Ok(default_config)
+            Self::write_config(config_path, &default_config)?;
+            return Ok(default_config);
+        }
+
+        let content = std::fs::read_to_string(config_path)?;
+        match Self::parse_config(&content) {
+            Ok((config, version)) => {
+                if version < CONFIG_SCHEMA_VERSION {
+                    let backup_path = Self::backup_path(config_path, &format!("v{}", version));
+                    std::fs::copy(config_path, &backup_path)?;
+                    Self::write_config(config_path, &config)?;
+                    logger::info("配置已迁移", &[
+                        ("from_version", &version),
+                        ("to_version", &CONFIG_SCHEMA_VERSION),
+                        ("backup", &backup_path.display()),
+                    ]);
+                } else if version > CONFIG_SCHEMA_VERSION {
+                    logger::warn("配置文件来自更新的版本，未知字段将被忽略", &[("version", &version)]);
+                }
+                Ok(config)
+            }
+            Err(e) => {
+                let backup_path = Self::backup_path(config_path, "invalid");
+                std::fs::rename(config_path, &backup_path)?;
+                logger::error("配置文件无法解析，已备份并恢复默认配置", &[("error", &e), ("backup", &backup_path.display())]);
+                let default_config = AppConfig::default();
+                Self::write_config(config_path, &default_config)?;
+                Ok(default_config)
+            }
+        }
+    }
+
+    /// 解析配置文件并依次执行迁移，返回配置和文件中的版本号
+    fn parse_config(content: &str) -> AppResult<(AppConfig, u32)> {
+        let invalid = |e: &dyn std::fmt::Display| {
+            AppError::validation_error(&format!("{}: {}", tr("配置文件格式错误", "Invalid config file format"), e))
+        };
+        let mut value: Value = serde_json::from_str(content).map_err(|e| invalid(&e))?;
+        let config = value.as_object_mut().ok_or_else(|| invalid(&"not an object"))?;
+        let version = config.get("schema_version")
+            .and_then(Value::as_u64)
+            .map_or(0, |version| version.min(u32::MAX as u64) as u32);
+        if version < CONFIG_SCHEMA_VERSION {
+            for migrate in &MIGRATIONS[version as usize..] {
+                migrate(config);
+            }
+            config.insert("schema_version".to_string(), json!(CONFIG_SCHEMA_VERSION));
         }
+        let config = serde_json::from_value(value).map_err(|e| invalid(&e))?;
+        Ok((config, version))
+    }
+
+    /// 备份文件路径，如 config.v0.20261018120000.json
+    pub(crate) fn backup_path(config_path: &Path, label: &str) -> PathBuf {
+        let stem = config_path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_else(|| "config".to_string());
+        config_path.with_file_name(format!("{}.{}.{}.json", stem, label, Utc::now().format("%Y%m%d%H%M%S")))
+    }
+
+    fn write_config(config_path: &Path, config: &AppConfig) -> AppResult<()> {
+        let content = serde_json::to_string_pretty(config)
+            .map_err(|e| AppError::validation_error(&format!("{}: {}", tr("序列化配置失败", "Failed to serialize config"), e)))?;
+        std::fs::write(config_path, content)?;
+        Ok(())
     }
 
     /// 保存配置
     pub fn save_config(&self) -> AppResult<()> {
-        let content = serde_json::to_string_pretty(&self.config)
-            .map_err(|e| AppError::validation_error(&format!("序列化配置失败: {}", e)))?;
-        std::fs::write(&self.config_path, content)?;
-        Ok(())
+        match &self.config_path {
+            Some(config_path) => Self::write_config(config_path, &self.config),
+            None => Err(AppError::file_system_error(tr("无法获取配置目录", "Unable to locate the config directory"))),
+        }
     }
 
     /// 获取当前配置
@@ -222,7 +661,10 @@ impl ConfigManager {
 
     /// 更新配置
     pub fn update_config(&mut self, new_config: AppConfig) -> AppResult<()> {
-        self.config = new_config;
+        self.config = AppConfig {
+            schema_version: CONFIG_SCHEMA_VERSION,
+            ..new_config
+        };
         self.save_config()
     }
 
@@ -238,27 +680,27 @@ impl ConfigManager {
         let config = &self.config;
         
         // 验证金额范围
-        if config.amount_recognition.min_amount < 0.0 {
-            return Err(AppError::validation_error("最小金额不能为负数"));
+        if config.amount_recognition.min_amount < Amount::ZERO {
+            return Err(AppError::validation_error(tr("最小金额不能为负数", "Minimum amount cannot be negative")));
         }
         
         if config.amount_recognition.max_amount <= config.amount_recognition.min_amount {
-            return Err(AppError::validation_error("最大金额必须大于最小金额"));
+            return Err(AppError::validation_error(tr("最大金额必须大于最小金额", "Maximum amount must be greater than the minimum amount")));
         }
 
         // 验证文件大小限制
         if config.file_filter.max_file_size == 0 {
-            return Err(AppError::validation_error("最大文件大小必须大于0"));
+            return Err(AppError::validation_error(tr("最大文件大小必须大于0", "Maximum file size must be greater than 0")));
         }
 
         // 验证支持的文件扩展名
         if config.file_filter.supported_extensions.is_empty() {
-            return Err(AppError::validation_error("必须指定至少一个支持的文件扩展名"));
+            return Err(AppError::validation_error(tr("必须指定至少一个支持的文件扩展名", "At least one supported file extension is required")));
         }
 
         // 验证文件名模板
         if config.rename_strategy.filename_template.is_empty() {
-            return Err(AppError::validation_error("文件名模板不能为空"));
+            return Err(AppError::validation_error(tr("文件名模板不能为空", "File name template cannot be empty")));
         }
 
         Ok(())
@@ -304,4 +746,45 @@ mod dirs {
     fn home_dir() -> Option<PathBuf> {
         std::env::var("HOME").ok().map(PathBuf::from)
     }
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_and_recover() {
+        let dir = std::env::temp_dir().join(format!("invoicepilot-config-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let config_path = dir.join("config.json");
+        let backups = || std::fs::read_dir(&dir).unwrap().count() - 1;
+
+        // 没有版本号、缺少后来新增字段的旧配置
+        std::fs::write(&config_path, r#"{
+            "default_scan_directory": "/tmp/invoices",
+            "ui_config": { "theme": "Dark", "language": "English" },
+            "validation": { "buyer_tax_id": "91110000MA01ABCDEF" }
+        }"#).unwrap();
+        let config = ConfigManager::load_config(&config_path).unwrap();
+        assert_eq!(config.schema_version, CONFIG_SCHEMA_VERSION);
+        assert_eq!(config.default_scan_directory.as_deref(), Some("/tmp/invoices"));
+        assert_eq!(config.ui_config.language, Language::English);
+        assert!(config.ui_config.auto_refresh);
+        assert_eq!(config.rename_strategy.filename_template, RenameStrategyConfig::default().filename_template);
+        assert_eq!(config.company.profiles[0].tax_id, "91110000MA01ABCDEF");
+        assert_eq!(config.company.active().map(|profile| profile.tax_id.as_str()), Some("91110000MA01ABCDEF"));
+        assert_eq!(backups(), 1);
+
+        // 已迁移的配置再次加载不再备份
+        ConfigManager::load_config(&config_path).unwrap();
+        assert_eq!(backups(), 1);
+
+        // 无法解析的配置备份后恢复默认配置
+        std::fs::write(&config_path, "{ not json").unwrap();
+        let config = ConfigManager::load_config(&config_path).unwrap();
+        assert!(config.default_scan_directory.is_none());
+        assert_eq!(backups(), 2);
+        assert!(ConfigManager::load_config(&config_path).is_ok());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
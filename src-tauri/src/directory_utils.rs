@@ -1,5 +1,7 @@
 use std::path::Path;
 use std::env;
+use crate::error::{AppError, AppResult};
+use crate::i18n::tr;
 
 /// 目录工具结构体
 pub struct DirectoryUtils;
@@ -17,7 +19,7 @@ impl DirectoryUtils {
     /// 2. 当前工作目录（如果不是根目录且不是编译目录）
     /// 3. 用户文档目录
     /// 4. 用户主目录
-    pub fn get_current_directory(&self) -> Result<String, String> {
+    pub fn get_current_directory(&self) -> AppResult<String> {
         // 首先尝试获取可执行文件所在的目录（处理 macOS 应用包）
         if let Some(exe_dir) = self.get_executable_directory() {
             return Ok(exe_dir);
@@ -131,13 +133,13 @@ impl DirectoryUtils {
     }
 
     /// 获取用户主目录
-    fn get_home_directory(&self) -> Result<String, String> {
+    fn get_home_directory(&self) -> AppResult<String> {
         env::var("HOME")
-            .map_err(|_| "无法获取用户主目录".to_string())
+            .map_err(|_| AppError::file_system_error(tr("无法获取用户主目录", "Unable to locate the home directory")))
             .or_else(|_| {
                 // 在 Windows 上尝试 USERPROFILE
                 env::var("USERPROFILE")
-                    .map_err(|_| "无法获取用户主目录".to_string())
+                    .map_err(|_| AppError::file_system_error(tr("无法获取用户主目录", "Unable to locate the home directory")))
             })
             .or_else(|_| {
                 // 最后的回退选项
@@ -0,0 +1,229 @@
+use serde::{Deserialize, Serialize};
+use regex::Regex;
+
+/// 票据类型
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DocumentType {
+    /// 增值税专用发票
+    #[serde(alias = "增值税专用发票")]
+    VatSpecialInvoice,
+    /// 增值税普通发票
+    #[serde(alias = "增值税普通发票")]
+    VatOrdinaryInvoice,
+    /// 全电发票（数电票）
+    #[serde(alias = "全电发票")]
+    FullyDigitalInvoice,
+    /// 铁路电子客票
+    #[serde(alias = "铁路电子客票")]
+    RailwayTicket,
+    /// 航空运输电子客票行程单
+    #[serde(alias = "机票行程单")]
+    FlightItinerary,
+    /// 出租车票
+    #[serde(alias = "出租车票")]
+    TaxiReceipt,
+    /// 酒店账单（水单）
+    #[serde(alias = "酒店账单")]
+    HotelFolio,
+    /// 银行回单
+    #[serde(alias = "银行回单")]
+    BankReceipt,
+    /// 无法识别
+    #[default]
+    #[serde(alias = "未知类型")]
+    Unknown,
+}
+
+impl DocumentType {
+    /// 所有已知类型（不含 Unknown）
+    pub const KNOWN: [DocumentType; 8] = [
+        DocumentType::VatSpecialInvoice,
+        DocumentType::VatOrdinaryInvoice,
+        DocumentType::FullyDigitalInvoice,
+        DocumentType::RailwayTicket,
+        DocumentType::FlightItinerary,
+        DocumentType::TaxiReceipt,
+        DocumentType::HotelFolio,
+        DocumentType::BankReceipt,
+    ];
+
+    /// 英文显示名称（英文界面下命名模板中的 {类型}）
+    pub fn english_name(&self) -> &'static str {
+        match self {
+            DocumentType::VatSpecialInvoice => "VAT Special Invoice",
+            DocumentType::VatOrdinaryInvoice => "VAT Invoice",
+            DocumentType::FullyDigitalInvoice => "Digital Invoice",
+            DocumentType::RailwayTicket => "Railway Ticket",
+            DocumentType::FlightItinerary => "Flight Itinerary",
+            DocumentType::TaxiReceipt => "Taxi Receipt",
+            DocumentType::HotelFolio => "Hotel Folio",
+            DocumentType::BankReceipt => "Bank Receipt",
+            DocumentType::Unknown => "Unknown Type",
+        }
+    }
+
+    /// 中文显示名称（用于命名模板中的 {类型}）
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            DocumentType::VatSpecialInvoice => "增值税专用发票",
+            DocumentType::VatOrdinaryInvoice => "增值税普通发票",
+            DocumentType::FullyDigitalInvoice => "全电发票",
+            DocumentType::RailwayTicket => "铁路电子客票",
+            DocumentType::FlightItinerary => "机票行程单",
+            DocumentType::TaxiReceipt => "出租车票",
+            DocumentType::HotelFolio => "酒店账单",
+            DocumentType::BankReceipt => "银行回单",
+            DocumentType::Unknown => "未知类型",
+        }
+    }
+}
+
+/// 分类线索：关键词或版面特征及其权重
+struct Cue {
+    document_type: DocumentType,
+    regex: Regex,
+    weight: u32,
+}
+
+/// 关键词线索（出现即计分）
+const KEYWORD_CUES: &[(DocumentType, &str, u32)] = &[
+    (DocumentType::VatSpecialInvoice, "增值税专用发票", 6),
+    (DocumentType::VatOrdinaryInvoice, "增值税普通发票", 6),
+    (DocumentType::VatOrdinaryInvoice, "增值税电子普通发票", 6),
+    (DocumentType::FullyDigitalInvoice, "电子发票（增值税专用发票）", 8),
+    (DocumentType::FullyDigitalInvoice, "电子发票（普通发票）", 8),
+    (DocumentType::FullyDigitalInvoice, "电子发票(增值税专用发票)", 8),
+    (DocumentType::FullyDigitalInvoice, "电子发票(普通发票)", 8),
+    (DocumentType::RailwayTicket, "铁路电子客票", 10),
+    (DocumentType::RailwayTicket, "电子客票号", 4),
+    (DocumentType::RailwayTicket, "检票", 2),
+    (DocumentType::FlightItinerary, "航空运输电子客票行程单", 10),
+    (DocumentType::FlightItinerary, "行程单", 4),
+    (DocumentType::FlightItinerary, "民航发展基金", 4),
+    (DocumentType::FlightItinerary, "燃油附加费", 3),
+    (DocumentType::FlightItinerary, "航班", 2),
+    (DocumentType::TaxiReceipt, "出租汽车", 6),
+    (DocumentType::TaxiReceipt, "出租车", 5),
+    (DocumentType::TaxiReceipt, "上车", 2),
+    (DocumentType::TaxiReceipt, "下车", 2),
+    (DocumentType::TaxiReceipt, "里程", 2),
+    (DocumentType::HotelFolio, "入住", 3),
+    (DocumentType::HotelFolio, "离店", 3),
+    (DocumentType::HotelFolio, "房号", 3),
+    (DocumentType::HotelFolio, "Folio", 4),
+    (DocumentType::HotelFolio, "账单", 1),
+    (DocumentType::HotelFolio, "酒店", 2),
+    (DocumentType::BankReceipt, "电子回单", 8),
+    (DocumentType::BankReceipt, "回单", 4),
+    (DocumentType::BankReceipt, "交易流水号", 3),
+    (DocumentType::BankReceipt, "付款人", 2),
+    (DocumentType::BankReceipt, "收款人", 2),
+];
+
+/// 版面线索（正则匹配即计分）
+const LAYOUT_CUES: &[(DocumentType, &str, u32)] = &[
+    // 全电发票：20位发票号码
+    (DocumentType::FullyDigitalInvoice, r"发票号码[：:]?\s*\d{20}\b", 6),
+    // 传统增值税发票：10位或12位发票代码 + 8位发票号码
+    (DocumentType::VatOrdinaryInvoice, r"发票代码[：:]?\s*\d{10,12}\b", 1),
+    (DocumentType::VatSpecialInvoice, r"发票代码[：:]?\s*\d{10,12}\b", 1),
+    // 车次 + 发站/到站
+    (DocumentType::RailwayTicket, r"\b[GDCZTKYL]\d{1,4}\b.*站", 3),
+    // 航班号 + 舱位
+    (DocumentType::FlightItinerary, r"\b(?:[A-Z]{2}|[A-Z]\d|\d[A-Z])\d{3,4}\b", 2),
+    // 上下车时间
+    (DocumentType::TaxiReceipt, r"\d{1,2}:\d{2}\s*[-—~至]\s*\d{1,2}:\d{2}", 2),
+    // 入住/离店日期
+    (DocumentType::HotelFolio, r"(?i)check[\s-]?(?:in|out)", 3),
+    // 银行账号
+    (DocumentType::BankReceipt, r"账号[：:]?\s*\d{12,19}", 2),
+];
+
+/// 判定为已知类型所需的最低得分
+const MIN_SCORE: u32 = 4;
+
+/// 票据类型分类器
+pub struct DocumentClassifier {
+    cues: Vec<Cue>,
+}
+
+impl DocumentClassifier {
+    pub fn new() -> Self {
+        let keyword_cues = KEYWORD_CUES.iter().filter_map(|(document_type, keyword, weight)| {
+            Regex::new(&regex::escape(keyword)).ok().map(|regex| Cue {
+                document_type: *document_type,
+                regex,
+                weight: *weight,
+            })
+        });
+        let layout_cues = LAYOUT_CUES.iter().filter_map(|(document_type, pattern, weight)| {
+            Regex::new(pattern).ok().map(|regex| Cue {
+                document_type: *document_type,
+                regex,
+                weight: *weight,
+            })
+        });
+
+        DocumentClassifier {
+            cues: keyword_cues.chain(layout_cues).collect(),
+        }
+    }
+
+    /// 计算各类型的得分
+    pub fn score(&self, text: &str) -> Vec<(DocumentType, u32)> {
+        DocumentType::KNOWN.iter()
+            .map(|document_type| {
+                let score = self.cues.iter()
+                    .filter(|cue| cue.document_type == *document_type)
+                    .filter(|cue| cue.regex.is_match(text))
+                    .map(|cue| cue.weight)
+                    .sum();
+                (*document_type, score)
+            })
+            .collect()
+    }
+
+    /// 对文本进行分类，得分最高且达到阈值的类型胜出
+    pub fn classify(&self, text: &str) -> DocumentType {
+        let scores = self.score(text);
+
+        // 得分相同时取 KNOWN 中靠前的类型
+        let best = scores.iter()
+            .fold(None, |best: Option<(DocumentType, u32)>, &(document_type, score)| {
+                match best {
+                    Some((_, best_score)) if best_score >= score => best,
+                    _ => Some((document_type, score)),
+                }
+            });
+
+        match best {
+            Some((document_type, score)) if score >= MIN_SCORE => document_type,
+            _ => DocumentType::Unknown,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_fully_digital_invoice() {
+        let classifier = DocumentClassifier::new();
+        let text = "电子发票（增值税专用发票）\n发票号码：24310000000012345678\n开票日期：2024年03月01日";
+        assert_eq!(classifier.classify(text), DocumentType::FullyDigitalInvoice);
+    }
+
+    #[test]
+    fn test_classify_railway_ticket() {
+        let classifier = DocumentClassifier::new();
+        let text = "电子发票（铁路电子客票）\n发票号码：24119110000000000001\n北京南站 G101 上海虹桥站\n票价:￥553.00";
+        assert_eq!(classifier.classify(text), DocumentType::RailwayTicket);
+    }
+
+    #[test]
+    fn test_classify_unknown() {
+        let classifier = DocumentClassifier::new();
+        assert_eq!(classifier.classify("会议纪要\n第一项议程"), DocumentType::Unknown);
+    }
+}
@@ -1,11 +1,18 @@
 use std::fmt;
-use serde::{Deserialize, Serialize};
+use std::path::Path;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use crate::i18n::tr;
 
 /// 应用程序错误类型
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// 序列化为 `{ code, message, user_message, path, retryable }`，供前端按错误代码本地化提示和提供重试；
+/// 消息按当前界面语言生成
+#[derive(Debug, Clone)]
 pub enum AppError {
     /// PDF解析错误
     PdfParseError(String),
+    /// PDF需要打开密码（附文件路径）
+    PasswordRequired(String),
     /// 金额提取错误
     AmountExtractionError(String),
     /// 文件系统错误
@@ -20,25 +27,42 @@ pub enum AppError {
     ValidationError(String),
     /// 未知错误
     UnknownError(String),
+    /// 与某个文件或目录相关的错误
+    AtPath { path: String, source: Box<AppError> },
 }
 
 impl fmt::Display for AppError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            AppError::PdfParseError(msg) => write!(f, "PDF解析错误: {}", msg),
-            AppError::AmountExtractionError(msg) => write!(f, "金额提取错误: {}", msg),
-            AppError::FileSystemError(msg) => write!(f, "文件系统错误: {}", msg),
-            AppError::NamingError(msg) => write!(f, "命名错误: {}", msg),
-            AppError::PermissionError(msg) => write!(f, "权限错误: {}", msg),
-            AppError::IoError(msg) => write!(f, "IO错误: {}", msg),
-            AppError::ValidationError(msg) => write!(f, "验证错误: {}", msg),
-            AppError::UnknownError(msg) => write!(f, "未知错误: {}", msg),
+            AppError::PdfParseError(msg) => write!(f, "{}: {}", tr("PDF解析错误", "PDF parse error"), msg),
+            AppError::PasswordRequired(path) => write!(f, "{}: {}", tr("PDF需要打开密码", "PDF requires an open password"), path),
+            AppError::AmountExtractionError(msg) => write!(f, "{}: {}", tr("金额提取错误", "Amount extraction error"), msg),
+            AppError::FileSystemError(msg) => write!(f, "{}: {}", tr("文件系统错误", "File system error"), msg),
+            AppError::NamingError(msg) => write!(f, "{}: {}", tr("命名错误", "Naming error"), msg),
+            AppError::PermissionError(msg) => write!(f, "{}: {}", tr("权限错误", "Permission error"), msg),
+            AppError::IoError(msg) => write!(f, "{}: {}", tr("IO错误", "IO error"), msg),
+            AppError::ValidationError(msg) => write!(f, "{}: {}", tr("验证错误", "Validation error"), msg),
+            AppError::UnknownError(msg) => write!(f, "{}: {}", tr("未知错误", "Unknown error"), msg),
+            // 路径单独序列化，消息中不重复
+            AppError::AtPath { source, .. } => source.fmt(f),
         }
     }
 }
 
 impl std::error::Error for AppError {}
 
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("AppError", 5)?;
+        state.serialize_field("code", self.error_code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field("user_message", &self.user_message())?;
+        state.serialize_field("path", &self.path())?;
+        state.serialize_field("retryable", &self.is_retryable())?;
+        state.end()
+    }
+}
+
 /// 应用程序结果类型
 pub type AppResult<T> = Result<T, AppError>;
 
@@ -49,6 +73,11 @@ impl AppError {
         AppError::PdfParseError(msg.to_string())
     }
 
+    /// 创建需要打开密码的错误
+    pub fn password_required(path: &str) -> Self {
+        AppError::PasswordRequired(path.to_string())
+    }
+
     /// 创建金额提取错误
     #[allow(dead_code)]
     pub fn amount_extraction_error(msg: &str) -> Self {
@@ -86,11 +115,50 @@ impl AppError {
         AppError::UnknownError(msg.to_string())
     }
 
+    /// 文件操作的IO错误，按错误种类归类并附带路径，如 `io_error_at(e, "重命名失败", path)`
+    pub fn io_error_at(err: std::io::Error, context: &str, path: &Path) -> Self {
+        let msg = format!("{}: {}", context, err);
+        let error = match err.kind() {
+            std::io::ErrorKind::PermissionDenied => AppError::permission_error(&msg),
+            std::io::ErrorKind::NotFound | std::io::ErrorKind::AlreadyExists => AppError::file_system_error(&msg),
+            _ => AppError::io_error(&msg),
+        };
+        error.with_path(path)
+    }
+
+    /// 附带相关的文件或目录，已有路径时保持不变
+    pub fn with_path(self, path: &Path) -> Self {
+        if self.path().is_some() {
+            return self;
+        }
+        AppError::AtPath {
+            path: path.to_string_lossy().to_string(),
+            source: Box::new(self),
+        }
+    }
+
+    /// 相关的文件或目录
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            AppError::AtPath { path, .. } | AppError::PasswordRequired(path) => Some(path),
+            _ => None,
+        }
+    }
+
+    /// 去掉路径后的错误，用于按错误种类判断
+    pub fn kind(&self) -> &AppError {
+        match self {
+            AppError::AtPath { source, .. } => source.kind(),
+            error => error,
+        }
+    }
+
     /// 获取错误代码
-    #[allow(dead_code)]
     pub fn error_code(&self) -> &'static str {
         match self {
+            AppError::AtPath { source, .. } => source.error_code(),
             AppError::PdfParseError(_) => "PDF_PARSE_ERROR",
+            AppError::PasswordRequired(_) => "PDF_PASSWORD_REQUIRED",
             AppError::AmountExtractionError(_) => "AMOUNT_EXTRACTION_ERROR",
             AppError::FileSystemError(_) => "FILE_SYSTEM_ERROR",
             AppError::NamingError(_) => "NAMING_ERROR",
@@ -102,25 +170,27 @@ impl AppError {
     }
 
     /// 获取用户友好的错误消息
-    #[allow(dead_code)]
     pub fn user_message(&self) -> String {
         match self {
-            AppError::PdfParseError(_) => "无法解析PDF文件，请检查文件是否损坏".to_string(),
-            AppError::AmountExtractionError(_) => "无法从PDF中提取金额信息".to_string(),
-            AppError::FileSystemError(_) => "文件操作失败，请检查文件权限".to_string(),
-            AppError::NamingError(_) => "文件命名失败，请检查文件名格式".to_string(),
-            AppError::PermissionError(_) => "没有足够的权限执行此操作".to_string(),
-            AppError::IoError(_) => "输入输出操作失败".to_string(),
-            AppError::ValidationError(_) => "数据验证失败".to_string(),
-            AppError::UnknownError(_) => "发生未知错误".to_string(),
+            AppError::AtPath { source, .. } => source.user_message(),
+            AppError::PdfParseError(_) => tr("无法解析PDF文件，请检查文件是否损坏", "Unable to parse the PDF file, please check whether it is damaged").to_string(),
+            AppError::PasswordRequired(_) => tr("PDF已加密，请输入打开密码", "The PDF is encrypted, please enter its open password").to_string(),
+            AppError::AmountExtractionError(_) => tr("无法从PDF中提取金额信息", "Unable to extract the amount from the PDF").to_string(),
+            AppError::FileSystemError(_) => tr("文件操作失败，请检查文件权限", "File operation failed, please check file permissions").to_string(),
+            AppError::NamingError(_) => tr("文件命名失败，请检查文件名格式", "File naming failed, please check the file name format").to_string(),
+            AppError::PermissionError(_) => tr("没有足够的权限执行此操作", "Insufficient permission for this operation").to_string(),
+            AppError::IoError(_) => tr("输入输出操作失败", "Input/output operation failed").to_string(),
+            AppError::ValidationError(_) => tr("数据验证失败", "Data validation failed").to_string(),
+            AppError::UnknownError(_) => tr("发生未知错误", "An unknown error occurred").to_string(),
         }
     }
 
     /// 是否可以重试
-    #[allow(dead_code)]
     pub fn is_retryable(&self) -> bool {
         match self {
+            AppError::AtPath { source, .. } => source.is_retryable(),
             AppError::PdfParseError(_) => false,
+            AppError::PasswordRequired(_) => true,
             AppError::AmountExtractionError(_) => false,
             AppError::FileSystemError(_) => true,
             AppError::NamingError(_) => true,
@@ -137,15 +207,15 @@ impl From<std::io::Error> for AppError {
     fn from(err: std::io::Error) -> Self {
         match err.kind() {
             std::io::ErrorKind::PermissionDenied => {
-                AppError::permission_error(&format!("权限不足: {}", err))
+                AppError::permission_error(&format!("{}: {}", tr("权限不足", "Permission denied"), err))
             }
             std::io::ErrorKind::NotFound => {
-                AppError::file_system_error(&format!("文件不存在: {}", err))
+                AppError::file_system_error(&format!("{}: {}", tr("文件不存在", "File not found"), err))
             }
             std::io::ErrorKind::AlreadyExists => {
-                AppError::file_system_error(&format!("文件已存在: {}", err))
+                AppError::file_system_error(&format!("{}: {}", tr("文件已存在", "File already exists"), err))
             }
-            _ => AppError::io_error(&format!("IO错误: {}", err)),
+            _ => AppError::io_error(&format!("{}: {}", tr("IO错误", "IO error"), err)),
         }
     }
 }
@@ -153,14 +223,45 @@ impl From<std::io::Error> for AppError {
 /// 从PDF提取错误转换
 impl From<pdf_extract::OutputError> for AppError {
     fn from(err: pdf_extract::OutputError) -> Self {
-        AppError::pdf_parse_error(&format!("PDF解析错误: {}", err))
+        AppError::pdf_parse_error(&format!("{}: {}", tr("PDF解析错误", "PDF parse error"), err))
     }
 }
 
 /// 从正则表达式错误转换
 impl From<regex::Error> for AppError {
     fn from(err: regex::Error) -> Self {
-        AppError::validation_error(&format!("正则表达式错误: {}", err))
+        AppError::validation_error(&format!("{}: {}", tr("正则表达式错误", "Regular expression error"), err))
+    }
+}
+
+/// 从CSV读写错误转换
+impl From<csv::Error> for AppError {
+    fn from(err: csv::Error) -> Self {
+        AppError::io_error(&format!("{}: {}", tr("生成表格失败", "Failed to generate table"), err))
+    }
+}
+
+/// 从CSV缓冲区错误转换
+impl<W> From<csv::IntoInnerError<W>> for AppError {
+    fn from(err: csv::IntoInnerError<W>) -> Self {
+        AppError::io_error(&format!("{}: {}", tr("生成表格失败", "Failed to generate table"), err.error()))
+    }
+}
+
+/// 从Excel生成错误转换
+impl From<rust_xlsxwriter::XlsxError> for AppError {
+    fn from(err: rust_xlsxwriter::XlsxError) -> Self {
+        AppError::io_error(&format!("{}: {}", tr("生成表格失败", "Failed to generate spreadsheet"), err))
+    }
+}
+
+/// 从ZIP写入错误转换
+impl From<zip::result::ZipError> for AppError {
+    fn from(err: zip::result::ZipError) -> Self {
+        match err {
+            zip::result::ZipError::Io(err) => err.into(),
+            err => AppError::io_error(&format!("{}: {}", tr("写入压缩包失败", "Failed to write archive"), err)),
+        }
     }
 }
 
@@ -216,4 +317,26 @@ impl<T> ErrorContext<T> for Result<T, AppError> {
     }
 }
 
- 
\ No newline at end of file
+ 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serialize_with_path() {
+        let io_error = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "denied");
+        let error = AppError::io_error_at(io_error, "重命名失败", Path::new("/tmp/a.pdf"));
+        assert!(matches!(error.kind(), AppError::PermissionError(_)));
+
+        let value = serde_json::to_value(&error).unwrap();
+        assert_eq!(value["code"], "PERMISSION_ERROR");
+        assert_eq!(value["path"], "/tmp/a.pdf");
+        assert_eq!(value["retryable"], false);
+        assert_eq!(value["message"], "权限错误: 重命名失败: denied");
+
+        // 已有路径时不再包一层
+        let error = AppError::password_required("/tmp/b.pdf").with_path(Path::new("/tmp/c.pdf"));
+        assert_eq!(error.path(), Some("/tmp/b.pdf"));
+        assert!(error.is_retryable());
+    }
+}
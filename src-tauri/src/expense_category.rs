@@ -0,0 +1,126 @@
+use serde::{Deserialize, Serialize};
+use crate::config::CategoryConfig;
+use crate::document_classifier::DocumentType;
+use crate::money::Amount;
+
+/// 没有规则命中时 {类别} 的替代文本
+pub const UNCATEGORIZED: &str = "未分类";
+
+/// 英文界面下的 UNCATEGORIZED
+pub const UNCATEGORIZED_EN: &str = "Uncategorized";
+
+/// 费用类别规则，已填写的条件全部满足时命中
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CategoryRule {
+    pub category: String,
+    /// 销售方名称包含其中任一关键词
+    #[serde(default)]
+    pub seller_keywords: Vec<String>,
+    /// 发票明细的项目名称包含其中任一关键词
+    #[serde(default)]
+    pub item_keywords: Vec<String>,
+    /// 票据类型为其中之一
+    #[serde(default)]
+    pub document_types: Vec<DocumentType>,
+    #[serde(default)]
+    pub min_amount: Option<Amount>,
+    #[serde(default)]
+    pub max_amount: Option<Amount>,
+}
+
+impl CategoryRule {
+    fn matches(&self, seller: Option<&str>, item_names: &[String], document_type: DocumentType, amount: Option<Amount>) -> bool {
+        let contains_any = |text: &str, keywords: &[String]| keywords.iter().any(|keyword| text.contains(keyword.as_str()));
+
+        // 没有任何条件的规则不命中，避免把所有发票归为一类
+        if self.seller_keywords.is_empty() && self.item_keywords.is_empty() && self.document_types.is_empty()
+            && self.min_amount.is_none() && self.max_amount.is_none()
+        {
+            return false;
+        }
+        if !self.seller_keywords.is_empty() && !seller.is_some_and(|seller| contains_any(seller, &self.seller_keywords)) {
+            return false;
+        }
+        if !self.item_keywords.is_empty() && !item_names.iter().any(|item| contains_any(item, &self.item_keywords)) {
+            return false;
+        }
+        if !self.document_types.is_empty() && !self.document_types.contains(&document_type) {
+            return false;
+        }
+        if self.min_amount.is_some_and(|min| amount.is_none_or(|amount| amount < min)) {
+            return false;
+        }
+        if self.max_amount.is_some_and(|max| amount.is_none_or(|amount| amount > max)) {
+            return false;
+        }
+        true
+    }
+}
+
+/// 费用类别识别器
+pub struct ExpenseCategorizer {
+    rules: Vec<CategoryRule>,
+}
+
+impl ExpenseCategorizer {
+    pub fn new() -> Self {
+        ExpenseCategorizer {
+            rules: CategoryConfig::default().rules,
+        }
+    }
+
+    /// 替换规则（配置加载或更新后调用）
+    pub fn set_rules(&mut self, rules: &[CategoryRule]) {
+        self.rules = rules.to_vec();
+    }
+
+    /// 按顺序匹配规则，返回第一个命中的类别
+    #[allow(dead_code)]
+    pub fn categorize(&self, seller: Option<&str>, item_names: &[String], document_type: DocumentType, amount: Option<Amount>) -> Option<String> {
+        self.rules.iter()
+            .find(|rule| rule.matches(seller, item_names, document_type, amount))
+            .map(|rule| rule.category.clone())
+    }
+
+    /// 列出所有命中的规则序号（从0开始）和类别，第一个即 `categorize` 的结果
+    pub fn matching_rules(&self, seller: Option<&str>, item_names: &[String], document_type: DocumentType, amount: Option<Amount>) -> Vec<(usize, String)> {
+        self.rules.iter()
+            .enumerate()
+            .filter(|(_, rule)| rule.matches(seller, item_names, document_type, amount))
+            .map(|(index, rule)| (index, rule.category.clone()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_categorize() {
+        let categorizer = ExpenseCategorizer::new();
+        let items = |names: &[&str]| names.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(categorizer.categorize(None, &[], DocumentType::RailwayTicket, None).as_deref(), Some("差旅-交通"));
+        assert_eq!(categorizer.categorize(None, &[], DocumentType::HotelFolio, None).as_deref(), Some("差旅-住宿"));
+        assert_eq!(
+            categorizer.categorize(Some("某某餐饮管理有限公司"), &items(&["*餐饮服务*餐费"]), DocumentType::VatOrdinaryInvoice, None).as_deref(),
+            Some("餐饮"),
+        );
+        assert_eq!(
+            categorizer.categorize(Some("中国移动通信集团上海有限公司"), &[], DocumentType::VatOrdinaryInvoice, None).as_deref(),
+            Some("通讯"),
+        );
+        assert_eq!(categorizer.categorize(Some("某某公司"), &[], DocumentType::Unknown, None), None);
+
+        let mut categorizer = ExpenseCategorizer::new();
+        categorizer.set_rules(&[CategoryRule {
+            category: "大额".to_string(),
+            min_amount: Some(Amount::from_units(1000)),
+            ..Default::default()
+        }]);
+        assert_eq!(categorizer.categorize(None, &[], DocumentType::Unknown, Some(Amount::from_units(1500))).as_deref(), Some("大额"));
+        assert_eq!(categorizer.categorize(None, &[], DocumentType::Unknown, Some(Amount::from_units(999))), None);
+        assert_eq!(categorizer.categorize(None, &[], DocumentType::Unknown, None), None);
+    }
+}
@@ -0,0 +1,653 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use regex::Regex;
+use crate::config::ConfigManager;
+use crate::document_classifier::DocumentType;
+use crate::money::{parse_amount_number, Amount, NUMBER_PATTERN};
+use crate::error::{AppError, AppResult};
+use crate::i18n::{tr, trf};
+use crate::logger;
+
+/// 规则文件当前支持的版本
+pub const RULES_FILE_VERSION: u32 = 1;
+
+/// 规则文件名（与 config.json 位于同一目录）
+const RULES_FILE_NAME: &str = "rules.json";
+
+/// 提取规则集合（对应 rules.json 的内容）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleSet {
+    /// 规则文件格式版本
+    pub version: u32,
+    /// 规则列表
+    pub rules: Vec<ExtractionRule>,
+}
+
+/// 单条提取规则
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtractionRule {
+    /// 规则唯一标识
+    pub id: String,
+    /// 规则名称
+    pub name: String,
+    /// 是否启用
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// 适用的票据类型；分类结果为其他已知类型时规则不生效
+    #[serde(default)]
+    pub document_type: Option<DocumentType>,
+    /// 关键词：文本中必须全部出现，规则才会生效
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    /// 锚点关键词：只在包含锚点的行及其后 `window` 行内匹配；为空时匹配全文
+    #[serde(default)]
+    pub anchor: Option<String>,
+    /// 锚点之后参与匹配的行数
+    #[serde(default)]
+    pub window: usize,
+    /// 正则表达式，可用 `{num}` 代表金额数字（支持千位分隔符）
+    pub pattern: String,
+    /// 优先级，数值越大越先尝试
+    #[serde(default)]
+    pub priority: i32,
+    /// 字段映射：字段名 -> 捕获组（组名或序号），`currency` 字段用于识别币种
+    pub fields: BTreeMap<String, String>,
+    /// 同一范围内有多个匹配时的取值方式
+    #[serde(default)]
+    pub pick: MatchPick,
+}
+
+/// 多个匹配时的取值方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum MatchPick {
+    /// 取第一个匹配
+    #[default]
+    First,
+    /// 取最后一个匹配
+    Last,
+    /// 取金额最大的匹配（按 amount 字段比较）
+    Max,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// 规则的一次匹配结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleMatch {
+    /// 命中的规则标识
+    pub rule_id: String,
+    /// 规则声明的票据类型
+    pub document_type: Option<DocumentType>,
+    /// 匹配所在行号（从1开始）
+    pub line: usize,
+    /// 匹配到的原文片段
+    pub matched_text: String,
+    /// 按字段映射提取出的字段值
+    pub fields: BTreeMap<String, String>,
+}
+
+/// 建议规则时金额前标签的最大字数
+const SUGGESTED_ANCHOR_CHARS: usize = 4;
+
+/// 建议规则的优先级，高于内置规则
+const SUGGESTED_RULE_PRIORITY: i32 = 95;
+
+/// 规则测试结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleTestResult {
+    /// 规则标识
+    pub rule_id: String,
+    /// 规则在该文本上是否生效（关键词全部出现）
+    pub applicable: bool,
+    /// 缺失的关键词
+    pub missing_keywords: Vec<String>,
+    /// 全部匹配（未经取值方式筛选）
+    pub matches: Vec<RuleMatch>,
+    /// 按取值方式最终选中的匹配
+    pub selected: Option<RuleMatch>,
+}
+
+/// 已编译的规则
+struct CompiledRule {
+    rule: ExtractionRule,
+    regex: Regex,
+}
+
+impl CompiledRule {
+    fn compile(rule: &ExtractionRule) -> AppResult<Self> {
+        let regex = Regex::new(&expand_pattern(&rule.pattern)).map_err(|e| {
+            AppError::validation_error(&trf("规则 {} 的正则表达式无效: {}", "Rule {} has an invalid regular expression: {}", &[&rule.id, &e]))
+        })?;
+
+        for (field, group) in &rule.fields {
+            let known = match group.parse::<usize>() {
+                Ok(index) => index < regex.captures_len(),
+                Err(_) => regex.capture_names().flatten().any(|name| name == group),
+            };
+            if !known {
+                return Err(AppError::validation_error(&trf(
+                    "规则 {} 的字段 {} 引用了不存在的捕获组 {}",
+                    "Rule {}: field {} refers to a missing capture group {}",
+                    &[&rule.id, field, group],
+                )));
+            }
+        }
+
+        Ok(CompiledRule {
+            rule: rule.clone(),
+            regex,
+        })
+    }
+
+    /// 规则是否适用于该票据类型（未识别的票据对所有规则开放）
+    fn accepts(&self, document_type: DocumentType) -> bool {
+        match self.rule.document_type {
+            Some(expected) => document_type == DocumentType::Unknown || expected == document_type,
+            None => true,
+        }
+    }
+
+    /// 返回缺失的关键词
+    fn missing_keywords(&self, text: &str) -> Vec<String> {
+        self.rule.keywords.iter()
+            .filter(|keyword| !text.contains(keyword.as_str()))
+            .cloned()
+            .collect()
+    }
+
+    /// 在文本中查找全部匹配
+    fn find_matches(&self, text: &str) -> Vec<RuleMatch> {
+        let lines: Vec<&str> = text.lines().collect();
+        let mut scopes: Vec<usize> = Vec::new();
+
+        match &self.rule.anchor {
+            Some(anchor) if !anchor.is_empty() => {
+                for (i, line) in lines.iter().enumerate() {
+                    if line.contains(anchor.as_str()) {
+                        let end = std::cmp::min(i + self.rule.window + 1, lines.len());
+                        scopes.extend(i..end);
+                    }
+                }
+                scopes.sort_unstable();
+                scopes.dedup();
+            }
+            _ => scopes.extend(0..lines.len()),
+        }
+
+        let mut matches = Vec::new();
+        for i in scopes {
+            for caps in self.regex.captures_iter(lines[i]) {
+                let mut fields = BTreeMap::new();
+                for (field, group) in &self.rule.fields {
+                    let value = match group.parse::<usize>() {
+                        Ok(index) => caps.get(index),
+                        Err(_) => caps.name(group),
+                    };
+                    if let Some(value) = value {
+                        fields.insert(field.clone(), value.as_str().trim().to_string());
+                    }
+                }
+
+                matches.push(RuleMatch {
+                    rule_id: self.rule.id.clone(),
+                    document_type: self.rule.document_type,
+                    line: i + 1,
+                    matched_text: caps.get(0).map(|m| m.as_str().to_string()).unwrap_or_default(),
+                    fields,
+                });
+            }
+        }
+
+        matches
+    }
+
+    /// 按取值方式从全部匹配中选出一个
+    fn select(&self, matches: &[RuleMatch]) -> Option<RuleMatch> {
+        match self.rule.pick {
+            MatchPick::First => matches.first().cloned(),
+            MatchPick::Last => matches.last().cloned(),
+            MatchPick::Max => matches.iter()
+                .filter_map(|m| {
+                    let amount = m.fields.get("amount").and_then(|v| parse_amount_number(v))?;
+                    Some((amount, m))
+                })
+                .max_by_key(|(amount, _)| *amount)
+                .map(|(_, m)| m.clone()),
+        }
+    }
+}
+
+/// 金额前面最近的中文标签，如 "（小写）¥" 中的 "小写"；大写金额不作为标签
+fn label_before(prefix: &str) -> Option<String> {
+    let trimmed = prefix.trim_end_matches(|c: char| c.is_whitespace() || "¥￥$:：()（）[]【】".contains(c));
+    let mut label: Vec<char> = trimmed.chars()
+        .rev()
+        .take_while(|c| ('\u{4e00}'..='\u{9fff}').contains(c))
+        .take(SUGGESTED_ANCHOR_CHARS)
+        .collect();
+    label.reverse();
+    if label.is_empty() || label.iter().any(|c| "圆元角分整".contains(*c)) {
+        None
+    } else {
+        Some(label.into_iter().collect())
+    }
+}
+
+/// 展开规则中的 `{num}` 占位符
+fn expand_pattern(pattern: &str) -> String {
+    pattern.replace("{num}", &format!("(?:{})", NUMBER_PATTERN))
+}
+
+/// 规则引擎：持有编译后的规则，按优先级执行
+pub struct RuleEngine {
+    rules: Vec<CompiledRule>,
+}
+
+impl RuleEngine {
+    /// 编译规则集，任何一条规则无效都会返回错误
+    pub fn compile(rule_set: &RuleSet) -> AppResult<Self> {
+        let mut rules = rule_set.rules.iter()
+            .filter(|rule| rule.enabled)
+            .map(CompiledRule::compile)
+            .collect::<AppResult<Vec<_>>>()?;
+
+        // 优先级高的规则先执行，同优先级保持文件中的顺序
+        rules.sort_by_key(|rule| std::cmp::Reverse(rule.rule.priority));
+
+        Ok(RuleEngine { rules })
+    }
+
+    /// 编译规则集，跳过无效的规则并记录日志
+    pub fn compile_valid(rule_set: &RuleSet) -> Self {
+        let mut rules: Vec<CompiledRule> = rule_set.rules.iter()
+            .filter(|rule| rule.enabled)
+            .filter_map(|rule| match CompiledRule::compile(rule) {
+                Ok(compiled) => Some(compiled),
+                Err(e) => {
+                    logger::warn("跳过无效的提取规则", &[("rule", &rule.id), ("error", &e)]);
+                    None
+                }
+            })
+            .collect();
+        rules.sort_by_key(|rule| std::cmp::Reverse(rule.rule.priority));
+
+        RuleEngine { rules }
+    }
+
+    /// 空规则引擎
+    pub fn empty() -> Self {
+        RuleEngine { rules: Vec::new() }
+    }
+
+    /// 依次执行规则，返回第一条命中且包含指定字段的匹配
+    pub fn extract_field(&self, text: &str, field: &str, document_type: DocumentType) -> Option<RuleMatch> {
+        self.rules.iter()
+            .filter(|rule| rule.rule.fields.contains_key(field))
+            .filter(|rule| rule.accepts(document_type))
+            .filter(|rule| rule.missing_keywords(text).is_empty())
+            .find_map(|rule| {
+                let matches: Vec<RuleMatch> = rule.find_matches(text)
+                    .into_iter()
+                    .filter(|m| m.fields.contains_key(field))
+                    .collect();
+                rule.select(&matches)
+            })
+    }
+
+    /// 根据人工修正的金额建议一条提取规则
+    ///
+    /// 在文本中找到与修正金额相同的数字，以其前面的中文标签（如"小写"）为锚点；
+    /// 指定销售方时以销售方名称为关键词，规则只对该销售方的发票生效。
+    /// 只返回在原文上能取到修正金额的规则。
+    pub fn suggest_amount_rule(text: &str, amount: Amount, seller: Option<&str>) -> Option<ExtractionRule> {
+        let number = Regex::new(NUMBER_PATTERN).ok()?;
+        // 销售方名称不在文本中时（如只能按位置识别）不作为关键词
+        let seller = seller.filter(|seller| text.contains(seller));
+        let keywords: Vec<String> = seller.map(|seller| vec![seller.to_string()]).unwrap_or_default();
+
+        for line in text.lines() {
+            for m in number.find_iter(line) {
+                if parse_amount_number(m.as_str()) != Some(amount) {
+                    continue;
+                }
+                let anchor = label_before(&line[..m.start()]);
+                let name = match (seller, &anchor) {
+                    (Some(seller), Some(anchor)) => trf("{}的金额在\"{}\"之后", "{} amount after \"{}\"", &[&seller, anchor]),
+                    (Some(seller), None) => trf("{}的金额", "{} amount", &[&seller]),
+                    (None, Some(anchor)) => trf("金额在\"{}\"之后", "Amount after \"{}\"", &[anchor]),
+                    (None, None) => tr("人工修正的金额", "Manually corrected amount").to_string(),
+                };
+
+                for pick in [MatchPick::First, MatchPick::Last, MatchPick::Max] {
+                    let rule = ExtractionRule {
+                        id: format!("correction-{}", chrono::Utc::now().format("%Y%m%d%H%M%S")),
+                        name: name.clone(),
+                        enabled: true,
+                        document_type: None,
+                        keywords: keywords.clone(),
+                        anchor: anchor.clone(),
+                        window: 0,
+                        pattern: format!(r"[¥￥]?\s*{}", AMOUNT_GROUP),
+                        priority: SUGGESTED_RULE_PRIORITY,
+                        fields: amount_fields(),
+                        pick,
+                    };
+                    let Ok(compiled) = CompiledRule::compile(&rule) else {
+                        continue;
+                    };
+                    let selected = compiled.select(&compiled.find_matches(text))
+                        .and_then(|m| m.fields.get("amount").and_then(|value| parse_amount_number(value)));
+                    if selected == Some(amount) {
+                        return Some(rule);
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    /// 针对一段文本测试单条规则（不要求规则已在引擎中）
+    pub fn test_rule(rule: &ExtractionRule, text: &str) -> AppResult<RuleTestResult> {
+        let compiled = CompiledRule::compile(rule)?;
+        let missing_keywords = compiled.missing_keywords(text);
+        let matches = compiled.find_matches(text);
+        let selected = compiled.select(&matches);
+
+        Ok(RuleTestResult {
+            rule_id: rule.id.clone(),
+            applicable: missing_keywords.is_empty(),
+            missing_keywords,
+            matches,
+            selected,
+        })
+    }
+}
+
+/// 提取规则管理器：负责 rules.json 的读写
+pub struct ExtractionRuleManager {
+    rule_set: RuleSet,
+    /// 规则文件路径，无法获取配置目录时为 None（只在内存中使用默认规则）
+    rules_path: Option<PathBuf>,
+    /// 规则文件无法加载时为 false，此时不覆盖它（可能是更高版本写入的或正在手动编辑）
+    writable: bool,
+}
+
+impl ExtractionRuleManager {
+    /// 创建规则管理器，规则文件不存在时写入默认规则
+    pub fn new() -> AppResult<Self> {
+        Ok(Self::load_from(ConfigManager::get_config_dir()?.join(RULES_FILE_NAME)))
+    }
+
+    /// 从指定路径加载规则，规则文件无法加载时在内存中使用默认规则，不改动文件
+    fn load_from(rules_path: PathBuf) -> Self {
+        let (rule_set, writable) = match Self::load_rules(&rules_path) {
+            Ok(rule_set) => (rule_set, true),
+            Err(e) => {
+                logger::warn("加载提取规则失败，本次使用默认规则", &[("error", &e)]);
+                (RuleSet::default(), false)
+            }
+        };
+
+        ExtractionRuleManager {
+            rule_set,
+            rules_path: Some(rules_path),
+            writable,
+        }
+    }
+
+    /// 无法获取配置目录时使用默认规则，修改不会保存
+    pub fn unsaved_default() -> Self {
+        ExtractionRuleManager {
+            rule_set: RuleSet::default(),
+            rules_path: None,
+            writable: false,
+        }
+    }
+
+    fn rules_path(&self) -> AppResult<&Path> {
+        self.rules_path.as_deref()
+            .ok_or_else(|| AppError::file_system_error(tr("无法获取配置目录", "Unable to locate the config directory")))
+    }
+
+    /// 加载规则文件
+    fn load_rules(rules_path: &Path) -> AppResult<RuleSet> {
+        if rules_path.exists() {
+            let content = std::fs::read_to_string(rules_path)
+                .map_err(|e| AppError::io_error_at(e, tr("读取规则文件失败", "Failed to read the rules file"), rules_path))?;
+            let rule_set: RuleSet = serde_json::from_str(&content)
+                .map_err(|e| AppError::validation_error(&trf("规则文件格式错误: {}", "Invalid rules file format: {}", &[&e])).with_path(rules_path))?;
+
+            if rule_set.version > RULES_FILE_VERSION {
+                return Err(AppError::validation_error(&trf(
+                    "规则文件版本 {} 高于当前支持的版本 {}",
+                    "Rules file version {} is newer than the supported version {}",
+                    &[&rule_set.version, &RULES_FILE_VERSION],
+                )).with_path(rules_path));
+            }
+
+            Ok(rule_set)
+        } else {
+            let default_rules = RuleSet::default();
+            Self::write_rules(rules_path, &default_rules)?;
+            Ok(default_rules)
+        }
+    }
+
+    fn write_rules(rules_path: &Path, rule_set: &RuleSet) -> AppResult<()> {
+        let content = serde_json::to_string_pretty(rule_set)
+            .map_err(|e| AppError::validation_error(&trf("序列化规则失败: {}", "Failed to serialize rules: {}", &[&e])))?;
+        std::fs::write(rules_path, content)
+            .map_err(|e| AppError::io_error_at(e, tr("保存规则文件失败", "Failed to save the rules file"), rules_path))
+    }
+
+    /// 重新从磁盘读取规则文件（用户手动编辑后调用），有无效规则时返回错误
+    pub fn reload(&mut self) -> AppResult<()> {
+        let rule_set = Self::load_rules(self.rules_path()?)?;
+        RuleEngine::compile(&rule_set)?;
+        self.rule_set = rule_set;
+        self.writable = true;
+        Ok(())
+    }
+
+    /// 获取当前规则集
+    pub fn get_rules(&self) -> &RuleSet {
+        &self.rule_set
+    }
+
+    /// 更新并保存规则集，规则无效或规则文件无法加载时不会写入
+    pub fn update_rules(&mut self, rule_set: RuleSet) -> AppResult<()> {
+        RuleEngine::compile(&rule_set)?;
+        let rules_path = self.rules_path()?;
+        if !self.writable {
+            return Err(AppError::validation_error(tr(
+                "规则文件无法加载，请修复或删除后重新加载规则",
+                "The rules file could not be loaded; fix or delete it, then reload the rules",
+            )).with_path(rules_path));
+        }
+        Self::write_rules(rules_path, &rule_set)?;
+        self.rule_set = rule_set;
+        Ok(())
+    }
+
+    /// 根据当前规则集构建规则引擎，无效的规则被跳过
+    pub fn build_engine(&self) -> RuleEngine {
+        RuleEngine::compile_valid(&self.rule_set)
+    }
+}
+
+/// 金额捕获组的通用写法
+const AMOUNT_GROUP: &str = r"(?P<amount>{num})";
+
+fn amount_fields() -> BTreeMap<String, String> {
+    let mut fields = BTreeMap::new();
+    fields.insert("amount".to_string(), "amount".to_string());
+    fields
+}
+
+fn amount_currency_fields() -> BTreeMap<String, String> {
+    let mut fields = amount_fields();
+    fields.insert("currency".to_string(), "currency".to_string());
+    fields
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        RuleSet {
+            version: RULES_FILE_VERSION,
+            rules: vec![
+                ExtractionRule {
+                    id: "railway-ticket".to_string(),
+                    name: "铁路电子客票票价".to_string(),
+                    enabled: true,
+                    document_type: Some(DocumentType::RailwayTicket),
+                    keywords: vec!["电子客票".to_string()],
+                    anchor: None,
+                    window: 0,
+                    pattern: format!(r"(?:票价[：:]?\s*)?[¥￥]\s*{}", AMOUNT_GROUP),
+                    priority: 90,
+                    fields: amount_fields(),
+                    pick: MatchPick::First,
+                },
+                ExtractionRule {
+                    id: "flight-itinerary".to_string(),
+                    name: "航空运输电子客票行程单合计".to_string(),
+                    enabled: true,
+                    document_type: Some(DocumentType::FlightItinerary),
+                    keywords: vec!["行程单".to_string()],
+                    anchor: Some("合计".to_string()),
+                    window: 1,
+                    pattern: format!(r"(?P<currency>CNY|USD|[¥￥$])\s*{}", AMOUNT_GROUP),
+                    priority: 90,
+                    fields: amount_currency_fields(),
+                    pick: MatchPick::Max,
+                },
+                ExtractionRule {
+                    id: "toll-road".to_string(),
+                    name: "通行费发票价税合计".to_string(),
+                    enabled: true,
+                    document_type: None,
+                    keywords: vec!["通行费".to_string()],
+                    anchor: Some("价税合计".to_string()),
+                    window: 2,
+                    pattern: format!(r"[¥￥]\s*{}", AMOUNT_GROUP),
+                    priority: 85,
+                    fields: amount_fields(),
+                    pick: MatchPick::Max,
+                },
+                ExtractionRule {
+                    id: "taxi-receipt".to_string(),
+                    name: "出租车票金额".to_string(),
+                    enabled: true,
+                    document_type: Some(DocumentType::TaxiReceipt),
+                    keywords: vec!["出租".to_string()],
+                    anchor: None,
+                    window: 0,
+                    pattern: format!(r"(?:实收金额|金额|合计)[：:]?\s*[¥￥]?\s*{}", AMOUNT_GROUP),
+                    priority: 80,
+                    fields: amount_fields(),
+                    pick: MatchPick::Last,
+                },
+                ExtractionRule {
+                    id: "hotel-folio".to_string(),
+                    name: "酒店账单合计".to_string(),
+                    enabled: true,
+                    document_type: Some(DocumentType::HotelFolio),
+                    keywords: vec!["酒店".to_string()],
+                    anchor: Some("合计".to_string()),
+                    window: 1,
+                    pattern: format!(r"[¥￥]\s*{}", AMOUNT_GROUP),
+                    priority: 60,
+                    fields: amount_fields(),
+                    pick: MatchPick::Max,
+                },
+                ExtractionRule {
+                    id: "vat-total".to_string(),
+                    name: "增值税发票价税合计".to_string(),
+                    enabled: true,
+                    document_type: None,
+                    keywords: Vec::new(),
+                    anchor: Some("价税合计".to_string()),
+                    window: 2,
+                    pattern: format!(r"[¥￥]\s*{}", AMOUNT_GROUP),
+                    priority: 50,
+                    fields: amount_fields(),
+                    pick: MatchPick::Max,
+                },
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_rules_compile() {
+        assert!(RuleEngine::compile(&RuleSet::default()).is_ok());
+    }
+
+    #[test]
+    fn test_railway_ticket_rule() {
+        let engine = RuleEngine::compile(&RuleSet::default()).unwrap();
+        let text = "铁路电子客票\n北京南站 G101 上海虹桥站\n票价:￥553.00\n";
+        let rule_match = engine.extract_field(text, "amount", DocumentType::RailwayTicket).unwrap();
+        assert_eq!(rule_match.rule_id, "railway-ticket");
+        assert_eq!(rule_match.fields.get("amount").map(String::as_str), Some("553.00"));
+    }
+
+    #[test]
+    fn test_rule_amount_with_thousands_separator() {
+        let engine = RuleEngine::compile(&RuleSet::default()).unwrap();
+        let text = "价税合计（大写）壹万贰仟叁佰肆拾伍圆陆角整 （小写）¥12,345.60";
+        let rule_match = engine.extract_field(text, "amount", DocumentType::Unknown).unwrap();
+        assert_eq!(rule_match.fields.get("amount").map(String::as_str), Some("12,345.60"));
+    }
+
+    #[test]
+    fn test_suggest_amount_rule() {
+        let text = "销售方名称：某某餐饮管理有限公司\n合 计 ¥300.00 ¥17.60\n价税合计（大写）叁佰壹拾柒圆陆角整 （小写）¥317.60";
+        let rule = RuleEngine::suggest_amount_rule(text, Amount::from_cents(31760), Some("某某餐饮管理有限公司")).unwrap();
+        assert_eq!(rule.anchor.as_deref(), Some("小写"));
+        assert_eq!(rule.keywords, vec!["某某餐饮管理有限公司".to_string()]);
+
+        let result = RuleEngine::test_rule(&rule, text).unwrap();
+        assert_eq!(result.selected.unwrap().fields.get("amount").map(String::as_str), Some("317.60"));
+        assert!(RuleEngine::suggest_amount_rule(text, Amount::from_cents(99900), None).is_none());
+    }
+
+    #[test]
+    fn test_invalid_group_rejected() {
+        let mut rule_set = RuleSet::default();
+        rule_set.rules[0].fields.insert("amount".to_string(), "missing".to_string());
+        assert!(RuleEngine::compile(&rule_set).is_err());
+        // 启动时只跳过这条规则
+        assert_eq!(RuleEngine::compile_valid(&rule_set).rules.len(), RuleSet::default().rules.len() - 1);
+    }
+
+    #[test]
+    fn test_newer_rules_file_is_not_replaced() {
+        let dir = std::env::temp_dir().join(format!("invoicepilot-rules-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let rules_path = dir.join(RULES_FILE_NAME);
+        let newer = format!(r#"{{"version": {}, "rules": [], "future": true}}"#, RULES_FILE_VERSION + 1);
+        std::fs::write(&rules_path, &newer).unwrap();
+
+        let mut manager = ExtractionRuleManager::load_from(rules_path.clone());
+        assert_eq!(manager.get_rules().rules.len(), RuleSet::default().rules.len());
+        assert!(manager.update_rules(RuleSet::default()).is_err());
+        assert_eq!(std::fs::read_to_string(&rules_path).unwrap(), newer);
+
+        // 修复文件后重新加载即可保存
+        std::fs::remove_file(&rules_path).unwrap();
+        manager.reload().unwrap();
+        assert!(manager.update_rules(RuleSet::default()).is_ok());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
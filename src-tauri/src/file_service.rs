@@ -2,7 +2,16 @@ use std::path::Path;
 use std::fs;
 use walkdir::WalkDir;
 use chrono::{DateTime, Utc};
-use crate::types::{PdfFile, ImageFile};
+use crate::types::{PdfFile, ImageFile, ScanStatus};
+use crate::config::FileFilterConfig;
+use crate::document_classifier::DocumentType;
+use crate::money::Currency;
+use crate::error::{AppError, AppResult};
+use crate::logger;
+use crate::i18n::{tr, trf};
+
+/// 支持的图片扩展名
+pub const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "gif", "bmp", "tiff", "webp"];
 
 pub struct FileService;
 
@@ -12,15 +21,17 @@ impl FileService {
     }
 
     /// 扫描指定目录中的PDF文件
-    pub fn scan_directory(&self, directory_path: &str) -> Result<Vec<PdfFile>, String> {
+    ///
+    /// 匹配排除规则、超过大小上限或无法读取的文件也会返回，并在 `status` 中注明原因
+    pub fn scan_directory(&self, directory_path: &str, filter: &FileFilterConfig) -> AppResult<Vec<PdfFile>> {
         let path = Path::new(directory_path);
         
         if !path.exists() {
-            return Err("目录不存在".to_string());
+            return Err(AppError::file_system_error(tr("目录不存在", "Directory does not exist")).with_path(path));
         }
 
         if !path.is_dir() {
-            return Err("路径不是目录".to_string());
+            return Err(AppError::file_system_error(tr("路径不是目录", "Path is not a directory")).with_path(path));
         }
 
         let mut pdf_files = Vec::new();
@@ -36,12 +47,7 @@ impl FileService {
             if path.is_file() {
                 if let Some(extension) = path.extension() {
                     if extension.to_string_lossy().to_lowercase() == "pdf" {
-                        match self.create_pdf_file_info(path) {
-                            Ok(pdf_file) => pdf_files.push(pdf_file),
-                            Err(e) => {
-                                eprintln!("处理文件时出错 {}: {}", path.display(), e);
-                            }
-                        }
+                        pdf_files.push(self.scanned_pdf_file(path, filter));
                     }
                 }
             }
@@ -51,20 +57,19 @@ impl FileService {
     }
 
     /// 扫描指定目录中的图片文件
-    pub fn scan_image_files(&self, directory_path: &str) -> Result<Vec<ImageFile>, String> {
+    pub fn scan_image_files(&self, directory_path: &str) -> AppResult<Vec<ImageFile>> {
         let path = Path::new(directory_path);
         
         if !path.exists() {
-            return Err("目录不存在".to_string());
+            return Err(AppError::file_system_error(tr("目录不存在", "Directory does not exist")).with_path(path));
         }
 
         if !path.is_dir() {
-            return Err("路径不是目录".to_string());
+            return Err(AppError::file_system_error(tr("路径不是目录", "Path is not a directory")).with_path(path));
         }
 
         let mut image_files = Vec::new();
-        let supported_extensions = ["jpg", "jpeg", "png", "gif", "bmp", "tiff", "webp"];
-        
+
         for entry in WalkDir::new(path)
             .min_depth(1)
             .max_depth(1)
@@ -76,11 +81,11 @@ impl FileService {
             if path.is_file() {
                 if let Some(extension) = path.extension() {
                     let ext = extension.to_string_lossy().to_lowercase();
-                    if supported_extensions.contains(&ext.as_str()) {
+                    if IMAGE_EXTENSIONS.contains(&ext.as_str()) {
                         match self.create_image_file_info(path) {
                             Ok(image_file) => image_files.push(image_file),
                             Err(e) => {
-                                eprintln!("处理图片文件时出错 {}: {}", path.display(), e);
+                                logger::warn("处理图片文件时出错", &[("path", &path.display()), ("error", &e)]);
                             }
                         }
                     }
@@ -91,43 +96,84 @@ impl FileService {
         Ok(image_files)
     }
 
+    /// 创建扫描结果，按过滤配置标记排除或过大的文件
+    pub fn scanned_pdf_file(&self, path: &Path, filter: &FileFilterConfig) -> PdfFile {
+        let mut pdf_file = match self.create_pdf_file_info(path) {
+            Ok(pdf_file) => pdf_file,
+            Err(e) => {
+                let status = match e.kind() {
+                    AppError::PermissionError(_) => ScanStatus::PermissionDenied,
+                    _ => ScanStatus::ParseFailed,
+                };
+                return PdfFile {
+                    status,
+                    status_detail: Some(e.to_string()),
+                    ..self.empty_pdf_file(path)
+                };
+            }
+        };
+
+        if let Some(pattern) = filter.exclude_patterns.iter().find(|pattern| wildcard_match(pattern, &pdf_file.name)) {
+            pdf_file.status = ScanStatus::Excluded;
+            pdf_file.status_detail = Some(trf("文件名匹配排除规则 {}", "File name matches exclude pattern {}", &[pattern]));
+        } else if pdf_file.size > filter.max_file_size {
+            pdf_file.status = ScanStatus::TooLarge;
+            pdf_file.status_detail = Some(trf("文件大小 {} 字节超过上限 {} 字节", "File size {} bytes exceeds the limit of {} bytes", &[&pdf_file.size, &filter.max_file_size]));
+        }
+        pdf_file
+    }
+
     /// 创建PdfFile信息
-    pub fn create_pdf_file_info(&self, path: &Path) -> Result<PdfFile, String> {
+    pub fn create_pdf_file_info(&self, path: &Path) -> AppResult<PdfFile> {
         let metadata = fs::metadata(path)
-            .map_err(|e| format!("无法获取文件信息: {}", e))?;
-
-        let file_name = path.file_name()
-            .and_then(|name| name.to_str())
-            .unwrap_or("unknown.pdf")
-            .to_string();
-
-        let file_path = path.to_string_lossy().to_string();
+            .map_err(|e| AppError::io_error_at(e, tr("无法获取文件信息", "Unable to read file metadata"), path))?;
 
         let size = metadata.len();
         
         let modified = metadata.modified()
-            .map_err(|e| format!("无法获取修改时间: {}", e))?;
+            .map_err(|e| AppError::io_error_at(e, tr("无法获取修改时间", "Unable to read modification time"), path))?;
         
         let modified_dt: DateTime<Utc> = modified.into();
 
+        Ok(PdfFile {
+            size,
+            modified: modified_dt,
+            ..self.empty_pdf_file(path)
+        })
+    }
+
+    /// 只有路径信息的PdfFile
+    fn empty_pdf_file(&self, path: &Path) -> PdfFile {
         // 生成唯一ID
         let id = format!("{}", path.display()).replace('/', "_").replace('\\', "_");
 
-        Ok(PdfFile {
+        PdfFile {
             id,
-            name: file_name,
-            path: file_path,
-            size,
-            modified: modified_dt,
+            name: path.file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| "unknown.pdf".to_string()),
+            path: path.to_string_lossy().to_string(),
+            size: 0,
+            modified: DateTime::<Utc>::default(),
             amount: None,
+            currency: Currency::CNY,
+            document_type: DocumentType::Unknown,
+            seller: None,
+            issue_date: None,
+            invoice_count: 1,
             suggested_name: None,
-        })
+            category: None,
+            validation: Vec::new(),
+            buyer_mismatch: false,
+            status: ScanStatus::Ok,
+            status_detail: None,
+        }
     }
 
     /// 创建ImageFile信息
-    pub fn create_image_file_info(&self, path: &Path) -> Result<ImageFile, String> {
+    pub fn create_image_file_info(&self, path: &Path) -> AppResult<ImageFile> {
         let metadata = fs::metadata(path)
-            .map_err(|e| format!("无法获取文件信息: {}", e))?;
+            .map_err(|e| AppError::io_error_at(e, tr("无法获取文件信息", "Unable to read file metadata"), path))?;
 
         let file_name = path.file_name()
             .and_then(|name| name.to_str())
@@ -139,7 +185,7 @@ impl FileService {
         let size = metadata.len();
         
         let modified = metadata.modified()
-            .map_err(|e| format!("无法获取修改时间: {}", e))?;
+            .map_err(|e| AppError::io_error_at(e, tr("无法获取修改时间", "Unable to read modification time"), path))?;
         
         let modified_dt: DateTime<Utc> = modified.into();
 
@@ -157,32 +203,53 @@ impl FileService {
     }
 
     /// 重命名文件
-    pub fn rename_file(&self, old_path: &str, new_path: &str) -> Result<(), String> {
+    pub fn rename_file(&self, old_path: &str, new_path: &str) -> AppResult<()> {
         let old_path = Path::new(old_path);
         let new_path = Path::new(new_path);
 
         if !old_path.exists() {
-            return Err("源文件不存在".to_string());
+            return Err(AppError::file_system_error(tr("源文件不存在", "Source file does not exist")).with_path(old_path));
         }
 
         if new_path.exists() {
-            return Err("目标文件已存在".to_string());
+            return Err(AppError::file_system_error(tr("目标文件已存在", "Target file already exists")).with_path(new_path));
         }
 
         // 确保目标目录存在
         if let Some(parent) = new_path.parent() {
             if !parent.exists() {
                 fs::create_dir_all(parent)
-                    .map_err(|e| format!("无法创建目录: {}", e))?;
+                    .map_err(|e| AppError::io_error_at(e, tr("无法创建目录", "Unable to create directory"), parent))?;
             }
         }
 
         fs::rename(old_path, new_path)
-            .map_err(|e| format!("重命名失败: {}", e))?;
+            .map_err(|e| AppError::io_error_at(e, tr("重命名失败", "Rename failed"), old_path))?;
 
         Ok(())
     }
 
+    /// 移动文件（可跨磁盘），目标已存在时先删除
+    pub fn move_file(&self, old_path: &str, new_path: &str, overwrite: bool) -> AppResult<()> {
+        if overwrite && Path::new(new_path).exists() {
+            fs::remove_file(new_path)
+                .map_err(|e| AppError::io_error_at(e, tr("无法删除已存在的文件", "Unable to delete the existing file"), Path::new(new_path)))?;
+        }
+
+        match self.rename_file(old_path, new_path) {
+            Ok(()) => Ok(()),
+            // 跨磁盘时 rename 会失败，改为复制后删除
+            Err(_) if Path::new(old_path).exists() && !Path::new(new_path).exists() => {
+                fs::copy(old_path, new_path)
+                    .map_err(|e| AppError::io_error_at(e, tr("移动失败", "Move failed"), Path::new(old_path)))?;
+                fs::remove_file(old_path)
+                    .map_err(|e| AppError::io_error_at(e, tr("无法删除源文件", "Unable to delete the source file"), Path::new(old_path)))?;
+                Ok(())
+            }
+            Err(e) => Err(e),
+        }
+    }
+
     /// 验证文件名是否合法
     #[allow(dead_code)]
     pub fn validate_filename(&self, filename: &str) -> bool {
@@ -219,10 +286,20 @@ impl FileService {
             sanitized = sanitized.replace(ch, "_");
         }
 
-        // 限制长度
+        // 限制长度（按字节计），只截短主文件名并保留扩展名，截断位置需落在字符边界上
         if sanitized.len() > 255 {
-            sanitized.truncate(252);
-            sanitized.push_str("...");
+            let extension = Path::new(&sanitized).extension()
+                .map(|extension| format!(".{}", extension.to_string_lossy()))
+                .filter(|extension| extension.len() <= 16)
+                .unwrap_or_default();
+            let stem_length = sanitized.len() - extension.len();
+            let end = sanitized[..stem_length].char_indices()
+                .map(|(index, _)| index)
+                .take_while(|index| *index <= 255 - extension.len())
+                .last()
+                .unwrap_or(0);
+            sanitized.truncate(end);
+            sanitized.push_str(&extension);
         }
 
         sanitized
@@ -255,11 +332,84 @@ impl FileService {
     }
 
     /// 获取文件的目录路径
-    #[allow(dead_code)]
     pub fn get_directory_path(&self, file_path: &str) -> Option<String> {
         Path::new(file_path).parent()
             .map(|p| p.to_string_lossy().to_string())
     }
 
 
-} 
\ No newline at end of file
+}
+
+/// 文件名通配符匹配（`*` 匹配任意字符，`?` 匹配单个字符），如 "*.tmp"、"~*"
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    // 记录最近一个 * 的位置及其匹配到的名称位置，失配时回溯
+    let (mut p, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = star {
+            p = star_p + 1;
+            n = star_n + 1;
+            star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_status() {
+        let dir = std::env::temp_dir().join(format!("invoicepilot-scan-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.pdf"), b"%PDF-1.4").unwrap();
+        std::fs::write(dir.join("~lock.pdf"), b"%PDF-1.4").unwrap();
+        std::fs::write(dir.join("big.pdf"), vec![b' '; 64]).unwrap();
+
+        let filter = FileFilterConfig {
+            max_file_size: 32,
+            ..FileFilterConfig::default()
+        };
+        let mut files = FileService::new().scan_directory(&dir.to_string_lossy(), &filter).unwrap();
+        files.sort_by(|a, b| a.name.cmp(&b.name));
+        let statuses: Vec<(&str, ScanStatus)> = files.iter().map(|file| (file.name.as_str(), file.status)).collect();
+        assert_eq!(statuses, vec![
+            ("a.pdf", ScanStatus::Ok),
+            ("big.pdf", ScanStatus::TooLarge),
+            ("~lock.pdf", ScanStatus::Excluded),
+        ]);
+        assert_eq!(files[2].status_detail.as_deref(), Some("文件名匹配排除规则 ~*"));
+
+        assert!(wildcard_match("*.tmp", "a.b.tmp"));
+        assert!(!wildcard_match("*.tmp", "a.tmp.pdf"));
+        assert!(wildcard_match(".*", ".DS_Store"));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_sanitize_long_chinese_filename() {
+        let service = FileService::new();
+        // 附件名中的多字节字符不能在截断时被切开
+        let filename = format!("a{}.pdf", "增值税电子普通发票".repeat(12));
+        let sanitized = service.sanitize_filename(&filename);
+        assert!(sanitized.len() <= 255);
+        assert!(sanitized.ends_with(".pdf"));
+        assert!(sanitized.starts_with("a增值税电子普通发票"));
+
+        // 过长的附件名只截短主文件名，扩展名保留
+        let sanitized = service.sanitize_filename(&format!("{}.pdf", "x".repeat(300)));
+        assert_eq!(sanitized, format!("{}.pdf", "x".repeat(251)));
+    }
+}
@@ -0,0 +1,60 @@
+use std::fmt::Display;
+use std::sync::RwLock;
+use crate::config::Language;
+
+/// 后端消息使用的界面语言（来自界面配置中的 language）
+static LANGUAGE: RwLock<Language> = RwLock::new(Language::Chinese);
+
+/// 切换后端消息的语言（启动和配置更新后调用）
+pub fn set_language(language: Language) {
+    *LANGUAGE.write().unwrap() = language;
+}
+
+/// 当前界面语言
+pub fn language() -> Language {
+    *LANGUAGE.read().unwrap()
+}
+
+/// 按指定语言选择文本
+pub fn localized(language: Language, zh: &'static str, en: &'static str) -> &'static str {
+    match language {
+        Language::Chinese => zh,
+        Language::English => en,
+    }
+}
+
+/// 按当前界面语言选择文本，如 `tr("重命名失败", "Rename failed")`
+pub fn tr(zh: &'static str, en: &'static str) -> &'static str {
+    localized(language(), zh, en)
+}
+
+/// 按当前界面语言选择带参数的文本，依次替换其中的 `{}`
+pub fn trf(zh: &'static str, en: &'static str, args: &[&dyn Display]) -> String {
+    fill(tr(zh, en), args)
+}
+
+fn fill(template: &str, args: &[&dyn Display]) -> String {
+    let mut args = args.iter();
+    let mut parts = template.split("{}");
+    let mut text = parts.next().unwrap_or_default().to_string();
+    for part in parts {
+        if let Some(arg) = args.next() {
+            text.push_str(&arg.to_string());
+        }
+        text.push_str(part);
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_localized_fill() {
+        assert_eq!(localized(Language::English, "重命名失败", "Rename failed"), "Rename failed");
+        let en = fill(localized(Language::English, "成功重命名 {} 个文件，失败 {} 个文件", "Renamed {} files, {} failed"), &[&3, &1]);
+        assert_eq!(en, "Renamed 3 files, 1 failed");
+        assert_eq!(fill("文件名匹配排除规则 {}", &[&"~*"]), "文件名匹配排除规则 ~*");
+    }
+}
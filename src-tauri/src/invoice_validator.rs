@@ -0,0 +1,307 @@
+use serde::{Deserialize, Serialize};
+use crate::config::CompanyProfile;
+use crate::document_classifier::DocumentType;
+use crate::i18n::{tr, trf};
+use crate::types::{InvoiceInfo, VatDetails};
+
+/// 增值税发票的法定税率（%）
+const LEGAL_TAX_RATES: &[f64] = &[0.0, 1.0, 1.5, 3.0, 5.0, 6.0, 9.0, 13.0];
+
+/// 不带百分比的合法税率写法
+const TAX_EXEMPT_MARKERS: &[&str] = &["免税", "不征税"];
+
+/// 统一社会信用代码字符集（不含 I、O、Z、S、V）
+const USCC_CHARSET: &str = "0123456789ABCDEFGHJKLMNPQRTUWXY";
+
+/// 统一社会信用代码前17位的加权因子
+const USCC_WEIGHTS: [u32; 17] = [1, 3, 9, 27, 19, 26, 16, 17, 20, 29, 25, 13, 8, 24, 10, 30, 28];
+
+/// 校验规则
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ValidationRule {
+    /// 发票代码格式
+    InvoiceCode,
+    /// 发票号码格式
+    InvoiceNumber,
+    /// 金额 + 税额 = 价税合计
+    AmountSum,
+    /// 税率为法定税率
+    TaxRate,
+    /// 购买方名称与当前公司一致
+    BuyerName,
+    /// 购买方纳税人识别号与当前公司一致
+    BuyerTaxId,
+    /// 购买方纳税人识别号校验位
+    TaxIdChecksum,
+}
+
+/// 校验状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ValidationStatus {
+    Passed,
+    Failed,
+    /// 信息不足或不适用，未校验
+    Skipped,
+}
+
+/// 一条规则的校验结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidationResult {
+    pub rule: ValidationRule,
+    pub status: ValidationStatus,
+    pub message: String,
+}
+
+impl ValidationResult {
+    fn new(rule: ValidationRule, status: ValidationStatus, message: impl Into<String>) -> Self {
+        ValidationResult { rule, status, message: message.into() }
+    }
+}
+
+/// 发票字段校验器
+pub struct InvoiceValidator;
+
+impl InvoiceValidator {
+    pub fn new() -> Self {
+        InvoiceValidator
+    }
+
+    /// 校验增值税发票的提取结果，非增值税发票不做校验
+    ///
+    /// `profile` 为当前使用的公司抬头，为空时不核对购买方
+    pub fn validate(&self, info: &InvoiceInfo, profile: Option<&CompanyProfile>) -> Vec<ValidationResult> {
+        if !matches!(
+            info.document_type,
+            DocumentType::VatSpecialInvoice | DocumentType::VatOrdinaryInvoice | DocumentType::FullyDigitalInvoice
+        ) {
+            return Vec::new();
+        }
+
+        let (code_result, number_result) = self.check_code_and_number(info);
+        let mut results = vec![code_result, number_result, self.check_amount_sum(info), self.check_tax_rates(info)];
+        results.extend(self.check_buyer(&info.vat, profile));
+        results
+    }
+
+    /// 校验结果中是否有购买方名称或纳税人识别号与当前公司不符
+    pub fn has_buyer_mismatch(results: &[ValidationResult]) -> bool {
+        results.iter().any(|result| {
+            matches!(result.rule, ValidationRule::BuyerName | ValidationRule::BuyerTaxId)
+                && result.status == ValidationStatus::Failed
+        })
+    }
+
+    /// 发票代码和号码：传统发票为10或12位代码加8位号码，数电票无代码、号码为20位
+    fn check_code_and_number(&self, info: &InvoiceInfo) -> (ValidationResult, ValidationResult) {
+        use ValidationRule::{InvoiceCode, InvoiceNumber};
+        use ValidationStatus::{Failed, Passed, Skipped};
+
+        let code = info.vat.invoice_code.as_deref();
+        let Some(number) = info.vat.invoice_number.as_deref() else {
+            return (
+                ValidationResult::new(InvoiceCode, Skipped, tr("未识别到发票号码", "Invoice number not recognized")),
+                ValidationResult::new(InvoiceNumber, Failed, tr("未识别到发票号码", "Invoice number not recognized")),
+            );
+        };
+        let all_digits = |value: &str| value.chars().all(|c| c.is_ascii_digit());
+        let number_ok = all_digits(number) && (number.len() == 8 || number.len() == 20);
+
+        let fully_digital = info.document_type == DocumentType::FullyDigitalInvoice || number.len() == 20;
+        let number_result = if !number_ok {
+            ValidationResult::new(InvoiceNumber, Failed, trf("发票号码 {} 应为8位或20位数字", "Invoice number {} should have 8 or 20 digits", &[&number]))
+        } else if fully_digital && number.len() != 20 {
+            ValidationResult::new(InvoiceNumber, Failed, trf("数电票号码 {} 应为20位数字", "Fully digital invoice number {} should have 20 digits", &[&number]))
+        } else if !fully_digital && number.len() != 8 {
+            ValidationResult::new(InvoiceNumber, Failed, trf("发票号码 {} 应为8位数字", "Invoice number {} should have 8 digits", &[&number]))
+        } else {
+            ValidationResult::new(InvoiceNumber, Passed, trf("发票号码 {}", "Invoice number {}", &[&number]))
+        };
+
+        let code_result = match (fully_digital, code) {
+            (true, None) => ValidationResult::new(InvoiceCode, Passed, tr("数电票无发票代码", "Fully digital invoices have no invoice code")),
+            (true, Some(code)) => ValidationResult::new(InvoiceCode, Failed, trf("数电票不应有发票代码 {}", "Fully digital invoice should not have invoice code {}", &[&code])),
+            (false, None) => ValidationResult::new(InvoiceCode, Failed, tr("未识别到发票代码", "Invoice code not recognized")),
+            (false, Some(code)) if all_digits(code) && (code.len() == 10 || code.len() == 12) => {
+                ValidationResult::new(InvoiceCode, Passed, trf("发票代码 {}", "Invoice code {}", &[&code]))
+            }
+            (false, Some(code)) => ValidationResult::new(InvoiceCode, Failed, trf("发票代码 {} 应为10位或12位数字", "Invoice code {} should have 10 or 12 digits", &[&code])),
+        };
+
+        (code_result, number_result)
+    }
+
+    /// 合计金额加合计税额应等于价税合计
+    fn check_amount_sum(&self, info: &InvoiceInfo) -> ValidationResult {
+        let total = info.vat.total_amount.or(info.amount);
+        match (info.vat.pre_tax_amount, info.vat.tax_amount, total) {
+            (Some(pre_tax), Some(tax), Some(total)) if pre_tax + tax == total => ValidationResult::new(
+                ValidationRule::AmountSum,
+                ValidationStatus::Passed,
+                format!("{} + {} = {}", pre_tax, tax, total),
+            ),
+            (Some(pre_tax), Some(tax), Some(total)) => ValidationResult::new(
+                ValidationRule::AmountSum,
+                ValidationStatus::Failed,
+                trf("{} + {} ≠ 价税合计 {}", "{} + {} ≠ total {}", &[&pre_tax, &tax, &total]),
+            ),
+            _ => ValidationResult::new(ValidationRule::AmountSum, ValidationStatus::Skipped, tr("未识别到金额、税额或价税合计", "Amount, tax or total not recognized")),
+        }
+    }
+
+    /// 每个税率都应为法定税率或免税、不征税
+    fn check_tax_rates(&self, info: &InvoiceInfo) -> ValidationResult {
+        if info.vat.tax_rates.is_empty() {
+            return ValidationResult::new(ValidationRule::TaxRate, ValidationStatus::Skipped, tr("未识别到税率", "Tax rate not recognized"));
+        }
+        let illegal: Vec<&str> = info.vat.tax_rates.iter()
+            .map(String::as_str)
+            .filter(|rate| !Self::is_legal_rate(rate))
+            .collect();
+        if illegal.is_empty() {
+            ValidationResult::new(ValidationRule::TaxRate, ValidationStatus::Passed, info.vat.tax_rates.join(tr("、", ", ")))
+        } else {
+            ValidationResult::new(ValidationRule::TaxRate, ValidationStatus::Failed, trf("非法定税率: {}", "Non-statutory tax rate: {}", &[&illegal.join(tr("、", ", "))]))
+        }
+    }
+
+    fn is_legal_rate(rate: &str) -> bool {
+        if TAX_EXEMPT_MARKERS.contains(&rate) {
+            return true;
+        }
+        rate.trim_end_matches('%').parse::<f64>()
+            .is_ok_and(|value| LEGAL_TAX_RATES.iter().any(|legal| (legal - value).abs() < 1e-9))
+    }
+
+    /// 购买方名称和纳税人识别号与当前公司核对，并检查18位统一社会信用代码的校验位
+    fn check_buyer(&self, vat: &VatDetails, profile: Option<&CompanyProfile>) -> Vec<ValidationResult> {
+        use ValidationStatus::{Failed, Passed, Skipped};
+
+        // 全角括号、空白不影响名称比较
+        let normalize = |name: &str| -> String {
+            name.chars()
+                .filter(|c| !c.is_whitespace())
+                .map(|c| match c {
+                    '（' => '(',
+                    '）' => ')',
+                    c => c,
+                })
+                .collect()
+        };
+
+        let name_result = match (vat.buyer_name.as_deref(), profile) {
+            (None, _) => ValidationResult::new(ValidationRule::BuyerName, Skipped, tr("未识别到购买方名称", "Buyer name not recognized")),
+            (Some(_), None) => ValidationResult::new(ValidationRule::BuyerName, Skipped, tr("未选择公司抬头", "No company profile selected")),
+            (Some(_), Some(profile)) if profile.name.trim().is_empty() => {
+                ValidationResult::new(ValidationRule::BuyerName, Skipped, tr("当前公司未配置名称", "The current company has no name"))
+            }
+            (Some(name), Some(profile)) if normalize(name) == normalize(&profile.name) => {
+                ValidationResult::new(ValidationRule::BuyerName, Passed, name)
+            }
+            (Some(name), Some(profile)) => ValidationResult::new(
+                ValidationRule::BuyerName,
+                Failed,
+                trf("购买方名称 {} 与当前公司 {} 不一致", "Buyer name {} does not match the current company {}", &[&name, &profile.name]),
+            ),
+        };
+
+        let Some(buyer_tax_id) = vat.buyer_tax_id.as_deref() else {
+            return vec![
+                name_result,
+                ValidationResult::new(ValidationRule::BuyerTaxId, Skipped, tr("未识别到购买方纳税人识别号", "Buyer taxpayer ID not recognized")),
+                ValidationResult::new(ValidationRule::TaxIdChecksum, Skipped, tr("未识别到购买方纳税人识别号", "Buyer taxpayer ID not recognized")),
+            ];
+        };
+
+        let match_result = match profile.map(|profile| profile.tax_id.trim()).filter(|id| !id.is_empty()) {
+            None => ValidationResult::new(ValidationRule::BuyerTaxId, Skipped, tr("当前公司未配置纳税人识别号", "The current company has no taxpayer ID")),
+            Some(expected) if expected.eq_ignore_ascii_case(buyer_tax_id) => {
+                ValidationResult::new(ValidationRule::BuyerTaxId, Passed, buyer_tax_id)
+            }
+            Some(expected) => ValidationResult::new(
+                ValidationRule::BuyerTaxId,
+                Failed,
+                trf("购买方纳税人识别号 {} 与当前公司 {} 不一致", "Buyer taxpayer ID {} does not match the current company {}", &[&buyer_tax_id, &expected]),
+            ),
+        };
+
+        let checksum_result = match buyer_tax_id.len() {
+            18 if is_valid_uscc(buyer_tax_id) => {
+                ValidationResult::new(ValidationRule::TaxIdChecksum, ValidationStatus::Passed, tr("统一社会信用代码校验位正确", "Unified social credit code check digit is correct"))
+            }
+            18 => ValidationResult::new(ValidationRule::TaxIdChecksum, ValidationStatus::Failed, trf("{} 校验位错误", "{} has an invalid check digit", &[&buyer_tax_id])),
+            15 | 17 | 20 => ValidationResult::new(ValidationRule::TaxIdChecksum, ValidationStatus::Skipped, tr("旧版纳税人识别号，无校验位", "Legacy taxpayer ID without a check digit")),
+            _ => ValidationResult::new(ValidationRule::TaxIdChecksum, ValidationStatus::Failed, trf("{} 长度不正确", "{} has an invalid length", &[&buyer_tax_id])),
+        };
+
+        vec![name_result, match_result, checksum_result]
+    }
+}
+
+/// 校验18位统一社会信用代码（GB 32100-2015）的校验位
+pub fn is_valid_uscc(code: &str) -> bool {
+    let code = code.to_ascii_uppercase();
+    let values: Option<Vec<u32>> = code.chars()
+        .map(|c| USCC_CHARSET.find(c).map(|index| index as u32))
+        .collect();
+    let Some(values) = values.filter(|values| values.len() == 18) else {
+        return false;
+    };
+    let sum: u32 = values[..17].iter().zip(USCC_WEIGHTS).map(|(value, weight)| value * weight).sum();
+    (31 - sum % 31) % 31 == values[17]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::money::Amount;
+    use crate::types::VatDetails;
+
+    #[test]
+    fn test_is_valid_uscc() {
+        assert!(is_valid_uscc("91350100M000100Y43"));
+        assert!(is_valid_uscc("91110000600037341L"));
+        assert!(!is_valid_uscc("91350100M000100Y44"));
+        assert!(!is_valid_uscc("91350100M000100Y4"));
+    }
+
+    #[test]
+    fn test_validate() {
+        let info = InvoiceInfo {
+            amount: Some(Amount::from_cents(31800)),
+            document_type: DocumentType::FullyDigitalInvoice,
+            vat: VatDetails {
+                invoice_number: Some("24312000000012345678".to_string()),
+                pre_tax_amount: Some(Amount::from_cents(30000)),
+                tax_amount: Some(Amount::from_cents(1900)),
+                tax_rates: vec!["6%".to_string(), "7%".to_string()],
+                buyer_name: Some("某某科技（上海）有限公司".to_string()),
+                buyer_tax_id: Some("91350100M000100Y43".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let profile = CompanyProfile {
+            name: "某某科技(上海)有限公司".to_string(),
+            tax_id: "91350100M000100Y43".to_string(),
+            ..Default::default()
+        };
+
+        let results = InvoiceValidator::new().validate(&info, Some(&profile));
+        let status = |rule| results.iter().find(|r| r.rule == rule).map(|r| r.status);
+        assert_eq!(status(ValidationRule::InvoiceCode), Some(ValidationStatus::Passed));
+        assert_eq!(status(ValidationRule::InvoiceNumber), Some(ValidationStatus::Passed));
+        assert_eq!(status(ValidationRule::AmountSum), Some(ValidationStatus::Failed));
+        assert_eq!(status(ValidationRule::TaxRate), Some(ValidationStatus::Failed));
+        assert_eq!(status(ValidationRule::BuyerTaxId), Some(ValidationStatus::Passed));
+        assert_eq!(status(ValidationRule::TaxIdChecksum), Some(ValidationStatus::Passed));
+        assert_eq!(status(ValidationRule::BuyerName), Some(ValidationStatus::Passed));
+        assert!(!InvoiceValidator::has_buyer_mismatch(&results));
+
+        // 只有纳税人识别号的抬头（由旧配置迁移而来）不核对名称
+        let tax_id_only = CompanyProfile { name: String::new(), ..profile.clone() };
+        assert!(!InvoiceValidator::has_buyer_mismatch(&InvoiceValidator::new().validate(&info, Some(&tax_id_only))));
+
+        let other = CompanyProfile { name: "另一家公司".to_string(), ..profile };
+        assert!(InvoiceValidator::has_buyer_mismatch(&InvoiceValidator::new().validate(&info, Some(&other))));
+    }
+}
@@ -0,0 +1,416 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, NaiveDate, Utc};
+use sha2::{Digest, Sha256};
+use crate::config::ConfigManager;
+use crate::error::{AppError, AppResult};
+use crate::i18n::{tr, trf};
+use crate::logger;
+use crate::money::{Amount, Currency, Money};
+use crate::expense_category::{UNCATEGORIZED, UNCATEGORIZED_EN};
+use crate::document_classifier::DocumentType;
+use crate::types::{FieldEvidence, InvoiceInfo};
+
+/// 台账文件格式版本
+pub const LEDGER_FILE_VERSION: u32 = 1;
+
+/// 台账中的一个文件，按文件内容的 SHA-256 索引，文件改名或移动后仍能对应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub content_hash: String,
+    /// 最近一次记录时的文件名
+    pub file_name: String,
+    /// 最近一次记录时的路径
+    pub path: String,
+    /// 来自邮件时的来源信息
+    #[serde(default)]
+    pub mail: Option<MailOrigin>,
+    /// 扫描时识别出的发票信息（已套用人工修正）
+    #[serde(default)]
+    pub invoice: Option<LedgerInvoice>,
+    /// 人工修正的字段，之后扫描时优先使用
+    #[serde(default)]
+    pub correction: Option<InvoiceCorrection>,
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// 文件的邮件来源
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MailOrigin {
+    pub sender: String,
+    pub subject: String,
+    pub message_id: Option<String>,
+    /// 从正文链接下载时的链接地址
+    pub link: Option<String>,
+}
+
+/// 扫描识别出的发票信息，供对账等功能在不重新解析PDF时使用
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LedgerInvoice {
+    pub amount: Option<Amount>,
+    pub currency: Currency,
+    pub seller: Option<String>,
+    pub issue_date: Option<NaiveDate>,
+    /// 费用类别
+    #[serde(default)]
+    pub category: Option<String>,
+}
+
+/// 人工修正的发票字段，未填写的字段沿用识别结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InvoiceCorrection {
+    #[serde(default)]
+    pub amount: Option<Amount>,
+    #[serde(default)]
+    pub currency: Option<Currency>,
+    #[serde(default)]
+    pub document_type: Option<DocumentType>,
+    #[serde(default)]
+    pub seller: Option<String>,
+    #[serde(default)]
+    pub issue_date: Option<NaiveDate>,
+    #[serde(default)]
+    pub category: Option<String>,
+    /// 修正时间，保存时填写
+    #[serde(default)]
+    pub corrected_at: Option<DateTime<Utc>>,
+}
+
+impl InvoiceCorrection {
+    /// 用修正值覆盖识别结果，对应字段的识别依据改为人工修正
+    pub fn apply(&self, invoice_info: &mut InvoiceInfo) {
+        let mut corrected: Vec<(&str, String)> = Vec::new();
+        if let Some(currency) = self.currency {
+            invoice_info.currency = currency;
+        }
+        if let Some(amount) = self.amount {
+            invoice_info.amount = Some(amount);
+            let money = Money::new(amount, invoice_info.currency);
+            corrected.push(("amount", format!("{} {}", money.format_amount(), money.currency.code())));
+        }
+        if let Some(document_type) = self.document_type {
+            invoice_info.document_type = document_type;
+            corrected.push(("document_type", format!("{:?}", document_type)));
+        }
+        if let Some(seller) = &self.seller {
+            invoice_info.seller = Some(seller.clone());
+            corrected.push(("seller", seller.clone()));
+        }
+        if let Some(issue_date) = self.issue_date {
+            invoice_info.issue_date = Some(issue_date);
+            corrected.push(("issue_date", issue_date.to_string()));
+        }
+        if let Some(category) = &self.category {
+            invoice_info.category = Some(category.clone());
+            corrected.push(("category", category.clone()));
+        }
+
+        invoice_info.evidence.retain(|evidence| !corrected.iter().any(|(field, _)| *field == evidence.field));
+        invoice_info.evidence.extend(corrected.into_iter().map(|(field, value)| FieldEvidence {
+            field: field.to_string(),
+            value,
+            strategy: tr("人工修正", "Manual correction").to_string(),
+            snippet: String::new(),
+            page: None,
+            line: None,
+            rejected: None,
+        }));
+    }
+
+    /// 把修正值写入台账中的发票信息
+    fn apply_to_ledger(&self, invoice: &mut LedgerInvoice) {
+        if let Some(currency) = self.currency {
+            invoice.currency = currency;
+        }
+        if let Some(amount) = self.amount {
+            invoice.amount = Some(amount);
+        }
+        if let Some(seller) = &self.seller {
+            invoice.seller = Some(seller.clone());
+        }
+        if let Some(issue_date) = self.issue_date {
+            invoice.issue_date = Some(issue_date);
+        }
+        if let Some(category) = &self.category {
+            invoice.category = Some(category.clone());
+        }
+    }
+}
+
+/// 一个费用类别的统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CategoryStats {
+    /// 类别名称，未分类时为 "未分类"
+    pub category: String,
+    pub invoice_count: usize,
+    pub totals_by_currency: BTreeMap<Currency, Amount>,
+}
+
+/// 台账文件内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerData {
+    pub version: u32,
+    #[serde(default)]
+    pub entries: BTreeMap<String, LedgerEntry>,
+    /// 已处理过的邮件标识
+    #[serde(default)]
+    pub processed_messages: BTreeSet<String>,
+}
+
+impl Default for LedgerData {
+    fn default() -> Self {
+        LedgerData {
+            version: LEDGER_FILE_VERSION,
+            entries: BTreeMap::new(),
+            processed_messages: BTreeSet::new(),
+        }
+    }
+}
+
+/// 处理台账（ledger.json）
+#[derive(Clone)]
+pub struct Ledger {
+    data: LedgerData,
+    /// 台账文件路径，无法获取配置目录时为 None（只在内存中记录）
+    ledger_path: Option<PathBuf>,
+}
+
+impl Ledger {
+    /// 加载配置目录中的 ledger.json，不存在时为空台账
+    pub fn new() -> AppResult<Self> {
+        let config_dir = ConfigManager::get_config_dir()?;
+        Self::load_or_recover(&config_dir.join("ledger.json"))
+    }
+
+    /// 无法获取配置目录时使用空台账，记录不会保存
+    pub fn unsaved() -> Self {
+        Ledger {
+            data: LedgerData::default(),
+            ledger_path: None,
+        }
+    }
+
+    /// 加载台账，文件无法读取或解析时备份后使用空台账
+    fn load_or_recover(ledger_path: &Path) -> AppResult<Self> {
+        Self::load(ledger_path).or_else(|e| {
+            let backup_path = ConfigManager::backup_path(ledger_path, "invalid");
+            std::fs::rename(ledger_path, &backup_path)
+                .map_err(|e| AppError::io_error_at(e, tr("备份台账失败", "Failed to back up the ledger"), ledger_path))?;
+            logger::warn("台账无法加载，已备份并使用空台账", &[("error", &e), ("backup", &backup_path.display())]);
+            Ok(Ledger {
+                data: LedgerData::default(),
+                ledger_path: Some(ledger_path.to_path_buf()),
+            })
+        })
+    }
+
+    /// 从指定路径加载台账
+    pub fn load(ledger_path: &Path) -> AppResult<Self> {
+        let data = if ledger_path.exists() {
+            let content = std::fs::read_to_string(ledger_path)
+                .map_err(|e| AppError::io_error_at(e, tr("读取台账失败", "Failed to read the ledger"), ledger_path))?;
+            serde_json::from_str(&content)
+                .map_err(|e| AppError::validation_error(&trf("台账格式错误: {}", "Invalid ledger format: {}", &[&e])).with_path(ledger_path))?
+        } else {
+            LedgerData::default()
+        };
+
+        Ok(Ledger {
+            data,
+            ledger_path: Some(ledger_path.to_path_buf()),
+        })
+    }
+
+    /// 保存台账
+    pub fn save(&self) -> AppResult<()> {
+        let content = serde_json::to_string_pretty(&self.data)
+            .map_err(|e| AppError::validation_error(&trf("序列化台账失败: {}", "Failed to serialize the ledger: {}", &[&e])))?;
+        let ledger_path = self.ledger_path.as_ref()
+            .ok_or_else(|| AppError::file_system_error(tr("无法获取配置目录", "Unable to locate the config directory")))?;
+        std::fs::write(ledger_path, content)
+            .map_err(|e| AppError::io_error_at(e, tr("保存台账失败", "Failed to save the ledger"), ledger_path))
+    }
+
+    /// 计算内容的 SHA-256（十六进制）
+    pub fn hash_bytes(bytes: &[u8]) -> String {
+        format!("{:x}", Sha256::digest(bytes))
+    }
+
+    /// 计算文件内容的 SHA-256
+    pub fn hash_file(path: &Path) -> AppResult<String> {
+        let bytes = std::fs::read(path)
+            .map_err(|e| AppError::io_error_at(e, tr("读取文件失败", "Failed to read file"), path))?;
+        Ok(Self::hash_bytes(&bytes))
+    }
+
+    pub fn get(&self, content_hash: &str) -> Option<&LedgerEntry> {
+        self.data.entries.get(content_hash)
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &LedgerEntry> {
+        self.data.entries.values()
+    }
+
+    /// 记录文件（同一内容只保留一条）
+    pub fn record(&mut self, entry: LedgerEntry) {
+        self.data.entries.insert(entry.content_hash.clone(), entry);
+    }
+
+    /// 记录扫描结果，已有记录时保留其邮件来源和人工修正
+    pub fn record_scan(&mut self, path: &Path, invoice: LedgerInvoice) -> AppResult<()> {
+        let content_hash = Self::hash_file(path)?;
+        let existing = self.get(&content_hash);
+        let mail = existing.and_then(|entry| entry.mail.clone());
+        let correction = existing.and_then(|entry| entry.correction.clone());
+        self.record(LedgerEntry {
+            file_name: file_name_of(path),
+            path: path.to_string_lossy().to_string(),
+            content_hash,
+            mail,
+            invoice: Some(invoice),
+            correction,
+            recorded_at: Utc::now(),
+        });
+        Ok(())
+    }
+
+    /// 文件内容对应的台账记录
+    pub fn entry_for(&self, path: &Path) -> Option<&LedgerEntry> {
+        let content_hash = Self::hash_file(path).ok()?;
+        self.get(&content_hash)
+    }
+
+    /// 文件内容对应的人工修正
+    pub fn correction_for(&self, path: &Path) -> Option<&InvoiceCorrection> {
+        self.entry_for(path)?.correction.as_ref()
+    }
+
+    /// 保存文件的人工修正，`None` 清除修正；台账中没有该文件时新建记录
+    pub fn set_correction(&mut self, path: &Path, correction: Option<InvoiceCorrection>) -> AppResult<LedgerEntry> {
+        let content_hash = Self::hash_file(path)?;
+        let entry = self.data.entries.entry(content_hash.clone()).or_insert_with(|| LedgerEntry {
+            content_hash,
+            file_name: file_name_of(path),
+            path: path.to_string_lossy().to_string(),
+            mail: None,
+            invoice: None,
+            correction: None,
+            recorded_at: Utc::now(),
+        });
+
+        entry.correction = correction.map(|correction| {
+            correction.apply_to_ledger(entry.invoice.get_or_insert_with(LedgerInvoice::default));
+            InvoiceCorrection {
+                corrected_at: Some(Utc::now()),
+                ..correction
+            }
+        });
+        Ok(entry.clone())
+    }
+
+    /// 按费用类别统计已扫描的发票，按类别名称排序
+    pub fn category_stats(&self) -> Vec<CategoryStats> {
+        let mut stats: BTreeMap<String, CategoryStats> = BTreeMap::new();
+        for invoice in self.entries().filter_map(|entry| entry.invoice.as_ref()) {
+            let category = invoice.category.clone().unwrap_or_else(|| tr(UNCATEGORIZED, UNCATEGORIZED_EN).to_string());
+            let stat = stats.entry(category.clone()).or_insert_with(|| CategoryStats {
+                category,
+                invoice_count: 0,
+                totals_by_currency: BTreeMap::new(),
+            });
+            stat.invoice_count += 1;
+            if let Some(amount) = invoice.amount {
+                *stat.totals_by_currency.entry(invoice.currency).or_default() += amount;
+            }
+        }
+        stats.into_values().collect()
+    }
+
+    pub fn is_message_processed(&self, message_key: &str) -> bool {
+        self.data.processed_messages.contains(message_key)
+    }
+
+    pub fn mark_message_processed(&mut self, message_key: &str) {
+        self.data.processed_messages.insert(message_key.to_string());
+    }
+
+    /// 合并在副本上新增的记录和已处理邮件，已有记录保持不变
+    pub fn merge_new(&mut self, other: &Ledger) {
+        for (content_hash, entry) in &other.data.entries {
+            self.data.entries.entry(content_hash.clone()).or_insert_with(|| entry.clone());
+        }
+        self.data.processed_messages.extend(other.data.processed_messages.iter().cloned());
+    }
+}
+
+fn file_name_of(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_correction_survives_rename_and_rescan() {
+        let dir = std::env::temp_dir().join(format!("invoicepilot-ledger-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let original = dir.join("scan.pdf");
+        std::fs::write(&original, b"%PDF-1.4 invoice").unwrap();
+
+        let mut ledger = Ledger::load(&dir.join("ledger.json")).unwrap();
+        let correction = InvoiceCorrection {
+            amount: Some(Amount::from_cents(31760)),
+            seller: Some("某某餐饮管理有限公司".to_string()),
+            ..Default::default()
+        };
+        let entry = ledger.set_correction(&original, Some(correction)).unwrap();
+        assert_eq!(entry.invoice.unwrap().amount, Some(Amount::from_cents(31760)));
+
+        // 改名后按内容仍能找到修正，重新扫描不会丢失
+        let renamed = dir.join("317.60元_发票.pdf");
+        std::fs::rename(&original, &renamed).unwrap();
+        ledger.record_scan(&renamed, LedgerInvoice::default()).unwrap();
+        let correction = ledger.correction_for(&renamed).unwrap();
+        assert_eq!(correction.seller.as_deref(), Some("某某餐饮管理有限公司"));
+        assert!(correction.corrected_at.is_some());
+
+        let mut invoice_info = InvoiceInfo {
+            amount: Some(Amount::from_cents(30000)),
+            ..Default::default()
+        };
+        correction.apply(&mut invoice_info);
+        assert_eq!(invoice_info.amount, Some(Amount::from_cents(31760)));
+        assert_eq!(invoice_info.evidence[0].strategy, "人工修正");
+
+        ledger.set_correction(&renamed, None).unwrap();
+        assert!(ledger.correction_for(&renamed).is_none());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_recover_from_invalid_ledger() {
+        let dir = std::env::temp_dir().join(format!("invoicepilot-ledger-recover-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let ledger_path = dir.join("ledger.json");
+        std::fs::write(&ledger_path, "{ not json").unwrap();
+
+        let ledger = Ledger::load_or_recover(&ledger_path).unwrap();
+        assert_eq!(ledger.entries().count(), 0);
+        assert!(!ledger_path.exists());
+        let backups = std::fs::read_dir(&dir).unwrap()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_name().to_string_lossy().starts_with("ledger.invalid."))
+            .count();
+        assert_eq!(backups, 1);
+        ledger.save().unwrap();
+        assert!(Ledger::load(&ledger_path).is_ok());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -5,6 +5,26 @@ mod config;
 mod pdf_service;
 mod file_service;
 mod naming_engine;
+mod extraction_rules;
+mod document_classifier;
+mod money;
+mod pdf_splitter;
+mod organizer;
+mod watch_service;
+mod ledger;
+mod mail_ingest;
+mod package_builder;
+mod payment_matcher;
+mod bank_statement;
+mod reconciliation;
+mod invoice_validator;
+mod expense_category;
+mod pdf_layout;
+mod pdf_security;
+mod logger;
+mod i18n;
+mod line_items;
+mod parser_accuracy;
 pub mod directory_utils;
 
 use types::*;
@@ -12,20 +32,78 @@ use config::*;
 use pdf_service::*;
 use file_service::*;
 use naming_engine::*;
+use extraction_rules::*;
+use document_classifier::*;
+use pdf_splitter::*;
+use organizer::*;
+use watch_service::*;
+use ledger::*;
+use mail_ingest::*;
+use package_builder::*;
+use payment_matcher::*;
+use bank_statement::*;
+use reconciliation::*;
+use invoice_validator::*;
 use directory_utils::*;
+use error::{AppError, AppResult};
 
+use std::collections::HashMap;
+use std::path::Path;
 use std::sync::Mutex;
-use tauri::State;
+use std::time::Instant;
+use logger::{LogEntry, LogLevel, Logger};
+use i18n::{tr, trf};
+use tauri::{AppHandle, Emitter, Manager, State};
 
 // 全局状态管理
+//
+// 命令可能并发执行，同时持有多个锁时按字段顺序加锁，避免死锁。
+// config_manager 只用于读取配置快照（`config_snapshot`）或单独修改配置，不与其他锁同时持有。
 struct AppState {
     config_manager: Mutex<ConfigManager>,
-    pdf_parser: Mutex<PdfParser>,
+    rule_manager: Mutex<ExtractionRuleManager>,
+    statement_importer: Mutex<StatementImporter>,
+    reconciler: Mutex<Reconciler>,
     file_service: Mutex<FileService>,
+    pdf_parser: Mutex<PdfParser>,
     naming_engine: Mutex<NamingEngine>,
+    pdf_splitter: Mutex<PdfSplitter>,
+    organizer: Mutex<Organizer>,
+    package_builder: Mutex<PackageBuilder>,
+    payment_matcher: Mutex<PaymentMatcher>,
+    ledger: Mutex<Ledger>,
+    /// 无内部状态，无需加锁
+    mail_ingestor: MailIngestor,
+    invoice_validator: Mutex<InvoiceValidator>,
+    watch_queue: Mutex<WatchQueue>,
+    folder_watcher: Mutex<FolderWatcher>,
     directory_utils: Mutex<DirectoryUtils>,
 }
 
+/// 当前配置的副本，读取后立即释放 config_manager
+fn config_snapshot(state: &AppState) -> AppConfig {
+    state.config_manager.lock().unwrap().get_config().clone()
+}
+
+/// 把配置应用到各服务并按配置重启文件夹监控
+///
+/// 调用时不能持有 config_manager 以外的锁；重启监控前释放其他锁，监控线程可能正在等待它们
+fn apply_config(app: &AppHandle, state: &AppState, config: &AppConfig) -> AppResult<()> {
+    {
+        let mut pdf_parser = state.pdf_parser.lock().unwrap();
+        let mut naming_engine = state.naming_engine.lock().unwrap();
+        let mut organizer = state.organizer.lock().unwrap();
+        pdf_parser.set_category_rules(&config.categories.rules);
+        pdf_parser.set_pdf_passwords(&config.pdf_passwords);
+        naming_engine.set_filename_template(&config.rename_strategy.filename_template);
+        naming_engine.set_language(config.ui_config.language);
+        organizer.set_directory_template(&config.organize.directory_template);
+    }
+    i18n::set_language(config.ui_config.language);
+    logger::set_detailed(config.ui_config.show_detailed_logs);
+    restart_folder_watcher(app, config, &mut state.folder_watcher.lock().unwrap())
+}
+
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 #[tauri::command]
 fn greet(name: &str) -> String {
@@ -33,42 +111,104 @@ fn greet(name: &str) -> String {
 }
 
 /// 扫描指定目录中的PDF文件
+///
+/// `document_types` 不为空时只返回这些票据类型的文件
 #[tauri::command]
-async fn scan_pdf_files(directory: String, state: State<'_, AppState>) -> Result<Vec<PdfFile>, String> {
+async fn scan_pdf_files(directory: String, document_types: Option<Vec<DocumentType>>, state: State<'_, AppState>) -> Result<Vec<PdfFile>, AppError> {
+    let config = config_snapshot(&state);
+    let company_profile = config.company.active().cloned();
     let file_service = state.file_service.lock().unwrap();
     let pdf_parser = state.pdf_parser.lock().unwrap();
     let naming_engine = state.naming_engine.lock().unwrap();
+    let mut ledger = state.ledger.lock().unwrap();
+    let invoice_validator = state.invoice_validator.lock().unwrap();
     
+    let batch_id = logger::batch_id("scan");
+    let started = Instant::now();
+
     // 扫描PDF文件
-    let mut files = file_service.scan_directory(&directory)
-        .map_err(|e| e.to_string())?;
+    let mut files = file_service.scan_directory(&directory, &config.file_filter)?;
     
-    // 为每个文件分析PDF内容并生成建议文件名
+    // 为每个文件分析PDF内容并生成建议文件名，排除、过大或无法读取的文件不解析
     for file in &mut files {
-        match pdf_parser.analyze_pdf(&file.path) {
+        if file.status != ScanStatus::Ok {
+            logger::info("跳过文件", &[("batch_id", &batch_id), ("path", &file.path), ("status", &format!("{:?}", file.status))]);
+            continue;
+        }
+        let file_started = Instant::now();
+        match analyze_with_corrections(&pdf_parser, &ledger, &file.path) {
             Ok(invoice_info) => {
+                if invoice_info.amount.is_none() {
+                    file.status = ScanStatus::NoAmount;
+                    file.status_detail = Some(tr("未识别到金额", "No amount recognized").to_string());
+                }
+                file.validation = invoice_validator.validate(&invoice_info, company_profile.as_ref());
+                file.buyer_mismatch = InvoiceValidator::has_buyer_mismatch(&file.validation);
                 file.amount = invoice_info.amount;
-                file.suggested_name = Some(naming_engine.generate_suggested_name(&file));
+                file.currency = invoice_info.currency;
+                file.document_type = invoice_info.document_type;
+                file.seller = invoice_info.seller;
+                file.category = invoice_info.category;
+                file.issue_date = invoice_info.issue_date;
+                file.invoice_count = invoice_info.segments.len().max(1);
+
+                // 记入台账，供对账等功能使用
+                let invoice = LedgerInvoice {
+                    amount: file.amount,
+                    currency: file.currency,
+                    seller: file.seller.clone(),
+                    issue_date: file.issue_date,
+                    category: file.category.clone(),
+                };
+                if let Err(e) = ledger.record_scan(Path::new(&file.path), invoice) {
+                    logger::warn("记录台账失败", &[("batch_id", &batch_id), ("path", &file.path), ("error", &e)]);
+                }
             }
             Err(e) => {
-                eprintln!("分析PDF文件失败 {}: {}", file.path, e);
-                file.suggested_name = Some("未知金额_发票.pdf".to_string());
+                logger::warn("分析PDF文件失败", &[("batch_id", &batch_id), ("path", &file.path), ("error", &e)]);
+                file.status = ScanStatus::from_error(&e);
+                file.status_detail = Some(e.to_string());
             }
         }
+        logger::debug("分析PDF文件", &[
+            ("batch_id", &batch_id),
+            ("path", &file.path),
+            ("status", &format!("{:?}", file.status)),
+            ("duration_ms", &file_started.elapsed().as_millis()),
+        ]);
+    }
+    if let Err(e) = ledger.save() {
+        logger::error("保存台账失败", &[("batch_id", &batch_id), ("error", &e)]);
+    }
+    logger::info("扫描完成", &[
+        ("batch_id", &batch_id),
+        ("directory", &directory),
+        ("files", &files.len()),
+        ("failed", &files.iter().filter(|file| file.status != ScanStatus::Ok).count()),
+        ("duration_ms", &started.elapsed().as_millis()),
+    ]);
+
+    // 按票据类型过滤
+    if let Some(document_types) = document_types.filter(|types| !types.is_empty()) {
+        files.retain(|file| document_types.contains(&file.document_type));
     }
     
-    // 解决重名冲突
+    // 解决重名冲突，只有扫描状态正常的文件参与命名，其余文件没有建议文件名
+    let renamable: Vec<usize> = files.iter()
+        .enumerate()
+        .filter(|(_, file)| file.status == ScanStatus::Ok)
+        .map(|(index, _)| index)
+        .collect();
+
     // 第一步：解决批量文件内部的重名冲突
-    let resolved_names = naming_engine.resolve_naming_conflicts(&files);
+    let resolved_names = naming_engine.resolve_naming_conflicts(renamable.iter().map(|&index| &files[index]));
     
     // 第二步：检查并解决与目录中已存在文件的冲突
     let final_names = naming_engine.resolve_directory_conflicts(&directory, &resolved_names);
     
     // 第三步：将最终解决冲突后的文件名更新到每个文件
-    for (i, file) in files.iter_mut().enumerate() {
-        if let Some(final_name) = final_names.get(i) {
-            file.suggested_name = Some(final_name.clone());
-        }
+    for (&index, final_name) in renamable.iter().zip(final_names) {
+        files[index].suggested_name = Some(final_name);
     }
     
     Ok(files)
@@ -76,13 +216,12 @@ async fn scan_pdf_files(directory: String, state: State<'_, AppState>) -> Result
 
 /// 扫描指定目录中的图片文件
 #[tauri::command]
-async fn scan_image_files(directory: String, state: State<'_, AppState>) -> Result<Vec<ImageFile>, String> {
+async fn scan_image_files(directory: String, state: State<'_, AppState>) -> Result<Vec<ImageFile>, AppError> {
     let file_service = state.file_service.lock().unwrap();
     let naming_engine = state.naming_engine.lock().unwrap();
     
     // 扫描图片文件
-    let mut files = file_service.scan_image_files(&directory)
-        .map_err(|e| e.to_string())?;
+    let mut files = file_service.scan_image_files(&directory)?;
     
     // 为每个图片文件生成建议文件名（基于时间戳）
     for file in &mut files {
@@ -105,21 +244,163 @@ async fn scan_image_files(directory: String, state: State<'_, AppState>) -> Resu
     Ok(files)
 }
 
+/// 分析PDF并套用台账中按内容哈希保存的人工修正
+///
+/// 邮件导入的加密PDF优先尝试该发件人的打开密码
+fn analyze_with_corrections(pdf_parser: &PdfParser, ledger: &Ledger, file_path: &str) -> AppResult<InvoiceInfo> {
+    let entry = ledger.entry_for(Path::new(file_path));
+    let sender = entry.and_then(|entry| entry.mail.as_ref()).map(|mail| mail.sender.as_str());
+    let mut invoice_info = pdf_parser.analyze_pdf_from_sender(file_path, sender)?;
+    if let Some(correction) = entry.and_then(|entry| entry.correction.as_ref()) {
+        correction.apply(&mut invoice_info);
+    }
+    Ok(invoice_info)
+}
+
 /// 分析单个PDF文件的内容
 #[tauri::command]
-async fn analyze_pdf_content(file_path: String, state: State<'_, AppState>) -> Result<InvoiceInfo, String> {
+async fn analyze_pdf_content(file_path: String, state: State<'_, AppState>) -> Result<InvoiceInfo, AppError> {
     let pdf_parser = state.pdf_parser.lock().unwrap();
+    let naming_engine = state.naming_engine.lock().unwrap();
+    let ledger = state.ledger.lock().unwrap();
     
-    pdf_parser.analyze_pdf(&file_path)
-        .map_err(|e| e.to_string())
+    let mut invoice_info = analyze_with_corrections(&pdf_parser, &ledger, &file_path)?;
+    let amount = invoice_info.amount.map(|amount| money::Money::new(amount, invoice_info.currency));
+    invoice_info.suggested_filename = naming_engine.generate_filename(amount, invoice_info.document_type, invoice_info.category.as_deref());
+    Ok(invoice_info)
+}
+
+/// 读取PDF每页带位置的文字块，用于排查字段识别问题
+#[tauri::command]
+async fn get_pdf_layout(file_path: String, state: State<'_, AppState>) -> Result<Vec<pdf_layout::PageLayout>, AppError> {
+    let pdf_parser = state.pdf_parser.lock().unwrap();
+    pdf_parser.extract_layout_from_pdf(std::path::Path::new(&file_path))
+}
+
+/// 列出PDF每个字段的全部候选及识别依据，包括未采用的候选
+#[tauri::command]
+async fn explain_pdf_fields(file_path: String, state: State<'_, AppState>) -> Result<Vec<FieldEvidence>, AppError> {
+    let pdf_parser = state.pdf_parser.lock().unwrap();
+    pdf_parser.explain_pdf(&file_path)
+}
+
+/// 用样本目录（PDF加同名 .json 期望字段）评估识别准确率
+#[tauri::command]
+async fn evaluate_parser_corpus(directory: String, state: State<'_, AppState>) -> Result<parser_accuracy::AccuracyReport, AppError> {
+    let pdf_parser = state.pdf_parser.lock().unwrap();
+    parser_accuracy::evaluate_corpus(&pdf_parser, Path::new(&directory))
+}
+
+/// 识别PDF中每张发票的页码范围
+#[tauri::command]
+async fn detect_invoice_segments(file_path: String, state: State<'_, AppState>) -> Result<Vec<InvoiceSegment>, AppError> {
+    let pdf_parser = state.pdf_parser.lock().unwrap();
+
+    let pages = pdf_parser.extract_pages_from_pdf(std::path::Path::new(&file_path))?;
+    Ok(pdf_parser.detect_invoice_segments(&pages))
+}
+
+/// 把包含多张发票的PDF拆分为每张发票一个文件
+///
+/// `source_handling` 为空时按配置处理原文件
+#[tauri::command]
+async fn split_pdf_invoices(file_path: String, source_handling: Option<SourceHandling>, state: State<'_, AppState>) -> Result<SplitResult, AppError> {
+    let split_config = config_snapshot(&state).pdf_split;
+    let file_service = state.file_service.lock().unwrap();
+    let pdf_parser = state.pdf_parser.lock().unwrap();
+    let naming_engine = state.naming_engine.lock().unwrap();
+    let pdf_splitter = state.pdf_splitter.lock().unwrap();
+
+    let pages = pdf_parser.extract_pages_from_pdf(std::path::Path::new(&file_path))?;
+    let segments = pdf_parser.detect_invoice_segments(&pages);
+    if segments.len() < 2 {
+        return Err(AppError::validation_error(tr("文件中只有一张发票，无需拆分", "The file contains only one invoice, nothing to split")).with_path(Path::new(&file_path)));
+    }
+
+    let output_paths = pdf_splitter.split(&file_path, &segments)?;
+    let archived_path = pdf_splitter.handle_source(
+        &file_path,
+        source_handling.unwrap_or(split_config.source_handling),
+        &split_config,
+    )?;
+
+    // 拆分后的文件分别识别并生成建议文件名
+    let mut files = Vec::new();
+    for output_path in &output_paths {
+        let mut file = file_service.create_pdf_file_info(std::path::Path::new(output_path))?;
+        if let Ok(invoice_info) = pdf_parser.analyze_pdf(output_path) {
+            file.amount = invoice_info.amount;
+            file.currency = invoice_info.currency;
+            file.document_type = invoice_info.document_type;
+            file.seller = invoice_info.seller;
+            file.category = invoice_info.category;
+            file.issue_date = invoice_info.issue_date;
+        }
+        file.suggested_name = Some(naming_engine.generate_suggested_name(&file));
+        files.push(file);
+    }
+
+    // 解决重名冲突
+    if let Some(directory) = file_service.get_directory_path(&file_path) {
+        let resolved_names = naming_engine.resolve_naming_conflicts(&files);
+        let final_names = naming_engine.resolve_directory_conflicts(&directory, &resolved_names);
+        for (file, final_name) in files.iter_mut().zip(final_names) {
+            file.suggested_name = Some(final_name);
+        }
+    }
+
+    Ok(SplitResult {
+        source_path: file_path,
+        archived_path,
+        files,
+    })
+}
+
+/// 获取提取规则
+#[tauri::command]
+async fn get_extraction_rules(state: State<'_, AppState>) -> Result<RuleSet, AppError> {
+    let rule_manager = state.rule_manager.lock().unwrap();
+    Ok(rule_manager.get_rules().clone())
+}
+
+/// 更新提取规则并立即生效
+#[tauri::command]
+async fn update_extraction_rules(rule_set: RuleSet, state: State<'_, AppState>) -> Result<(), AppError> {
+    let mut rule_manager = state.rule_manager.lock().unwrap();
+    let mut pdf_parser = state.pdf_parser.lock().unwrap();
+
+    rule_manager.update_rules(rule_set)?;
+    pdf_parser.set_rule_engine(rule_manager.build_engine());
+    Ok(())
+}
+
+/// 重新加载规则文件（手动编辑 rules.json 后调用）
+#[tauri::command]
+async fn reload_extraction_rules(state: State<'_, AppState>) -> Result<RuleSet, AppError> {
+    let mut rule_manager = state.rule_manager.lock().unwrap();
+    let mut pdf_parser = state.pdf_parser.lock().unwrap();
+
+    rule_manager.reload()?;
+    pdf_parser.set_rule_engine(rule_manager.build_engine());
+    Ok(rule_manager.get_rules().clone())
+}
+
+/// 用样例文件测试单条提取规则
+#[tauri::command]
+async fn test_extraction_rule(rule: ExtractionRule, file_path: String, state: State<'_, AppState>) -> Result<RuleTestResult, AppError> {
+    let pdf_parser = state.pdf_parser.lock().unwrap();
+
+    let text = pdf_parser.extract_text_from_pdf(std::path::Path::new(&file_path))?;
+    RuleEngine::test_rule(&rule, &text)
 }
 
 /// 生成重命名预览
 #[tauri::command]
-async fn preview_rename(file_paths: Vec<String>, state: State<'_, AppState>) -> Result<Vec<RenamePreview>, String> {
+async fn preview_rename(file_paths: Vec<String>, state: State<'_, AppState>) -> Result<Vec<RenamePreview>, AppError> {
     let file_service = state.file_service.lock().unwrap();
     let pdf_parser = state.pdf_parser.lock().unwrap();
     let naming_engine = state.naming_engine.lock().unwrap();
+    let ledger = state.ledger.lock().unwrap();
     
     let mut previews = Vec::new();
     
@@ -128,59 +409,111 @@ async fn preview_rename(file_paths: Vec<String>, state: State<'_, AppState>) ->
         let file_info = match file_service.create_pdf_file_info(&std::path::Path::new(&file_path)) {
             Ok(info) => info,
             Err(e) => {
-                eprintln!("创建文件信息失败 {}: {}", file_path, e);
+                logger::warn("创建文件信息失败", &[("path", &file_path), ("error", &e)]);
                 continue;
             }
         };
         
         // 分析PDF内容
-        let amount = match pdf_parser.analyze_pdf(&file_path) {
-            Ok(invoice_info) => invoice_info.amount,
-            Err(_) => None,
+        let (amount, document_type, category) = match analyze_with_corrections(&pdf_parser, &ledger, &file_path) {
+            Ok(invoice_info) => (
+                invoice_info.amount.map(|amount| money::Money::new(amount, invoice_info.currency)),
+                invoice_info.document_type,
+                invoice_info.category,
+            ),
+            Err(_) => (None, DocumentType::Unknown, None),
         };
         
         // 生成预览
-        let suggested_name = match amount {
-            Some(amt) => naming_engine.generate_filename(amt),
-            None => "未知金额_发票.pdf".to_string(),
-        };
+        let suggested_name = naming_engine.generate_filename(amount, document_type, category.as_deref());
         
         previews.push(RenamePreview {
             original_name: file_info.name,
             suggested_name,
-            amount,
+            amount: amount.map(|money| money.amount),
+            currency: amount.map(|money| money.currency).unwrap_or_default(),
         });
     }
     
     Ok(previews)
 }
 
+/// 重命名前重新检查PDF：被排除、过大、无法读取或解析、没有识别到金额时返回原因
+fn check_renamable(file_service: &FileService, pdf_parser: &PdfParser, ledger: &Ledger, file_path: &str, filter: &FileFilterConfig) -> Result<InvoiceInfo, String> {
+    let file = file_service.scanned_pdf_file(Path::new(file_path), filter);
+    if file.status != ScanStatus::Ok {
+        return Err(file.status_detail.unwrap_or_default());
+    }
+    let invoice_info = analyze_with_corrections(pdf_parser, ledger, file_path).map_err(|e| e.to_string())?;
+    if invoice_info.amount.is_none() {
+        return Err(tr("未识别到金额", "No amount recognized").to_string());
+    }
+    Ok(invoice_info)
+}
+
 /// 执行文件重命名
+///
+/// 扫描状态不正常的PDF不会被重命名；
+/// 购买方与当前公司抬头不符的发票会被跳过，除非其路径在 `allow_buyer_mismatch` 中
 #[tauri::command]
-async fn execute_rename(renames: Vec<RenameOperation>, state: State<'_, AppState>) -> Result<RenameResult, String> {
+async fn execute_rename(renames: Vec<RenameOperation>, allow_buyer_mismatch: Option<Vec<String>>, state: State<'_, AppState>) -> Result<RenameResult, AppError> {
+    let config = config_snapshot(&state);
+    let company_profile = config.company.active().cloned();
     let file_service = state.file_service.lock().unwrap();
+    let pdf_parser = state.pdf_parser.lock().unwrap();
+    let ledger = state.ledger.lock().unwrap();
+    let invoice_validator = state.invoice_validator.lock().unwrap();
+    let allow_buyer_mismatch = allow_buyer_mismatch.unwrap_or_default();
+    let batch_id = logger::batch_id("rename");
+    let started = Instant::now();
     
     let mut success_count = 0;
     let mut failed_files = Vec::new();
     
     for rename_op in renames {
+        let is_pdf = Path::new(&rename_op.old_path).extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("pdf"));
+        if is_pdf {
+            let invoice_info = match check_renamable(&file_service, &pdf_parser, &ledger, &rename_op.old_path, &config.file_filter) {
+                Ok(invoice_info) => invoice_info,
+                Err(reason) => {
+                    failed_files.push(trf("{}: 文件未通过扫描（{}），已跳过", "{}: file did not pass the scan ({}), skipped", &[&rename_op.old_path, &reason]));
+                    continue;
+                }
+            };
+            if let Some(profile) = company_profile.as_ref().filter(|_| !allow_buyer_mismatch.contains(&rename_op.old_path)) {
+                if InvoiceValidator::has_buyer_mismatch(&invoice_validator.validate(&invoice_info, Some(profile))) {
+                    failed_files.push(trf("{}: 购买方与当前公司 {} 不符，已跳过", "{}: buyer does not match the current company {}, skipped", &[&rename_op.old_path, &profile.name]));
+                    continue;
+                }
+            }
+        }
+
         match file_service.rename_file(&rename_op.old_path, &rename_op.new_path) {
             Ok(()) => {
+                logger::debug("重命名文件", &[("batch_id", &batch_id), ("path", &rename_op.old_path), ("new_path", &rename_op.new_path)]);
                 success_count += 1;
             }
             Err(e) => {
+                logger::warn("重命名失败", &[("batch_id", &batch_id), ("path", &rename_op.old_path), ("error", &e)]);
                 failed_files.push(format!("{}: {}", rename_op.old_path, e));
             }
         }
     }
     
     let total_files = success_count + failed_files.len();
+    logger::info("重命名完成", &[
+        ("batch_id", &batch_id),
+        ("files", &total_files),
+        ("failed", &failed_files.len()),
+        ("duration_ms", &started.elapsed().as_millis()),
+    ]);
     let success = failed_files.is_empty();
     
     let message = if success {
-        format!("成功重命名 {} 个文件", success_count)
+        trf("成功重命名 {} 个文件", "Renamed {} files", &[&success_count])
     } else {
-        format!("成功重命名 {} 个文件，失败 {} 个文件", success_count, failed_files.len())
+        trf("成功重命名 {} 个文件，失败 {} 个文件", "Renamed {} files, {} failed", &[&success_count, &failed_files.len()])
     };
     
     Ok(RenameResult {
@@ -191,9 +524,551 @@ async fn execute_rename(renames: Vec<RenameOperation>, state: State<'_, AppState
     })
 }
 
+/// 预览归档整理结果
+///
+/// `archive_root` 为空时使用配置中的归档根目录；
+/// `conflict_overrides` 按目标子目录指定重名处理方式
+#[tauri::command]
+async fn preview_organize(
+    file_paths: Vec<String>,
+    archive_root: Option<String>,
+    conflict_overrides: Option<HashMap<String, ConflictResolution>>,
+    state: State<'_, AppState>,
+) -> Result<OrganizePreview, AppError> {
+    let config = config_snapshot(&state);
+    let file_service = state.file_service.lock().unwrap();
+    let pdf_parser = state.pdf_parser.lock().unwrap();
+    let naming_engine = state.naming_engine.lock().unwrap();
+    let organizer = state.organizer.lock().unwrap();
+    let ledger = state.ledger.lock().unwrap();
+
+    let archive_root = archive_root
+        .or_else(|| config.organize.archive_root.clone())
+        .ok_or_else(|| AppError::validation_error(tr("未设置归档根目录", "Archive root directory is not set")))?;
+
+    let mut files = Vec::new();
+    for file_path in &file_paths {
+        let mut file = match file_service.create_pdf_file_info(std::path::Path::new(file_path)) {
+            Ok(info) => info,
+            Err(e) => {
+                logger::warn("创建文件信息失败", &[("path", file_path), ("error", &e)]);
+                continue;
+            }
+        };
+        if let Ok(invoice_info) = analyze_with_corrections(&pdf_parser, &ledger, file_path) {
+            file.amount = invoice_info.amount;
+            file.currency = invoice_info.currency;
+            file.document_type = invoice_info.document_type;
+            file.seller = invoice_info.seller;
+            file.category = invoice_info.category;
+            file.issue_date = invoice_info.issue_date;
+        }
+        files.push(file);
+    }
+
+    let items: Vec<(&PdfFile, String)> = files.iter()
+        .map(|file| (file, naming_engine.generate_suggested_name(file)))
+        .collect();
+    Ok(organizer.plan(
+        &archive_root,
+        &items,
+        config.rename_strategy.conflict_resolution,
+        &conflict_overrides.unwrap_or_default(),
+    ))
+}
+
+/// 按归档预览移动文件
+#[tauri::command]
+async fn execute_organize(entries: Vec<OrganizeEntry>, state: State<'_, AppState>) -> Result<RenameResult, AppError> {
+    let organizer = state.organizer.lock().unwrap();
+    Ok(organizer.execute(&entries))
+}
+
+/// 获取监控到的待确认文件
+#[tauri::command]
+async fn get_watch_queue(state: State<'_, AppState>) -> Result<Vec<WatchedFile>, AppError> {
+    let watch_queue = state.watch_queue.lock().unwrap();
+    Ok(watch_queue.files().to_vec())
+}
+
+/// 确认待确认队列中的文件，按建议文件名重命名
+#[tauri::command]
+async fn approve_watched_files(paths: Vec<String>, state: State<'_, AppState>) -> Result<RenameResult, AppError> {
+    let file_service = state.file_service.lock().unwrap();
+    let mut watch_queue = state.watch_queue.lock().unwrap();
+
+    let files = watch_queue.take(&paths);
+    let mut success_count = 0;
+    let mut failed_files = Vec::new();
+    for file in &files {
+        match rename_watched_file(&file_service, &mut watch_queue, file) {
+            Ok(_) => success_count += 1,
+            Err(e) => failed_files.push(format!("{}: {}", file.path, e)),
+        }
+    }
+
+    let success = failed_files.is_empty();
+    let message = if success {
+        trf("成功重命名 {} 个文件", "Renamed {} files", &[&success_count])
+    } else {
+        trf("成功重命名 {} 个文件，失败 {} 个文件", "Renamed {} files, {} failed", &[&success_count, &failed_files.len()])
+    };
+
+    Ok(RenameResult {
+        success,
+        message,
+        processed_files: files.len(),
+        failed_files,
+    })
+}
+
+/// 从待确认队列中移除文件（不重命名）
+#[tauri::command]
+async fn dismiss_watched_files(paths: Vec<String>, state: State<'_, AppState>) -> Result<(), AppError> {
+    let mut watch_queue = state.watch_queue.lock().unwrap();
+    watch_queue.take(&paths);
+    Ok(())
+}
+
+/// 分析监控到的新文件并生成建议文件名
+fn analyze_watched_file(state: &AppState, folder: &WatchFolder, path: &Path) -> WatchedFile {
+    let company_profile = config_snapshot(state).company.active().cloned();
+    let file_service = state.file_service.lock().unwrap();
+    let pdf_parser = state.pdf_parser.lock().unwrap();
+    let naming_engine = state.naming_engine.lock().unwrap();
+    let ledger = state.ledger.lock().unwrap();
+    let invoice_validator = state.invoice_validator.lock().unwrap();
+
+    let kind = FolderWatcher::file_kind(path).unwrap_or(WatchedFileKind::Pdf);
+    let mut watched = WatchedFile {
+        path: path.to_string_lossy().to_string(),
+        folder: folder.path.clone(),
+        kind,
+        amount: None,
+        currency: money::Currency::default(),
+        document_type: DocumentType::Unknown,
+        suggested_name: String::new(),
+        buyer_mismatch: false,
+        renamed_path: None,
+        error: None,
+    };
+
+    match kind {
+        WatchedFileKind::Image => match file_service.create_image_file_info(path) {
+            Ok(image) => {
+                watched.suggested_name = naming_engine.generate_image_filename(&image.name, &image.modified);
+            }
+            Err(e) => watched.error = Some(e.to_string()),
+        },
+        WatchedFileKind::Pdf => match analyze_with_corrections(&pdf_parser, &ledger, &watched.path) {
+            Ok(invoice_info) => {
+                let amount = invoice_info.amount.map(|amount| money::Money::new(amount, invoice_info.currency));
+                watched.buyer_mismatch = InvoiceValidator::has_buyer_mismatch(
+                    &invoice_validator.validate(&invoice_info, company_profile.as_ref()),
+                );
+                watched.amount = invoice_info.amount;
+                watched.currency = invoice_info.currency;
+                watched.document_type = invoice_info.document_type;
+                watched.suggested_name = naming_engine.generate_filename(amount, invoice_info.document_type, invoice_info.category.as_deref());
+            }
+            Err(e) => {
+                watched.suggested_name = naming_engine.generate_filename(None, DocumentType::Unknown, None);
+                watched.error = Some(e.to_string());
+            }
+        },
+    }
+
+    watched
+}
+
+/// 把监控到的文件重命名为建议文件名，返回新路径
+fn rename_watched_file(file_service: &FileService, watch_queue: &mut WatchQueue, file: &WatchedFile) -> AppResult<String> {
+    let path = Path::new(&file.path);
+    let directory = path.parent()
+        .ok_or_else(|| AppError::file_system_error(tr("无法获取文件所在目录", "Unable to determine the file's directory")).with_path(path))?;
+
+    // 已经是建议文件名时无需重命名
+    if path.file_name().is_some_and(|name| name.to_string_lossy() == file.suggested_name) {
+        return Ok(file.path.clone());
+    }
+
+    let filename = file_service.resolve_filename_conflict(&directory.to_string_lossy(), &file.suggested_name);
+    let new_path = directory.join(filename);
+    let new_path_str = new_path.to_string_lossy().to_string();
+
+    // 重命名会产生新的文件事件，提前标记以免重复处理
+    watch_queue.mark_produced(&new_path);
+    file_service.rename_file(&file.path, &new_path_str)?;
+    Ok(new_path_str)
+}
+
+/// 处理监控到的新文件：加入待确认队列或按规则自动重命名，并通知前端
+fn handle_watched_file(app: &AppHandle, folder: &WatchFolder, path: &Path) {
+    let state = app.state::<AppState>();
+    if state.watch_queue.lock().unwrap().is_produced(path) {
+        return;
+    }
+
+    let mut watched = analyze_watched_file(&state, folder, path);
+
+    // 识别失败、没有金额或购买方不符的PDF不自动重命名，交给用户确认
+    let auto_rename = folder.mode == WatchMode::AutoRename
+        && watched.error.is_none()
+        && !watched.buyer_mismatch
+        && (watched.kind == WatchedFileKind::Image || watched.amount.is_some());
+
+    let event = if auto_rename {
+        let file_service = state.file_service.lock().unwrap();
+        let mut watch_queue = state.watch_queue.lock().unwrap();
+        match rename_watched_file(&file_service, &mut watch_queue, &watched) {
+            Ok(new_path) => {
+                watched.renamed_path = Some(new_path);
+                EVENT_FILE_RENAMED
+            }
+            Err(e) => {
+                watched.error = Some(e.to_string());
+                EVENT_FILE_FAILED
+            }
+        }
+    } else {
+        state.watch_queue.lock().unwrap().push(watched.clone());
+        EVENT_FILE_QUEUED
+    };
+
+    if let Err(e) = app.emit(event, &watched) {
+        logger::warn("发送监控事件失败", &[("path", &watched.path), ("error", &e)]);
+    }
+}
+
+/// 按配置启动或停止文件夹监控
+fn restart_folder_watcher(app: &AppHandle, config: &AppConfig, folder_watcher: &mut FolderWatcher) -> AppResult<()> {
+    if !config.ui_config.auto_refresh || config.watch.folders.is_empty() {
+        folder_watcher.stop();
+        return Ok(());
+    }
+
+    let handle = app.clone();
+    folder_watcher.start(&config.watch, move |folder, path| handle_watched_file(&handle, folder, path))
+}
+
+/// 从配置的邮件来源导入发票附件和链接文件到暂存目录
+///
+/// `source_index` 为空时导入所有来源
+#[tauri::command]
+async fn ingest_mail(source_index: Option<usize>, state: State<'_, AppState>) -> Result<IngestReport, AppError> {
+    let config = config_snapshot(&state).mail_ingest;
+    let staging = config.staging_directory.as_ref()
+        .ok_or_else(|| AppError::validation_error(tr("未设置邮件暂存目录", "Mail staging directory is not set")))?;
+    let sources: Vec<&MailSourceConfig> = match source_index {
+        Some(index) => vec![config.sources.get(index).ok_or_else(|| AppError::validation_error(tr("邮件来源不存在", "Mail source does not exist")))?],
+        None => config.sources.iter().collect(),
+    };
+
+    // 收取邮件和下载期间不持有锁，在台账副本上查重，结束后再合并写回
+    let mut pending = state.ledger.lock().unwrap().clone();
+    let mut report = IngestReport::default();
+    for source in sources {
+        match state.mail_ingestor.ingest(source, &config, Path::new(staging), &mut pending) {
+            Ok(source_report) => report.merge(source_report),
+            Err(e) => report.errors.push(e.to_string()),
+        }
+    }
+
+    let mut ledger = state.ledger.lock().unwrap();
+    ledger.merge_new(&pending);
+    ledger.save()?;
+    Ok(report)
+}
+
+/// 获取台账中的全部记录
+#[tauri::command]
+async fn get_ledger_entries(state: State<'_, AppState>) -> Result<Vec<LedgerEntry>, AppError> {
+    let ledger = state.ledger.lock().unwrap();
+    Ok(ledger.entries().cloned().collect())
+}
+
+/// 按费用类别统计台账中的发票
+#[tauri::command]
+async fn get_category_stats(state: State<'_, AppState>) -> Result<Vec<CategoryStats>, AppError> {
+    let ledger = state.ledger.lock().unwrap();
+    Ok(ledger.category_stats())
+}
+
+/// 保存文件的人工修正（按内容哈希，改名后仍有效），`correction` 为空时清除
+#[tauri::command]
+async fn save_invoice_correction(file_path: String, correction: Option<InvoiceCorrection>, state: State<'_, AppState>) -> Result<LedgerEntry, AppError> {
+    let mut ledger = state.ledger.lock().unwrap();
+    let entry = ledger.set_correction(Path::new(&file_path), correction)?;
+    ledger.save()?;
+    Ok(entry)
+}
+
+/// 输入加密PDF的打开密码，密码正确时本次运行中不再需要输入
+///
+/// `remember` 为真时保存到配置：邮件导入的文件按发件人保存，其余文件的密码适用于所有文件
+#[tauri::command]
+async fn unlock_pdf(file_path: String, password: String, remember: bool, state: State<'_, AppState>) -> Result<(), AppError> {
+    let path = Path::new(&file_path);
+    state.pdf_parser.lock().unwrap().unlock_pdf(path, &password)?;
+    if !remember {
+        return Ok(());
+    }
+
+    let sender = state.ledger.lock().unwrap().entry_for(path)
+        .and_then(|entry| entry.mail.as_ref())
+        .map(|mail| mail.sender.clone())
+        .unwrap_or_default();
+    let pdf_passwords = {
+        let mut config_manager = state.config_manager.lock().unwrap();
+        let mut config = config_manager.get_config().clone();
+        config.pdf_passwords.remember(&sender, &password);
+        config_manager.update_config(config)?;
+        config_manager.get_config().pdf_passwords.clone()
+    };
+    state.pdf_parser.lock().unwrap().set_pdf_passwords(&pdf_passwords);
+    Ok(())
+}
+
+/// 根据文件的人工修正金额建议一条提取规则，规则需要用户确认后再保存
+#[tauri::command]
+async fn suggest_rule_from_correction(file_path: String, state: State<'_, AppState>) -> Result<Option<ExtractionRule>, AppError> {
+    let pdf_parser = state.pdf_parser.lock().unwrap();
+    let ledger = state.ledger.lock().unwrap();
+
+    let amount = ledger.correction_for(Path::new(&file_path))
+        .and_then(|correction| correction.amount)
+        .ok_or_else(|| AppError::validation_error(tr("该文件没有人工修正的金额", "The file has no manually corrected amount")).with_path(Path::new(&file_path)))?;
+    let invoice_info = analyze_with_corrections(&pdf_parser, &ledger, &file_path)?;
+    let text = pdf_parser.extract_text_from_pdf(Path::new(&file_path))?;
+    Ok(RuleEngine::suggest_amount_rule(&text, amount, invoice_info.seller.as_deref()))
+}
+
+/// 生成报销包ZIP（重命名后的发票和支付凭证、汇总表、合并打印PDF和校验清单）
+#[tauri::command]
+async fn build_reimbursement_package(pdf_paths: Vec<String>, image_paths: Vec<String>, output_path: String, state: State<'_, AppState>) -> Result<PackageResult, AppError> {
+    let file_service = state.file_service.lock().unwrap();
+    let pdf_parser = state.pdf_parser.lock().unwrap();
+    let naming_engine = state.naming_engine.lock().unwrap();
+    let package_builder = state.package_builder.lock().unwrap();
+    let ledger = state.ledger.lock().unwrap();
+
+    let mut items = Vec::new();
+    for pdf_path in &pdf_paths {
+        let mut file = file_service.create_pdf_file_info(Path::new(pdf_path))?;
+        let mut line_items = Vec::new();
+        if let Ok(invoice_info) = analyze_with_corrections(&pdf_parser, &ledger, pdf_path) {
+            file.amount = invoice_info.amount;
+            file.currency = invoice_info.currency;
+            file.document_type = invoice_info.document_type;
+            file.seller = invoice_info.seller;
+            file.category = invoice_info.category;
+            file.issue_date = invoice_info.issue_date;
+            line_items = invoice_info.line_items;
+        }
+        items.push(PackageItem {
+            package_name: naming_engine.generate_suggested_name(&file),
+            source_path: file.path,
+            kind: PackageItemKind::Invoice,
+            amount: file.amount,
+            currency: file.currency,
+            document_type: file.document_type,
+            seller: file.seller,
+            issue_date: file.issue_date,
+            category: file.category,
+            line_items,
+        });
+    }
+    for image_path in &image_paths {
+        let image = file_service.create_image_file_info(Path::new(image_path))?;
+        items.push(PackageItem {
+            package_name: naming_engine.generate_image_filename(&image.name, &image.modified),
+            source_path: image.path,
+            kind: PackageItemKind::PaymentProof,
+            amount: None,
+            currency: money::Currency::default(),
+            document_type: DocumentType::Unknown,
+            seller: None,
+            issue_date: None,
+            category: None,
+            line_items: Vec::new(),
+        });
+    }
+
+    package_builder.build(&items, Path::new(&output_path))
+}
+
+/// 为发票匹配支付凭证截图，返回排序后的建议和未匹配项
+///
+/// `proof_hints` 为用户填写的凭证金额、日期或商户，优先于从文件名识别的结果
+#[tauri::command]
+async fn match_payment_proofs(pdf_paths: Vec<String>, image_paths: Vec<String>, proof_hints: Option<Vec<PaymentProof>>, state: State<'_, AppState>) -> Result<MatchResult, AppError> {
+    let file_service = state.file_service.lock().unwrap();
+    let pdf_parser = state.pdf_parser.lock().unwrap();
+    let payment_matcher = state.payment_matcher.lock().unwrap();
+    let ledger = state.ledger.lock().unwrap();
+
+    let mut invoices = Vec::new();
+    for pdf_path in &pdf_paths {
+        let file = file_service.create_pdf_file_info(Path::new(pdf_path))?;
+        let invoice_info = analyze_with_corrections(&pdf_parser, &ledger, pdf_path).ok();
+        invoices.push(InvoiceCandidate {
+            path: file.path,
+            amount: invoice_info.as_ref().and_then(|info| info.amount),
+            currency: invoice_info.as_ref().map(|info| info.currency).unwrap_or_default(),
+            date: invoice_info.as_ref().and_then(|info| info.issue_date).or(Some(file.modified.date_naive())),
+            seller: invoice_info.and_then(|info| info.seller),
+        });
+    }
+
+    let proof_hints = proof_hints.unwrap_or_default();
+    let mut proofs = Vec::new();
+    for image_path in &image_paths {
+        let image = file_service.create_image_file_info(Path::new(image_path))?;
+        let mut proof = payment_matcher.proof_from_filename(&image.path, Some(image.modified.date_naive()));
+        if let Some(hint) = proof_hints.iter().find(|hint| hint.path == *image_path) {
+            proof.amount = hint.amount.or(proof.amount);
+            proof.date = hint.date.or(proof.date);
+            proof.merchant = hint.merchant.clone().or(proof.merchant);
+        }
+        proofs.push(proof);
+    }
+
+    Ok(payment_matcher.match_all(&invoices, &proofs))
+}
+
+/// 导入银行、信用卡、支付宝或微信账单，`format` 为空时自动识别
+#[tauri::command]
+async fn import_bank_statement(statement_path: String, format: Option<StatementFormat>, state: State<'_, AppState>) -> Result<Statement, AppError> {
+    let statement_importer = state.statement_importer.lock().unwrap();
+    statement_importer.import(Path::new(&statement_path), format)
+}
+
+/// 账单与发票对账
+///
+/// 指定 `pdf_paths` 时解析这些发票，否则使用台账中已扫描的发票
+#[tauri::command]
+async fn reconcile_statement(statement_path: String, format: Option<StatementFormat>, pdf_paths: Option<Vec<String>>, date_window_days: Option<i64>, state: State<'_, AppState>) -> Result<ReconciliationReport, AppError> {
+    let statement_importer = state.statement_importer.lock().unwrap();
+    let reconciler = state.reconciler.lock().unwrap();
+    let statement = statement_importer.import(Path::new(&statement_path), format)?;
+
+    let invoices: Vec<ReconcileInvoice> = match pdf_paths {
+        Some(pdf_paths) => {
+            let file_service = state.file_service.lock().unwrap();
+            let pdf_parser = state.pdf_parser.lock().unwrap();
+            let ledger = state.ledger.lock().unwrap();
+            let mut invoices = Vec::new();
+            for pdf_path in &pdf_paths {
+                let file = file_service.create_pdf_file_info(Path::new(pdf_path))?;
+                let invoice_info = analyze_with_corrections(&pdf_parser, &ledger, pdf_path).ok();
+                invoices.push(ReconcileInvoice {
+                    path: file.path,
+                    file_name: file.name,
+                    amount: invoice_info.as_ref().and_then(|info| info.amount),
+                    currency: invoice_info.as_ref().map(|info| info.currency).unwrap_or_default(),
+                    issue_date: invoice_info.as_ref().and_then(|info| info.issue_date),
+                    seller: invoice_info.as_ref().and_then(|info| info.seller.clone()),
+                    category: invoice_info.and_then(|info| info.category),
+                });
+            }
+            invoices
+        }
+        None => {
+            let ledger = state.ledger.lock().unwrap();
+            ledger.entries()
+                .filter_map(|entry| {
+                    let invoice = entry.invoice.as_ref()?;
+                    Some(ReconcileInvoice {
+                        path: entry.path.clone(),
+                        file_name: entry.file_name.clone(),
+                        amount: invoice.amount,
+                        currency: invoice.currency,
+                        issue_date: invoice.issue_date,
+                        seller: invoice.seller.clone(),
+                        category: invoice.category.clone(),
+                    })
+                })
+                .collect()
+        }
+    };
+
+    Ok(reconciler.reconcile(&invoices, &statement.lines, date_window_days.unwrap_or(DEFAULT_DATE_WINDOW_DAYS)))
+}
+
+/// 导出对账报告（.xlsx 或 .csv）
+#[tauri::command]
+async fn export_reconciliation_report(report: ReconciliationReport, output_path: String, state: State<'_, AppState>) -> Result<(), AppError> {
+    let reconciler = state.reconciler.lock().unwrap();
+    reconciler.export(&report, Path::new(&output_path))
+}
+
+/// 按确认的配对重命名发票和支付凭证，两者使用相同的序号前缀
+#[tauri::command]
+async fn apply_payment_pairs(pairs: Vec<ConfirmedPair>, state: State<'_, AppState>) -> Result<RenameResult, AppError> {
+    let file_service = state.file_service.lock().unwrap();
+    let pdf_parser = state.pdf_parser.lock().unwrap();
+    let naming_engine = state.naming_engine.lock().unwrap();
+    let ledger = state.ledger.lock().unwrap();
+
+    let mut success_count = 0;
+    let mut failed_files = Vec::new();
+
+    for (index, pair) in pairs.iter().enumerate() {
+        let mut invoice = match file_service.create_pdf_file_info(Path::new(&pair.invoice_path)) {
+            Ok(invoice) => invoice,
+            Err(e) => {
+                failed_files.push(format!("{}: {}", pair.invoice_path, e));
+                continue;
+            }
+        };
+        if let Ok(invoice_info) = analyze_with_corrections(&pdf_parser, &ledger, &pair.invoice_path) {
+            invoice.amount = invoice_info.amount;
+            invoice.currency = invoice_info.currency;
+            invoice.document_type = invoice_info.document_type;
+            invoice.category = invoice_info.category;
+        }
+        let invoice_name = naming_engine.with_pair_prefix(index + 1, &naming_engine.generate_suggested_name(&invoice));
+
+        let proof_name = match file_service.create_image_file_info(Path::new(&pair.proof_path)) {
+            Ok(image) => naming_engine.with_pair_prefix(index + 1, &naming_engine.generate_image_filename(&image.name, &image.modified)),
+            Err(e) => {
+                failed_files.push(format!("{}: {}", pair.proof_path, e));
+                continue;
+            }
+        };
+
+        for (old_path, new_name) in [(&pair.invoice_path, invoice_name), (&pair.proof_path, proof_name)] {
+            let Some(directory) = file_service.get_directory_path(old_path) else {
+                failed_files.push(format!("{}: {}", old_path, tr("无法获取文件所在目录", "Unable to determine the file's directory")));
+                continue;
+            };
+            let new_name = file_service.resolve_filename_conflict(&directory, &new_name);
+            let new_path = Path::new(&directory).join(new_name).to_string_lossy().to_string();
+            match file_service.rename_file(old_path, &new_path) {
+                Ok(()) => success_count += 1,
+                Err(e) => failed_files.push(format!("{}: {}", old_path, e)),
+            }
+        }
+    }
+
+    let success = failed_files.is_empty();
+    let message = if success {
+        trf("成功重命名 {} 个文件", "Renamed {} files", &[&success_count])
+    } else {
+        trf("成功重命名 {} 个文件，失败 {} 个文件", "Renamed {} files, {} failed", &[&success_count, &failed_files.len()])
+    };
+
+    Ok(RenameResult {
+        success,
+        message,
+        processed_files: success_count + failed_files.len(),
+        failed_files,
+    })
+}
+
 /// 选择目录
 #[tauri::command]
-async fn select_directory(state: State<'_, AppState>) -> Result<String, String> {
+async fn select_directory(state: State<'_, AppState>) -> Result<String, AppError> {
     let directory_utils = state.directory_utils.lock().unwrap();
     let directory = directory_utils.get_current_directory()?;
     Ok(directory)
@@ -201,80 +1076,176 @@ async fn select_directory(state: State<'_, AppState>) -> Result<String, String>
 
 /// 获取应用配置
 #[tauri::command]
-async fn get_config(state: State<'_, AppState>) -> Result<AppConfig, String> {
+async fn get_config(state: State<'_, AppState>) -> Result<AppConfig, AppError> {
     let config_manager = state.config_manager.lock().unwrap();
     Ok(config_manager.get_config().clone())
 }
 
 /// 更新应用配置
 #[tauri::command]
-async fn update_config(new_config: AppConfig, state: State<'_, AppState>) -> Result<(), String> {
-    let mut config_manager = state.config_manager.lock().unwrap();
-    config_manager.update_config(new_config)
-        .map_err(|e| e.to_string())
+async fn update_config(new_config: AppConfig, app: AppHandle, state: State<'_, AppState>) -> Result<(), AppError> {
+    let config = {
+        let mut config_manager = state.config_manager.lock().unwrap();
+        config_manager.update_config(new_config)?;
+        config_manager.get_config().clone()
+    };
+    apply_config(&app, &state, &config)
 }
 
 /// 重置配置为默认值
 #[tauri::command]
-async fn reset_config(state: State<'_, AppState>) -> Result<(), String> {
-    let mut config_manager = state.config_manager.lock().unwrap();
-    config_manager.reset_to_default()
-        .map_err(|e| e.to_string())
+async fn reset_config(app: AppHandle, state: State<'_, AppState>) -> Result<(), AppError> {
+    let config = {
+        let mut config_manager = state.config_manager.lock().unwrap();
+        config_manager.reset_to_default()?;
+        config_manager.get_config().clone()
+    };
+    apply_config(&app, &state, &config)
+}
+
+/// 读取最近的日志，新的在前
+#[tauri::command]
+async fn read_logs(limit: Option<usize>, min_level: Option<LogLevel>) -> Result<Vec<LogEntry>, AppError> {
+    Ok(logger::recent_entries(limit.unwrap_or(200), min_level.unwrap_or(LogLevel::Debug)))
 }
 
 /// 验证目录权限
 #[tauri::command]
-async fn validate_directory(directory: String, state: State<'_, AppState>) -> Result<bool, String> {
+async fn validate_directory(directory: String, state: State<'_, AppState>) -> Result<bool, AppError> {
     let directory_utils = state.directory_utils.lock().unwrap();
     Ok(directory_utils.is_directory_writable(&directory))
 }
 
 /// 验证目录是否有效
 #[tauri::command]
-async fn validate_directory_exists(directory: String, state: State<'_, AppState>) -> Result<bool, String> {
+async fn validate_directory_exists(directory: String, state: State<'_, AppState>) -> Result<bool, AppError> {
     let directory_utils = state.directory_utils.lock().unwrap();
     Ok(directory_utils.is_directory_valid(&directory))
 }
 
 /// 获取目录的父目录
 #[tauri::command]
-async fn get_parent_directory(directory: String, state: State<'_, AppState>) -> Result<Option<String>, String> {
+async fn get_parent_directory(directory: String, state: State<'_, AppState>) -> Result<Option<String>, AppError> {
     let directory_utils = state.directory_utils.lock().unwrap();
     Ok(directory_utils.get_parent_directory(&directory))
 }
 
 /// 规范化目录路径
 #[tauri::command]
-async fn normalize_directory_path(path: String, state: State<'_, AppState>) -> Result<String, String> {
+async fn normalize_directory_path(path: String, state: State<'_, AppState>) -> Result<String, AppError> {
     let directory_utils = state.directory_utils.lock().unwrap();
     Ok(directory_utils.normalize_path(&path))
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // 日志写入配置目录下的 logs 目录，先于配置初始化以记录配置迁移和恢复
+    match ConfigManager::get_config_dir() {
+        Ok(config_dir) => logger::init(Logger::new(&config_dir.join("logs"), false)),
+        Err(e) => logger::error("无法初始化日志目录", &[("error", &e)]),
+    }
+
+    // 加载配置，失败时使用默认配置继续运行
+    let config_manager = ConfigManager::new().unwrap_or_else(|e| {
+        logger::error("加载配置失败，使用默认配置", &[("error", &e)]);
+        ConfigManager::unsaved_default()
+    });
+    logger::set_detailed(config_manager.get_config().ui_config.show_detailed_logs);
+    i18n::set_language(config_manager.get_config().ui_config.language);
+
+    // 加载提取规则
+    let rule_manager = ExtractionRuleManager::new().unwrap_or_else(|e| {
+        logger::error("加载提取规则失败，使用默认规则", &[("error", &e)]);
+        ExtractionRuleManager::unsaved_default()
+    });
+    let mut pdf_parser = PdfParser::new();
+    pdf_parser.set_rule_engine(rule_manager.build_engine());
+
+    // 把文件名模板交给命名引擎
+    let mut naming_engine = NamingEngine::new();
+    naming_engine.set_filename_template(&config_manager.get_config().rename_strategy.filename_template);
+    naming_engine.set_language(config_manager.get_config().ui_config.language);
+    let mut organizer = Organizer::new();
+    let ledger = Ledger::new().unwrap_or_else(|e| {
+        logger::error("加载处理台账失败，使用空台账", &[("error", &e)]);
+        Ledger::unsaved()
+    });
+    organizer.set_directory_template(&config_manager.get_config().organize.directory_template);
+    pdf_parser.set_category_rules(&config_manager.get_config().categories.rules);
+    pdf_parser.set_pdf_passwords(&config_manager.get_config().pdf_passwords);
+
     // 初始化应用状态
     let app_state = AppState {
-        config_manager: Mutex::new(ConfigManager::new().expect("无法初始化配置管理器")),
-        pdf_parser: Mutex::new(PdfParser::new()),
+        config_manager: Mutex::new(config_manager),
+        pdf_parser: Mutex::new(pdf_parser),
         file_service: Mutex::new(FileService::new()),
-        naming_engine: Mutex::new(NamingEngine::new()),
+        naming_engine: Mutex::new(naming_engine),
         directory_utils: Mutex::new(DirectoryUtils::new()),
+        rule_manager: Mutex::new(rule_manager),
+        pdf_splitter: Mutex::new(PdfSplitter::new()),
+        organizer: Mutex::new(organizer),
+        folder_watcher: Mutex::new(FolderWatcher::new()),
+        watch_queue: Mutex::new(WatchQueue::new()),
+        ledger: Mutex::new(ledger),
+        mail_ingestor: MailIngestor::new(),
+        package_builder: Mutex::new(PackageBuilder::new()),
+        payment_matcher: Mutex::new(PaymentMatcher::new()),
+        statement_importer: Mutex::new(StatementImporter::new()),
+        reconciler: Mutex::new(Reconciler::new()),
+        invoice_validator: Mutex::new(InvoiceValidator::new()),
     };
     
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .manage(app_state)
+        .setup(|app| {
+            // 按配置启动文件夹监控
+            let state = app.state::<AppState>();
+            let config = config_snapshot(&state);
+            let mut folder_watcher = state.folder_watcher.lock().unwrap();
+            if let Err(e) = restart_folder_watcher(app.handle(), &config, &mut folder_watcher) {
+                logger::error("启动文件夹监控失败", &[("error", &e)]);
+            }
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             greet,
             scan_pdf_files,
             scan_image_files,
             analyze_pdf_content,
+            get_pdf_layout,
+            explain_pdf_fields,
+            evaluate_parser_corpus,
+            detect_invoice_segments,
+            split_pdf_invoices,
+            get_extraction_rules,
+            update_extraction_rules,
+            reload_extraction_rules,
+            test_extraction_rule,
             preview_rename,
             execute_rename,
+            preview_organize,
+            execute_organize,
+            get_watch_queue,
+            approve_watched_files,
+            dismiss_watched_files,
+            ingest_mail,
+            get_ledger_entries,
+            get_category_stats,
+            save_invoice_correction,
+            unlock_pdf,
+            suggest_rule_from_correction,
+            build_reimbursement_package,
+            match_payment_proofs,
+            apply_payment_pairs,
+            import_bank_statement,
+            reconcile_statement,
+            export_reconciliation_report,
             select_directory,
             get_config,
             update_config,
             reset_config,
+            read_logs,
             validate_directory,
             validate_directory_exists,
             get_parent_directory,
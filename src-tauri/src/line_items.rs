@@ -0,0 +1,196 @@
+use crate::money::{parse_amount_number, Amount};
+use crate::pdf_layout::{PageLayout, TextRun};
+use crate::types::LineItem;
+
+/// 明细表的列
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Column {
+    Name,
+    Specification,
+    Unit,
+    Quantity,
+    UnitPrice,
+    Amount,
+    TaxRate,
+    Tax,
+}
+
+/// 表头关键词（按顺序匹配，去掉空白后比较）
+const HEADER_KEYWORDS: &[(&str, Column)] = &[
+    ("名称", Column::Name),
+    ("规格", Column::Specification),
+    ("单位", Column::Unit),
+    ("数量", Column::Quantity),
+    ("单价", Column::UnitPrice),
+    ("税额", Column::Tax),
+    ("金额", Column::Amount),
+    ("税率", Column::TaxRate),
+    ("征收率", Column::TaxRate),
+];
+
+/// 表头至少要识别出的列数
+const MIN_HEADER_COLUMNS: usize = 4;
+
+fn normalize(text: &str) -> String {
+    text.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+fn header_column(run: &TextRun) -> Option<Column> {
+    let text = normalize(&run.text);
+    HEADER_KEYWORDS.iter()
+        .find(|(keyword, _)| text.contains(keyword))
+        .map(|(_, column)| *column)
+}
+
+/// 解析明细中的金额，支持折扣行的负数
+fn parse_signed_amount(text: &str) -> Option<Amount> {
+    let text = normalize(text);
+    let digits = text.trim_start_matches(['-', '¥', '￥']);
+    let amount = parse_amount_number(digits)?;
+    Some(if text.starts_with('-') { Amount::ZERO - amount } else { amount })
+}
+
+/// 按文字位置识别"货物或应税劳务、服务名称"明细表
+///
+/// 先找到包含名称、金额等列名的表头行，以相邻表头中心的中点作为列边界，
+/// 把表头下方到"合计"行之间的文字按列归位；没有金额的行视为上一行名称的续行。
+pub fn extract_line_items(pages: &[PageLayout]) -> Vec<LineItem> {
+    let mut items = Vec::new();
+
+    for page in pages {
+        let lines = page.lines();
+        let Some((header_index, mut columns)) = lines.iter().enumerate().find_map(|(index, line)| {
+            let columns: Vec<(f64, Column)> = line.iter()
+                .filter_map(|run| header_column(run).map(|column| (run.center_x(), column)))
+                .collect();
+            let has = |wanted: Column| columns.iter().any(|(_, column)| *column == wanted);
+            (columns.len() >= MIN_HEADER_COLUMNS && has(Column::Name) && has(Column::Amount)).then_some((index, columns))
+        }) else {
+            continue;
+        };
+        columns.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        // 第 i 列的右边界为第 i 列与第 i+1 列表头中心的中点
+        let boundaries: Vec<f64> = columns.windows(2).map(|pair| (pair[0].0 + pair[1].0) / 2.0).collect();
+        let column_of = |run: &TextRun| {
+            let center = run.center_x();
+            let index = boundaries.iter().position(|boundary| center < *boundary).unwrap_or(columns.len() - 1);
+            columns[index].1
+        };
+
+        for line in &lines[header_index + 1..] {
+            let text = normalize(&line.iter().map(|run| run.text.as_str()).collect::<String>());
+            if text.contains("合计") {
+                break;
+            }
+
+            let mut cells: Vec<(Column, String)> = Vec::new();
+            for run in line {
+                let column = column_of(run);
+                match cells.iter_mut().find(|(existing, _)| *existing == column) {
+                    Some((_, value)) => {
+                        value.push(' ');
+                        value.push_str(&run.text);
+                    }
+                    None => cells.push((column, run.text.clone())),
+                }
+            }
+            let cell = |wanted: Column| {
+                cells.iter()
+                    .find(|(column, _)| *column == wanted)
+                    .map(|(_, value)| value.trim().to_string())
+                    .filter(|value| !value.is_empty())
+            };
+
+            match cell(Column::Amount).and_then(|value| parse_signed_amount(&value)) {
+                Some(amount) => items.push(LineItem {
+                    name: cell(Column::Name).unwrap_or_default(),
+                    specification: cell(Column::Specification),
+                    unit: cell(Column::Unit),
+                    quantity: cell(Column::Quantity),
+                    unit_price: cell(Column::UnitPrice),
+                    amount: Some(amount),
+                    tax_rate: cell(Column::TaxRate),
+                    tax_amount: cell(Column::Tax).and_then(|value| parse_signed_amount(&value)),
+                }),
+                // 名称过长时换行显示，续行只有名称或规格
+                None => {
+                    if let (Some(item), Some(name)) = (items.last_mut(), cell(Column::Name)) {
+                        item.name.push_str(&name);
+                    }
+                    if let (Some(item), Some(specification)) = (items.last_mut(), cell(Column::Specification)) {
+                        match item.specification.as_mut() {
+                            Some(existing) => existing.push_str(&specification),
+                            None => item.specification = Some(specification),
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(x: f64, y: f64, text: &str) -> TextRun {
+        TextRun {
+            page: 1,
+            x,
+            y,
+            width: text.chars().count() as f64 * 9.0,
+            font_size: 9.0,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_extract_line_items() {
+        let page = PageLayout {
+            page: 1,
+            width: 595.0,
+            height: 842.0,
+            runs: vec![
+                run(20.0, 200.0, "项目名称"),
+                run(150.0, 200.0, "规格型号"),
+                run(230.0, 200.0, "单位"),
+                run(280.0, 200.0, "数量"),
+                run(330.0, 200.0, "单价"),
+                run(400.0, 200.0, "金额"),
+                run(460.0, 200.0, "税率"),
+                run(520.0, 200.0, "税额"),
+                run(20.0, 220.0, "*餐饮服务*餐费"),
+                run(232.0, 220.0, "次"),
+                run(285.0, 220.0, "1"),
+                run(325.0, 220.0, "300.00"),
+                run(395.0, 220.0, "300.00"),
+                run(462.0, 220.0, "6%"),
+                run(515.0, 220.0, "18.00"),
+                run(20.0, 235.0, "*办公用品*打印纸A4"),
+                run(150.0, 235.0, "70g"),
+                run(395.0, 235.0, "-10.00"),
+                run(462.0, 235.0, "13%"),
+                run(515.0, 235.0, "-1.30"),
+                run(20.0, 247.0, "（500张/包）"),
+                run(20.0, 270.0, "合 计"),
+                run(395.0, 270.0, "¥290.00"),
+            ],
+        };
+
+        let items = extract_line_items(&[page]);
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].name, "*餐饮服务*餐费");
+        assert_eq!(items[0].unit.as_deref(), Some("次"));
+        assert_eq!(items[0].quantity.as_deref(), Some("1"));
+        assert_eq!(items[0].unit_price.as_deref(), Some("300.00"));
+        assert_eq!(items[0].amount, Some(Amount::from_cents(30000)));
+        assert_eq!(items[0].tax_rate.as_deref(), Some("6%"));
+        assert_eq!(items[0].tax_amount, Some(Amount::from_cents(1800)));
+        assert_eq!(items[1].name, "*办公用品*打印纸A4（500张/包）");
+        assert_eq!(items[1].specification.as_deref(), Some("70g"));
+        assert_eq!(items[1].amount, Some(Amount::ZERO - Amount::from_cents(1000)));
+    }
+}
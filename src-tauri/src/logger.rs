@@ -0,0 +1,236 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// 当前日志文件名，轮转后为 invoicepilot.1.log、invoicepilot.2.log …（数字越大越旧）
+const LOG_FILE_STEM: &str = "invoicepilot";
+
+/// 单个日志文件的大小上限
+const MAX_LOG_FILE_SIZE: u64 = 1024 * 1024;
+
+/// 保留的轮转日志文件数
+const MAX_ROTATED_FILES: usize = 5;
+
+/// 日志级别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum LogLevel {
+    /// 详细日志（逐个文件的耗时等），只在开启详细日志时记录
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+/// 一条日志，日志文件中每行一条 JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogEntry {
+    pub timestamp: DateTime<Utc>,
+    pub level: LogLevel,
+    pub message: String,
+    /// 结构化字段，如 path、batch_id、duration_ms
+    #[serde(default)]
+    pub fields: BTreeMap<String, String>,
+}
+
+/// 写入配置目录下的轮转日志文件
+pub struct Logger {
+    log_dir: PathBuf,
+    /// 是否记录 Debug 级别（界面配置中的 show_detailed_logs）
+    detailed: bool,
+    max_file_size: u64,
+    max_rotated_files: usize,
+}
+
+impl Logger {
+    pub fn new(log_dir: &Path, detailed: bool) -> Self {
+        Logger {
+            log_dir: log_dir.to_path_buf(),
+            detailed,
+            max_file_size: MAX_LOG_FILE_SIZE,
+            max_rotated_files: MAX_ROTATED_FILES,
+        }
+    }
+
+    /// 开启或关闭详细日志
+    pub fn set_detailed(&mut self, detailed: bool) {
+        self.detailed = detailed;
+    }
+
+    /// 第 `index` 个日志文件，0 为当前文件
+    fn file_path(&self, index: usize) -> PathBuf {
+        if index == 0 {
+            self.log_dir.join(format!("{}.log", LOG_FILE_STEM))
+        } else {
+            self.log_dir.join(format!("{}.{}.log", LOG_FILE_STEM, index))
+        }
+    }
+
+    /// 追加一条日志，当前文件超过大小上限时先轮转
+    pub fn write(&self, entry: &LogEntry) -> Result<(), String> {
+        if entry.level == LogLevel::Debug && !self.detailed {
+            return Ok(());
+        }
+
+        let line = serde_json::to_string(entry)
+            .map_err(|e| format!("序列化日志失败: {}", e))?;
+        fs::create_dir_all(&self.log_dir)
+            .map_err(|e| format!("无法创建日志目录: {}", e))?;
+        let current = self.file_path(0);
+        let size = fs::metadata(&current).map(|metadata| metadata.len()).unwrap_or(0);
+        if size > 0 && size + line.len() as u64 + 1 > self.max_file_size {
+            self.rotate().map_err(|e| format!("日志轮转失败: {}", e))?;
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&current)
+            .map_err(|e| format!("无法打开日志文件: {}", e))?;
+        writeln!(file, "{}", line)
+            .map_err(|e| format!("写入日志失败: {}", e))
+    }
+
+    /// 依次后移旧文件，超出保留数的最旧文件被覆盖
+    fn rotate(&self) -> std::io::Result<()> {
+        for index in (1..=self.max_rotated_files).rev() {
+            let from = self.file_path(index - 1);
+            if from.exists() {
+                fs::rename(&from, self.file_path(index))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 最近的日志，新的在前；只返回不低于 `min_level` 的条目
+    pub fn recent(&self, limit: usize, min_level: LogLevel) -> Vec<LogEntry> {
+        let mut entries = Vec::new();
+        for index in 0..=self.max_rotated_files {
+            let Ok(content) = fs::read_to_string(self.file_path(index)) else {
+                continue;
+            };
+            for line in content.lines().rev() {
+                if entries.len() >= limit {
+                    return entries;
+                }
+                match serde_json::from_str::<LogEntry>(line) {
+                    Ok(entry) if entry.level >= min_level => entries.push(entry),
+                    _ => {}
+                }
+            }
+        }
+        entries
+    }
+}
+
+static LOGGER: OnceLock<Mutex<Logger>> = OnceLock::new();
+
+/// 设置全局日志（应用启动时调用一次），未设置时日志只输出到标准错误
+pub fn init(logger: Logger) {
+    if LOGGER.set(Mutex::new(logger)).is_err() {
+        warn("日志已初始化，忽略重复初始化", &[]);
+    }
+}
+
+/// 开启或关闭详细日志（配置更新后调用）
+pub fn set_detailed(detailed: bool) {
+    if let Some(logger) = LOGGER.get() {
+        logger.lock().unwrap().set_detailed(detailed);
+    }
+}
+
+/// 读取最近的日志
+pub fn recent_entries(limit: usize, min_level: LogLevel) -> Vec<LogEntry> {
+    LOGGER.get()
+        .map(|logger| logger.lock().unwrap().recent(limit, min_level))
+        .unwrap_or_default()
+}
+
+/// 批量操作的编号，同一批的日志使用相同的 batch_id
+pub fn batch_id(operation: &str) -> String {
+    format!("{}-{}", operation, Utc::now().format("%Y%m%d%H%M%S%3f"))
+}
+
+pub fn debug(message: &str, fields: &[(&str, &dyn Display)]) {
+    log(LogLevel::Debug, message, fields);
+}
+
+pub fn info(message: &str, fields: &[(&str, &dyn Display)]) {
+    log(LogLevel::Info, message, fields);
+}
+
+pub fn warn(message: &str, fields: &[(&str, &dyn Display)]) {
+    log(LogLevel::Warn, message, fields);
+}
+
+pub fn error(message: &str, fields: &[(&str, &dyn Display)]) {
+    log(LogLevel::Error, message, fields);
+}
+
+/// 记录一条日志，开发构建同时输出到标准错误
+pub fn log(level: LogLevel, message: &str, fields: &[(&str, &dyn Display)]) {
+    let entry = LogEntry {
+        timestamp: Utc::now(),
+        level,
+        message: message.to_string(),
+        fields: fields.iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect(),
+    };
+
+    let logger = LOGGER.get();
+    if cfg!(debug_assertions) || logger.is_none() {
+        let fields: Vec<String> = entry.fields.iter().map(|(key, value)| format!("{}={}", key, value)).collect();
+        eprintln!("[{:?}] {} {}", entry.level, entry.message, fields.join(" "));
+    }
+    if let Some(logger) = logger {
+        if let Err(e) = logger.lock().unwrap().write(&entry) {
+            eprintln!("{}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(level: LogLevel, message: &str) -> LogEntry {
+        LogEntry {
+            timestamp: Utc::now(),
+            level,
+            message: message.to_string(),
+            fields: BTreeMap::from([("path".to_string(), "/tmp/a.pdf".to_string())]),
+        }
+    }
+
+    #[test]
+    fn test_rotation_and_recent() {
+        let dir = std::env::temp_dir().join(format!("invoicepilot-logs-{}", std::process::id()));
+        let mut logger = Logger::new(&dir, false);
+        logger.max_file_size = 200;
+        logger.max_rotated_files = 2;
+
+        logger.write(&entry(LogLevel::Debug, "skipped")).unwrap();
+        for index in 0..10 {
+            logger.write(&entry(LogLevel::Info, &format!("entry {}", index))).unwrap();
+        }
+        logger.write(&entry(LogLevel::Error, "failed")).unwrap();
+
+        assert!(logger.file_path(1).exists());
+        assert!(logger.file_path(2).exists());
+        assert!(!logger.file_path(3).exists());
+
+        let recent = logger.recent(3, LogLevel::Info);
+        let messages: Vec<&str> = recent.iter().map(|entry| entry.message.as_str()).collect();
+        assert_eq!(messages, vec!["failed", "entry 9", "entry 8"]);
+        assert_eq!(recent[0].fields["path"], "/tmp/a.pdf");
+        assert_eq!(logger.recent(10, LogLevel::Error).len(), 1);
+        assert!(logger.recent(100, LogLevel::Debug).iter().all(|entry| entry.message != "skipped"));
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
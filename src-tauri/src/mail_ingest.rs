@@ -0,0 +1,636 @@
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::path::Path;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use chrono::Utc;
+use regex::Regex;
+use mailparse::{DispositionType, MailHeaderMap, ParsedMail};
+use crate::config::{ImapConfig, MailIngestConfig, MailSourceConfig};
+use crate::error::{AppError, AppResult};
+use crate::file_service::{FileService, IMAGE_EXTENSIONS};
+use crate::i18n::{tr, trf};
+use crate::ledger::{Ledger, LedgerEntry, MailOrigin};
+
+/// 导入的发票文件扩展名（图片另见 IMAGE_EXTENSIONS）
+const INVOICE_EXTENSIONS: &[&str] = &["pdf", "ofd"];
+
+/// 链接下载的最大文件大小
+const MAX_DOWNLOAD_SIZE: u64 = 20 * 1024 * 1024;
+
+/// 每次 UID FETCH 读取的邮件数
+const IMAP_FETCH_BATCH: usize = 50;
+
+/// 正文中的链接
+const URL_PATTERN: &str = r#"https?://[^\s"'<>()]+"#;
+
+/// 文件的获取方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IngestSource {
+    /// 邮件附件
+    Attachment,
+    /// 正文中的下载链接
+    Link,
+}
+
+/// 导入的单个文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IngestedFile {
+    pub path: String,
+    pub sender: String,
+    pub subject: String,
+    pub source: IngestSource,
+}
+
+/// 一次导入的结果
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct IngestReport {
+    /// 读取到的邮件数
+    pub messages_scanned: usize,
+    /// 已处理过而跳过的邮件数
+    pub messages_skipped: usize,
+    pub files: Vec<IngestedFile>,
+    /// 内容与台账中已有文件相同而跳过的文件数
+    pub duplicates_skipped: usize,
+    pub errors: Vec<String>,
+}
+
+impl IngestReport {
+    /// 合并另一个来源的结果
+    pub fn merge(&mut self, other: IngestReport) {
+        self.messages_scanned += other.messages_scanned;
+        self.messages_skipped += other.messages_skipped;
+        self.files.extend(other.files);
+        self.duplicates_skipped += other.duplicates_skipped;
+        self.errors.extend(other.errors);
+    }
+}
+
+/// 读取到的原始邮件
+struct RawMessage {
+    /// 来源内的唯一标识（如 IMAP 的 UID），没有时使用 Message-ID
+    source_key: Option<String>,
+    data: Vec<u8>,
+}
+
+/// 邮件发票导入器
+pub struct MailIngestor {
+    file_service: FileService,
+    url_pattern: Regex,
+}
+
+impl MailIngestor {
+    pub fn new() -> Self {
+        MailIngestor {
+            file_service: FileService::new(),
+            url_pattern: Regex::new(URL_PATTERN).unwrap(),
+        }
+    }
+
+    /// 从一个来源导入发票附件和链接文件到暂存目录，结果记入 `ledger`（不保存，由调用方写回）
+    pub fn ingest(&self, source: &MailSourceConfig, config: &MailIngestConfig, staging: &Path, ledger: &mut Ledger) -> AppResult<IngestReport> {
+        std::fs::create_dir_all(staging)
+            .map_err(|e| AppError::io_error_at(e, tr("无法创建暂存目录", "Unable to create staging directory"), staging))?;
+
+        let messages = match source {
+            MailSourceConfig::Maildir { path } => Self::read_maildir(Path::new(path))?,
+            MailSourceConfig::Mbox { path } => Self::read_mbox(Path::new(path))?,
+            MailSourceConfig::Imap(imap) => Self::fetch_imap(imap, ledger)?,
+        };
+
+        let mut report = IngestReport::default();
+        for message in &messages {
+            report.messages_scanned += 1;
+            self.process_message(message, config, staging, ledger, &mut report);
+        }
+        Ok(report)
+    }
+
+    /// 读取 Maildir 中 cur 和 new 下的邮件
+    fn read_maildir(path: &Path) -> AppResult<Vec<RawMessage>> {
+        if !path.is_dir() {
+            return Err(AppError::file_system_error(tr("Maildir 目录不存在", "Maildir directory does not exist")).with_path(path));
+        }
+
+        let mut messages = Vec::new();
+        for sub in ["cur", "new"] {
+            let Ok(entries) = std::fs::read_dir(path.join(sub)) else {
+                continue;
+            };
+            for entry in entries.filter_map(|e| e.ok()) {
+                if entry.path().is_file() {
+                    let data = std::fs::read(entry.path())
+                        .map_err(|e| AppError::io_error_at(e, tr("读取邮件失败", "Failed to read message"), &entry.path()))?;
+                    messages.push(RawMessage { source_key: None, data });
+                }
+            }
+        }
+        Ok(messages)
+    }
+
+    /// 按 "From " 分隔行拆分 mbox 文件
+    fn read_mbox(path: &Path) -> AppResult<Vec<RawMessage>> {
+        let content = std::fs::read(path)
+            .map_err(|e| AppError::io_error_at(e, tr("读取 mbox 失败", "Failed to read mbox"), path))?;
+        Ok(Self::split_mbox(&content)
+            .into_iter()
+            .map(|data| RawMessage { source_key: None, data })
+            .collect())
+    }
+
+    fn split_mbox(content: &[u8]) -> Vec<Vec<u8>> {
+        let mut messages = Vec::new();
+        let mut current: Option<Vec<u8>> = None;
+        let mut previous_blank = true;
+
+        for line in content.split_inclusive(|b| *b == b'\n') {
+            if previous_blank && line.starts_with(b"From ") {
+                messages.extend(current.take());
+                current = Some(Vec::new());
+            } else if let Some(message) = current.as_mut() {
+                // 还原 mbox 中转义的 ">From "
+                let line = if line.starts_with(b">From ") { &line[1..] } else { line };
+                message.extend_from_slice(line);
+            }
+            previous_blank = line == b"\n" || line == b"\r\n";
+        }
+        messages.extend(current);
+        messages
+    }
+
+    /// 从 IMAP 服务器读取尚未处理的邮件（只读取，不改变已读状态）
+    fn fetch_imap(config: &ImapConfig, ledger: &Ledger) -> AppResult<Vec<RawMessage>> {
+        let address = (config.host.as_str(), config.port);
+        if config.use_tls {
+            let tls = native_tls::TlsConnector::builder().build()
+                .map_err(|e| network_error(tr("TLS 初始化失败", "TLS initialization failed"), e))?;
+            let client = imap::connect(address, &config.host, &tls)
+                .map_err(|e| network_error(tr("无法连接 IMAP 服务器", "Unable to connect to the IMAP server"), e))?;
+            Self::fetch_imap_messages(client, config, ledger)
+        } else {
+            let stream = TcpStream::connect(address)
+                .map_err(|e| network_error(tr("无法连接 IMAP 服务器", "Unable to connect to the IMAP server"), e))?;
+            let mut client = imap::Client::new(stream);
+            client.read_greeting()
+                .map_err(|e| network_error(tr("IMAP 握手失败", "IMAP handshake failed"), e))?;
+            Self::fetch_imap_messages(client, config, ledger)
+        }
+    }
+
+    fn fetch_imap_messages<T: Read + Write>(client: imap::Client<T>, config: &ImapConfig, ledger: &Ledger) -> AppResult<Vec<RawMessage>> {
+        let mut session = client.login(&config.username, &config.password)
+            .map_err(|(e, _)| AppError::permission_error(&format!("{}: {}", tr("IMAP 登录失败", "IMAP login failed"), e)))?;
+        let mailbox_name = if config.mailbox.is_empty() { "INBOX" } else { config.mailbox.as_str() };
+        let mailbox = session.select(mailbox_name)
+            .map_err(|e| AppError::validation_error(&format!("{} {}: {}", tr("无法打开邮箱文件夹", "Unable to open mailbox folder"), mailbox_name, e)))?;
+
+        // UIDVALIDITY 变化时 UID 会被重新分配
+        let key_prefix = format!(
+            "imap:{}@{}/{}/{}",
+            config.username,
+            config.host,
+            mailbox_name,
+            mailbox.uid_validity.unwrap_or(0)
+        );
+        // 只搜索最近的邮件，避免每次都列出整个邮箱
+        let query = if config.since_days == 0 {
+            "ALL".to_string()
+        } else {
+            let since = Utc::now().date_naive() - chrono::Days::new(config.since_days as u64);
+            format!("SINCE {}", since.format("%d-%b-%Y"))
+        };
+        let mut uids: Vec<u32> = session.uid_search(&query)
+            .map_err(|e| network_error(tr("IMAP 搜索失败", "IMAP search failed"), e))?
+            .into_iter()
+            .filter(|uid| !ledger.is_message_processed(&format!("{}/{}", key_prefix, uid)))
+            .collect();
+        uids.sort_unstable();
+
+        let mut messages = Vec::new();
+        for batch in uids.chunks(IMAP_FETCH_BATCH) {
+            let uid_set = batch.iter().map(|uid| uid.to_string()).collect::<Vec<_>>().join(",");
+            let fetches = session.uid_fetch(&uid_set, "BODY.PEEK[]")
+                .map_err(|e| network_error(tr("IMAP 读取邮件失败", "IMAP fetch failed"), e))?;
+            for fetch in fetches.iter() {
+                if let (Some(uid), Some(body)) = (fetch.uid, fetch.body()) {
+                    messages.push(RawMessage {
+                        source_key: Some(format!("{}/{}", key_prefix, uid)),
+                        data: body.to_vec(),
+                    });
+                }
+            }
+        }
+
+        let _ = session.logout();
+        Ok(messages)
+    }
+
+    /// 处理单封邮件：保存附件、下载正文链接并写入台账
+    fn process_message(&self, message: &RawMessage, config: &MailIngestConfig, staging: &Path, ledger: &mut Ledger, report: &mut IngestReport) {
+        let parsed = match mailparse::parse_mail(&message.data) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                report.errors.push(trf("邮件解析失败: {}", "Failed to parse message: {}", &[&e]));
+                return;
+            }
+        };
+
+        let message_id = parsed.headers.get_first_value("Message-ID")
+            .map(|id| id.trim().to_string())
+            .filter(|id| !id.is_empty());
+        let message_key = match &message_id {
+            Some(id) => format!("message-id:{}", id),
+            None => format!("sha256:{}", Ledger::hash_bytes(&message.data)),
+        };
+        if ledger.is_message_processed(&message_key)
+            || message.source_key.as_deref().is_some_and(|key| ledger.is_message_processed(key))
+        {
+            report.messages_skipped += 1;
+            return;
+        }
+
+        let origin = MailOrigin {
+            sender: parsed.headers.get_first_value("From").unwrap_or_default(),
+            subject: parsed.headers.get_first_value("Subject").unwrap_or_default(),
+            message_id,
+            link: None,
+        };
+
+        // 有文件保存或下载失败时不标记为已处理，下次导入重试
+        let error_count = report.errors.len();
+        let mut bodies = Vec::new();
+        self.collect_parts(&parsed, &origin, staging, ledger, report, &mut bodies);
+
+        // 正文中指向发票平台的下载链接
+        let mut seen_links = Vec::new();
+        for body in &bodies {
+            for link in self.url_pattern.find_iter(body) {
+                let url = link.as_str().trim_end_matches(['.', ',', ';', '。', '，']).replace("&amp;", "&");
+                if seen_links.contains(&url) || !Self::is_invoice_link(&url, &config.link_domains) {
+                    continue;
+                }
+                seen_links.push(url.clone());
+
+                match Self::download(&url) {
+                    Ok((filename, bytes)) => {
+                        let origin = MailOrigin { link: Some(url.clone()), ..origin.clone() };
+                        self.save_file(&filename, &bytes, IngestSource::Link, &origin, staging, ledger, report);
+                    }
+                    Err(e) => report.errors.push(format!("{}: {}", url, e)),
+                }
+            }
+        }
+
+        if report.errors.len() > error_count {
+            return;
+        }
+        ledger.mark_message_processed(&message_key);
+        if let Some(source_key) = &message.source_key {
+            ledger.mark_message_processed(source_key);
+        }
+    }
+
+    /// 遍历邮件各部分，保存发票附件并收集正文文本
+    fn collect_parts(&self, part: &ParsedMail, origin: &MailOrigin, staging: &Path, ledger: &mut Ledger, report: &mut IngestReport, bodies: &mut Vec<String>) {
+        if !part.subparts.is_empty() {
+            for subpart in &part.subparts {
+                self.collect_parts(subpart, origin, staging, ledger, report, bodies);
+            }
+            return;
+        }
+
+        let disposition = part.get_content_disposition();
+        let filename = disposition.params.get("filename")
+            .or_else(|| part.ctype.params.get("name"))
+            .cloned();
+
+        match filename {
+            Some(filename) if Self::is_ingestible(&filename) => match part.get_body_raw() {
+                Ok(bytes) => self.save_file(&filename, &bytes, IngestSource::Attachment, origin, staging, ledger, report),
+                Err(e) => report.errors.push(trf("{}: 附件解码失败: {}", "{}: failed to decode attachment: {}", &[&filename, &e])),
+            },
+            None if disposition.disposition != DispositionType::Attachment
+                && part.ctype.mimetype.starts_with("text/") =>
+            {
+                if let Ok(body) = part.get_body() {
+                    bodies.push(body);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// 保存文件到暂存目录并记入台账，内容重复的文件跳过
+    #[allow(clippy::too_many_arguments)]
+    fn save_file(&self, filename: &str, bytes: &[u8], source: IngestSource, origin: &MailOrigin, staging: &Path, ledger: &mut Ledger, report: &mut IngestReport) {
+        let content_hash = Ledger::hash_bytes(bytes);
+        if ledger.get(&content_hash).is_some() {
+            report.duplicates_skipped += 1;
+            return;
+        }
+
+        let filename = self.file_service.sanitize_filename(filename);
+        let filename = self.file_service.resolve_filename_conflict(&staging.to_string_lossy(), &filename);
+        let path = staging.join(&filename);
+        if let Err(e) = std::fs::write(&path, bytes) {
+            report.errors.push(trf("{}: 保存失败: {}", "{}: failed to save: {}", &[&filename, &e]));
+            return;
+        }
+
+        let path = path.to_string_lossy().to_string();
+        ledger.record(LedgerEntry {
+            content_hash,
+            file_name: filename,
+            path: path.clone(),
+            mail: Some(origin.clone()),
+            invoice: None,
+            correction: None,
+            recorded_at: Utc::now(),
+        });
+        report.files.push(IngestedFile {
+            path,
+            sender: origin.sender.clone(),
+            subject: origin.subject.clone(),
+            source,
+        });
+    }
+
+    /// 是否为需要导入的文件类型
+    fn is_ingestible(filename: &str) -> bool {
+        let extension = Path::new(filename).extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        INVOICE_EXTENSIONS.contains(&extension.as_str()) || IMAGE_EXTENSIONS.contains(&extension.as_str())
+    }
+
+    /// 链接域名是否属于配置的发票平台
+    fn is_invoice_link(url: &str, link_domains: &[String]) -> bool {
+        let host = url.split("://").nth(1)
+            .and_then(|rest| rest.split(['/', '?', '#', ':']).next())
+            .unwrap_or_default()
+            .to_lowercase();
+        link_domains.iter().any(|domain| {
+            let domain = domain.trim().to_lowercase();
+            !domain.is_empty() && (host == domain || host.ends_with(&format!(".{}", domain)))
+        })
+    }
+
+    /// 下载链接指向的发票文件，返回文件名和内容
+    fn download(url: &str) -> AppResult<(String, Vec<u8>)> {
+        let response = ureq::get(url)
+            .timeout(Duration::from_secs(30))
+            .call()
+            .map_err(|e| network_error(tr("下载失败", "Download failed"), e))?;
+
+        let content_type = response.content_type().to_lowercase();
+        let filename = response.header("Content-Disposition")
+            .and_then(|value| value.split(';').find_map(|param| {
+                param.trim().strip_prefix("filename=").map(|name| name.trim_matches('"').to_string())
+            }))
+            .or_else(|| {
+                url.split(['?', '#']).next()
+                    .and_then(|path| path.rsplit('/').next())
+                    .filter(|name| !name.is_empty())
+                    .map(|name| name.to_string())
+            })
+            .unwrap_or_default();
+
+        // 发票平台常返回预览网页，只保存PDF/OFD文件
+        let filename = if Self::is_ingestible(&filename) {
+            filename
+        } else if content_type.contains("pdf") {
+            format!("{}.pdf", Path::new(&filename).file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_else(|| tr("发票", "invoice").to_string()))
+        } else if content_type.contains("ofd") {
+            format!("{}.ofd", Path::new(&filename).file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_else(|| tr("发票", "invoice").to_string()))
+        } else {
+            return Err(AppError::validation_error(tr("链接不是发票文件", "The link is not an invoice file")));
+        };
+
+        let mut bytes = Vec::new();
+        response.into_reader()
+            .take(MAX_DOWNLOAD_SIZE + 1)
+            .read_to_end(&mut bytes)
+            .map_err(|e| network_error(tr("下载失败", "Download failed"), e))?;
+        if bytes.len() as u64 > MAX_DOWNLOAD_SIZE {
+            return Err(AppError::validation_error(tr("文件过大", "File is too large")));
+        }
+
+        Ok((filename, bytes))
+    }
+}
+
+/// 网络和邮件服务器错误，可重试
+fn network_error(context: &str, error: impl std::fmt::Display) -> AppError {
+    AppError::io_error(&format!("{}: {}", context, error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+
+    const MESSAGE: &str = "From: =?UTF-8?B?5byg5LiJ?= <zhang@example.com>\r\n\
+Subject: invoice 2024-03\r\n\
+Message-ID: <abc@example.com>\r\n\
+MIME-Version: 1.0\r\n\
+Content-Type: multipart/mixed; boundary=\"XX\"\r\n\
+\r\n\
+--XX\r\n\
+Content-Type: text/plain; charset=utf-8\r\n\
+\r\n\
+See attachment.\r\n\
+--XX\r\n\
+Content-Type: application/pdf; name=\"invoice.pdf\"\r\n\
+Content-Disposition: attachment; filename=\"invoice.pdf\"\r\n\
+Content-Transfer-Encoding: base64\r\n\
+\r\n\
+JVBERi0xLjQKJUVPRgo=\r\n\
+--XX--\r\n";
+
+    /// 只支持本测试用到的命令的 IMAP 服务器替身，邮箱中有 `uids` 这些邮件，收到的命令发送到返回的通道
+    fn spawn_imap_stand_in(uids: Vec<u32>) -> (u16, std::sync::mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().take(2) {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                stream.write_all(b"* OK IMAP4rev1 stand-in ready\r\n").unwrap();
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap_or(0) > 0 {
+                    let (tag, command) = line.trim_end().split_once(' ').unwrap();
+                    let command = command.to_uppercase();
+                    let _ = sender.send(command.clone());
+                    let response = if command.starts_with("LOGIN") {
+                        format!("{} OK LOGIN completed\r\n", tag)
+                    } else if command.starts_with("SELECT") {
+                        format!("* {} EXISTS\r\n* OK [UIDVALIDITY 42] UIDs valid\r\n{} OK [READ-WRITE] SELECT completed\r\n", uids.len(), tag)
+                    } else if command.starts_with("UID SEARCH") {
+                        let found = uids.iter().map(|uid| uid.to_string()).collect::<Vec<_>>().join(" ");
+                        format!("* SEARCH {}\r\n{} OK SEARCH completed\r\n", found, tag)
+                    } else if command.starts_with("UID FETCH") {
+                        let mut response = String::new();
+                        let requested = command.split_whitespace().nth(2).unwrap_or_default();
+                        for uid in requested.split(',').filter_map(|uid| uid.parse::<u32>().ok()) {
+                            let sequence = uids.iter().position(|known| *known == uid).unwrap() + 1;
+                            let message = MESSAGE.replace("<abc@example.com>", &format!("<{}@example.com>", uid));
+                            response.push_str(&format!("* {} FETCH (UID {} BODY[] {{{}}}\r\n{})\r\n", sequence, uid, message.len(), message));
+                        }
+                        format!("{}{} OK FETCH completed\r\n", response, tag)
+                    } else {
+                        format!("* BYE\r\n{} OK completed\r\n", tag)
+                    };
+                    stream.write_all(response.as_bytes()).unwrap();
+                    if command.starts_with("LOGOUT") {
+                        break;
+                    }
+                    line.clear();
+                }
+            }
+        });
+        (port, receiver)
+    }
+
+    fn imap_source(port: u16) -> MailSourceConfig {
+        MailSourceConfig::Imap(ImapConfig {
+            host: "127.0.0.1".to_string(),
+            port,
+            use_tls: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            ..ImapConfig::default()
+        })
+    }
+
+    #[test]
+    fn test_ingest_from_imap_stand_in_skips_processed_messages() {
+        let dir = std::env::temp_dir().join(format!("invoicepilot-mail-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let staging = dir.join("staging");
+        let mut ledger = Ledger::load(&dir.join("ledger.json")).unwrap();
+
+        let (port, commands) = spawn_imap_stand_in(vec![7]);
+        let source = imap_source(port);
+        let ingestor = MailIngestor::new();
+        let config = MailIngestConfig::default();
+
+        let report = ingestor.ingest(&source, &config, &staging, &mut ledger).unwrap();
+        assert_eq!(report.files.len(), 1, "{:?}", report.errors);
+        assert_eq!(report.files[0].sender, "张三 <zhang@example.com>");
+        assert_eq!(report.files[0].subject, "invoice 2024-03");
+        assert!(staging.join("invoice.pdf").exists());
+
+        let entry = ledger.entries().next().unwrap();
+        assert_eq!(entry.mail.as_ref().unwrap().subject, "invoice 2024-03");
+        assert!(commands.try_iter().any(|command| command.starts_with("UID SEARCH SINCE")));
+
+        // 第二次导入时 UID 已处理，不再读取
+        let report = ingestor.ingest(&source, &config, &staging, &mut ledger).unwrap();
+        assert_eq!(report.messages_scanned, 0);
+        assert!(report.files.is_empty());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_imap_messages_are_fetched_in_batches() {
+        let dir = std::env::temp_dir().join(format!("invoicepilot-mail-batch-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut ledger = Ledger::load(&dir.join("ledger.json")).unwrap();
+
+        let (port, commands) = spawn_imap_stand_in((1..=120).collect());
+        let report = MailIngestor::new()
+            .ingest(&imap_source(port), &MailIngestConfig::default(), &dir.join("staging"), &mut ledger)
+            .unwrap();
+        assert_eq!(report.messages_scanned, 120, "{:?}", report.errors);
+        // 附件内容相同，只保存一份
+        assert_eq!(report.files.len(), 1);
+
+        let fetches = commands.try_iter().filter(|command| command.starts_with("UID FETCH")).count();
+        assert_eq!(fetches, 120usize.div_ceil(IMAP_FETCH_BATCH));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_long_attachment_name_keeps_extension() {
+        let dir = std::env::temp_dir().join(format!("invoicepilot-mail-long-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut ledger = Ledger::load(&dir.join("ledger.json")).unwrap();
+        let mut report = IngestReport::default();
+        let origin = MailOrigin { sender: String::new(), subject: String::new(), message_id: None, link: None };
+
+        let filename = format!("{}.pdf", "发".repeat(300));
+        MailIngestor::new().save_file(&filename, b"%PDF-1.4", IngestSource::Attachment, &origin, &dir, &mut ledger, &mut report);
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+        assert!(report.files[0].path.ends_with(".pdf"));
+        assert!(MailIngestor::is_ingestible(&report.files[0].path));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_failed_download_is_retried() {
+        let dir = std::env::temp_dir().join(format!("invoicepilot-mail-retry-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let staging = dir.join("staging");
+        let mut ledger = Ledger::load(&dir.join("ledger.json")).unwrap();
+
+        // 先占用一个端口再释放，第一次下载时连接被拒绝
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let mbox_path = dir.join("inbox.mbox");
+        std::fs::write(&mbox_path, format!(
+            "From a@example.com Mon Jan  1 00:00:00 2024\nFrom: a@example.com\nSubject: link\nMessage-ID: <link@example.com>\n\nhttp://127.0.0.1:{}/invoice.pdf\n",
+            port
+        )).unwrap();
+        let source = MailSourceConfig::Mbox { path: mbox_path.to_string_lossy().to_string() };
+        let config = MailIngestConfig { link_domains: vec!["127.0.0.1".to_string()], ..Default::default() };
+        let ingestor = MailIngestor::new();
+
+        let report = ingestor.ingest(&source, &config, &staging, &mut ledger).unwrap();
+        assert_eq!(report.errors.len(), 1);
+        assert!(report.files.is_empty());
+
+        // 链接恢复后重新导入同一封邮件
+        let listener = TcpListener::bind(("127.0.0.1", port)).unwrap();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap_or(0) > 2 {
+                line.clear();
+            }
+            let body = b"%PDF-1.4\n%%EOF\n";
+            let header = format!("HTTP/1.1 200 OK\r\nContent-Type: application/pdf\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", body.len());
+            stream.write_all(header.as_bytes()).unwrap();
+            stream.write_all(body).unwrap();
+        });
+        let report = ingestor.ingest(&source, &config, &staging, &mut ledger).unwrap();
+        assert_eq!(report.messages_skipped, 0);
+        assert_eq!(report.files.len(), 1, "{:?}", report.errors);
+        assert!(staging.join("invoice.pdf").exists());
+
+        let report = ingestor.ingest(&source, &config, &staging, &mut ledger).unwrap();
+        assert_eq!(report.messages_skipped, 1);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_split_mbox_and_invoice_links() {
+        let mbox = b"From a@example.com Mon Jan  1 00:00:00 2024\nSubject: one\n\n>From here\n\nFrom b@example.com Mon Jan  1 00:00:00 2024\nSubject: two\n\nbody\n";
+        let messages = MailIngestor::split_mbox(mbox);
+        assert_eq!(messages.len(), 2);
+        assert!(String::from_utf8_lossy(&messages[0]).contains("\nFrom here"));
+
+        let domains = MailIngestConfig::default().link_domains;
+        assert!(MailIngestor::is_invoice_link("https://dlj.nuonuo.com/x?id=1", &domains));
+        assert!(!MailIngestor::is_invoice_link("https://nuonuo.com.evil.example/x", &domains));
+    }
+}
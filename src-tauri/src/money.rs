@@ -0,0 +1,429 @@
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use regex::Regex;
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Sub};
+use std::str::FromStr;
+
+/// 金额数字的通用写法：支持 1,234.56、1.234,56、1'234.56 以及不带分隔符的写法
+pub const NUMBER_PATTERN: &str = r"\d{1,3}(?:[,.'\u{00A0}\u{202F}]\d{3})+(?:[.,]\d{1,2})?|\d+(?:[.,]\d{1,2})?";
+
+/// 币种（ISO 4217 代码）
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Currency {
+    #[default]
+    CNY,
+    USD,
+    EUR,
+    JPY,
+    HKD,
+    GBP,
+    AUD,
+    CAD,
+    SGD,
+    CHF,
+    KRW,
+    TWD,
+    MOP,
+}
+
+impl Currency {
+    /// ISO 4217 代码
+    pub fn code(&self) -> &'static str {
+        match self {
+            Currency::CNY => "CNY",
+            Currency::USD => "USD",
+            Currency::EUR => "EUR",
+            Currency::JPY => "JPY",
+            Currency::HKD => "HKD",
+            Currency::GBP => "GBP",
+            Currency::AUD => "AUD",
+            Currency::CAD => "CAD",
+            Currency::SGD => "SGD",
+            Currency::CHF => "CHF",
+            Currency::KRW => "KRW",
+            Currency::TWD => "TWD",
+            Currency::MOP => "MOP",
+        }
+    }
+
+    /// 小数位数
+    pub fn minor_units(&self) -> usize {
+        match self {
+            Currency::JPY | Currency::KRW => 0,
+            _ => 2,
+        }
+    }
+
+    /// 根据货币符号、ISO代码或中文名称识别币种
+    pub fn from_marker(marker: &str) -> Option<Currency> {
+        let currency = match marker.trim().to_uppercase().as_str() {
+            "¥" | "￥" | "元" | "人民币" | "CNY" | "RMB" => Currency::CNY,
+            "$" | "US$" | "USD" | "美元" => Currency::USD,
+            "€" | "EUR" | "欧元" => Currency::EUR,
+            "JP¥" | "円" | "JPY" | "日元" => Currency::JPY,
+            "HK$" | "HKD" | "港币" | "港元" => Currency::HKD,
+            "£" | "GBP" | "英镑" => Currency::GBP,
+            "A$" | "AU$" | "AUD" | "澳元" => Currency::AUD,
+            "C$" | "CA$" | "CAD" | "加元" => Currency::CAD,
+            "S$" | "SGD" | "新加坡元" => Currency::SGD,
+            "CHF" | "瑞士法郎" => Currency::CHF,
+            "₩" | "KRW" | "韩元" => Currency::KRW,
+            "NT$" | "TWD" | "新台币" => Currency::TWD,
+            "MOP$" | "MOP" | "澳门元" => Currency::MOP,
+            _ => return None,
+        };
+        Some(currency)
+    }
+}
+
+/// 定点金额，以"分"为单位保存，避免浮点累加误差
+///
+/// 序列化为字符串（如 "317.60"），反序列化时同时接受字符串和数字
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Amount(i64);
+
+impl Amount {
+    pub const ZERO: Amount = Amount(0);
+
+    /// 由"分"创建
+    pub const fn from_cents(cents: i64) -> Self {
+        Amount(cents)
+    }
+
+    /// 由整数个货币单位（元、美元等）创建
+    pub const fn from_units(units: i64) -> Self {
+        Amount(units * 100)
+    }
+
+    /// 以"分"为单位的数值
+    pub fn cents(&self) -> i64 {
+        self.0
+    }
+
+    /// 由浮点数创建（四舍五入到分），只用于兼容旧数据
+    pub fn from_f64(value: f64) -> Option<Self> {
+        if !value.is_finite() {
+            return None;
+        }
+        let cents = (value * 100.0).round();
+        if cents.abs() > i64::MAX as f64 {
+            return None;
+        }
+        Some(Amount(cents as i64))
+    }
+
+    /// 精确解析十进制字符串（如 "317.6"、"-12.345"），超过两位的小数四舍五入
+    pub fn parse_decimal(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let (negative, digits) = match value.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, value.strip_prefix('+').unwrap_or(value)),
+        };
+        let (integer, fraction) = match digits.split_once('.') {
+            Some((integer, fraction)) => (integer, fraction),
+            None => (digits, ""),
+        };
+        if (integer.is_empty() && fraction.is_empty())
+            || !integer.chars().all(|c| c.is_ascii_digit())
+            || !fraction.chars().all(|c| c.is_ascii_digit())
+        {
+            return None;
+        }
+
+        let integer: i64 = if integer.is_empty() { 0 } else { integer.parse().ok()? };
+        let mut fraction_digits = fraction.bytes().map(|b| (b - b'0') as i64);
+        let tenths = fraction_digits.next().unwrap_or(0);
+        let hundredths = fraction_digits.next().unwrap_or(0);
+        let round_up = fraction_digits.next().unwrap_or(0) >= 5;
+
+        let cents = integer.checked_mul(100)?
+            .checked_add(tenths * 10 + hundredths + i64::from(round_up))?;
+        Some(Amount(if negative { -cents } else { cents }))
+    }
+
+    /// 按指定小数位数格式化（0～2位），如日元不显示小数
+    pub fn format_with(&self, decimals: usize) -> String {
+        match decimals {
+            0 => {
+                let rounded = (self.0.abs() + 50) / 100;
+                format!("{}{}", if self.0 < 0 { "-" } else { "" }, rounded)
+            }
+            1 => {
+                let rounded = (self.0.abs() + 5) / 10;
+                format!("{}{}.{}", if self.0 < 0 { "-" } else { "" }, rounded / 10, rounded % 10)
+            }
+            _ => self.to_string(),
+        }
+    }
+
+    /// 按数量平均（四舍五入到分）
+    pub fn average(&self, count: usize) -> Amount {
+        if count == 0 {
+            return Amount::ZERO;
+        }
+        let count = count as i64;
+        let half = count / 2;
+        let rounded = if self.0 >= 0 { (self.0 + half) / count } else { (self.0 - half) / count };
+        Amount(rounded)
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        write!(f, "{}{}.{:02}", sign, self.0.abs() / 100, self.0.abs() % 100)
+    }
+}
+
+impl FromStr for Amount {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Amount::parse_decimal(value).ok_or_else(|| format!("无效的金额: {}", value))
+    }
+}
+
+impl Add for Amount {
+    type Output = Amount;
+
+    fn add(self, other: Amount) -> Amount {
+        Amount(self.0 + other.0)
+    }
+}
+
+impl AddAssign for Amount {
+    fn add_assign(&mut self, other: Amount) {
+        self.0 += other.0;
+    }
+}
+
+impl Sub for Amount {
+    type Output = Amount;
+
+    fn sub(self, other: Amount) -> Amount {
+        Amount(self.0 - other.0)
+    }
+}
+
+impl Sum for Amount {
+    fn sum<I: Iterator<Item = Amount>>(iter: I) -> Amount {
+        iter.fold(Amount::ZERO, Add::add)
+    }
+}
+
+impl Serialize for Amount {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Amount {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct AmountVisitor;
+
+        impl<'de> Visitor<'de> for AmountVisitor {
+            type Value = Amount;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("金额字符串或数字")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Amount, E> {
+                Amount::parse_decimal(value).ok_or_else(|| E::custom(format!("无效的金额: {}", value)))
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<Amount, E> {
+                value.checked_mul(100).map(Amount).ok_or_else(|| E::custom("金额超出范围"))
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<Amount, E> {
+                i64::try_from(value).ok()
+                    .and_then(|value| value.checked_mul(100))
+                    .map(Amount)
+                    .ok_or_else(|| E::custom("金额超出范围"))
+            }
+
+            // 兼容旧版本配置和前端传入的数字
+            fn visit_f64<E: de::Error>(self, value: f64) -> Result<Amount, E> {
+                Amount::from_f64(value).ok_or_else(|| E::custom("金额超出范围"))
+            }
+        }
+
+        deserializer.deserialize_any(AmountVisitor)
+    }
+}
+
+/// 带币种的金额
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Money {
+    pub amount: Amount,
+    pub currency: Currency,
+}
+
+impl Money {
+    pub fn new(amount: Amount, currency: Currency) -> Self {
+        Money { amount, currency }
+    }
+
+    /// 人民币金额
+    pub fn cny(amount: Amount) -> Self {
+        Money::new(amount, Currency::CNY)
+    }
+
+    /// 按币种的小数位数格式化数值部分（不含币种）
+    pub fn format_amount(&self) -> String {
+        self.amount.format_with(self.currency.minor_units())
+    }
+}
+
+/// 解析金额数字，自动判断千位分隔符与小数点
+///
+/// `1,234.56`、`1.234,56`、`1'234.56`、`1234,5` 都能得到正确结果
+pub fn parse_amount_number(value: &str) -> Option<Amount> {
+    let cleaned: String = value.trim()
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '\'' | '\u{00A0}' | '\u{202F}'))
+        .collect();
+
+    let last_comma = cleaned.rfind(',');
+    let last_dot = cleaned.rfind('.');
+
+    let decimal_separator = match (last_comma, last_dot) {
+        // 两种分隔符都出现时，靠后的是小数点
+        (Some(comma), Some(dot)) => Some(if comma > dot { ',' } else { '.' }),
+        // 只有一种分隔符：只出现一次且后面不是3位数字时才视为小数点
+        (Some(pos), None) | (None, Some(pos)) => {
+            let separator = cleaned.as_bytes()[pos] as char;
+            let digits_after = cleaned.len() - pos - 1;
+            if cleaned.matches(separator).count() == 1 && digits_after != 3 {
+                Some(separator)
+            } else {
+                None
+            }
+        }
+        (None, None) => None,
+    };
+
+    let normalized: String = match decimal_separator {
+        Some(separator) => {
+            let split_at = cleaned.rfind(separator)?;
+            let (integer, fraction) = cleaned.split_at(split_at);
+            let integer: String = integer.chars().filter(|c| c.is_ascii_digit()).collect();
+            format!("{}.{}", integer, &fraction[1..])
+        }
+        None => cleaned.chars().filter(|c| c.is_ascii_digit()).collect(),
+    };
+
+    Amount::parse_decimal(&normalized)
+}
+
+/// 币种识别器：在文本中查找货币符号、ISO 代码和中文币种名称
+pub struct CurrencyDetector {
+    prefix_money: Regex,
+    suffix_money: Regex,
+}
+
+/// 写在金额前面的币种标记
+const PREFIX_MARKERS: &str = r"(?P<prefix>MOP\$|NT\$|HK\$|US\$|AU\$|CA\$|A\$|C\$|S\$|JP¥|[$€£₩¥￥]|(?-u:\b)(?:CNY|RMB|USD|EUR|JPY|HKD|GBP|AUD|CAD|SGD|CHF|KRW|TWD|MOP))";
+
+/// 写在金额后面的币种标记
+const SUFFIX_MARKERS: &str = r"(?P<suffix>CNY|RMB|USD|EUR|JPY|HKD|GBP|AUD|CAD|SGD|CHF|KRW|TWD|MOP|人民币|美元|欧元|日元|港币|港元|英镑|澳元|加元|新加坡元|瑞士法郎|韩元|新台币|澳门元|円|元)";
+
+impl CurrencyDetector {
+    pub fn new() -> Self {
+        CurrencyDetector {
+            prefix_money: Regex::new(&format!(r"{}\s*(?P<amount>{})", PREFIX_MARKERS, NUMBER_PATTERN))
+                .expect("内置币种模式无效"),
+            suffix_money: Regex::new(&format!(r"(?P<amount>{})\s*{}", NUMBER_PATTERN, SUFFIX_MARKERS))
+                .expect("内置币种模式无效"),
+        }
+    }
+
+    /// 查找文本中全部带币种标记的金额
+    pub fn find_all(&self, text: &str) -> Vec<Money> {
+        let prefixed = self.prefix_money.captures_iter(text)
+            .filter_map(|caps| Self::money_from(&caps, "prefix"));
+        let suffixed = self.suffix_money.captures_iter(text)
+            .filter_map(|caps| Self::money_from(&caps, "suffix"));
+        prefixed.chain(suffixed).collect()
+    }
+
+    /// 查找文本中第一个带币种标记的金额
+    pub fn find_first(&self, text: &str) -> Option<Money> {
+        let prefixed = self.prefix_money.captures(text)
+            .and_then(|caps| Some((caps.get(0)?.start(), Self::money_from(&caps, "prefix")?)));
+        let suffixed = self.suffix_money.captures(text)
+            .and_then(|caps| Some((caps.get(0)?.start(), Self::money_from(&caps, "suffix")?)));
+
+        match (prefixed, suffixed) {
+            (Some(a), Some(b)) => Some(if a.0 <= b.0 { a.1 } else { b.1 }),
+            (a, b) => a.or(b).map(|(_, money)| money),
+        }
+    }
+
+    /// 一段文本中出现的币种（取出现次数最多的，相同时优先人民币）
+    pub fn detect(&self, text: &str) -> Option<Currency> {
+        let mut counts: std::collections::BTreeMap<Currency, usize> = std::collections::BTreeMap::new();
+        for money in self.find_all(text) {
+            *counts.entry(money.currency).or_insert(0) += 1;
+        }
+
+        counts.into_iter()
+            .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(&a.0)))
+            .map(|(currency, _)| currency)
+    }
+
+    fn money_from(caps: &regex::Captures, marker_group: &str) -> Option<Money> {
+        let currency = Currency::from_marker(caps.name(marker_group)?.as_str())?;
+        let amount = parse_amount_number(caps.name("amount")?.as_str())?;
+        Some(Money::new(amount, currency))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_locale_separators() {
+        assert_eq!(parse_amount_number("1,234.56"), Some(Amount::from_cents(123456)));
+        assert_eq!(parse_amount_number("1.234,56"), Some(Amount::from_cents(123456)));
+        assert_eq!(parse_amount_number("1'234.56"), Some(Amount::from_cents(123456)));
+        assert_eq!(parse_amount_number("1.234.567"), Some(Amount::from_units(1234567)));
+        assert_eq!(parse_amount_number("12,345"), Some(Amount::from_units(12345)));
+        assert_eq!(parse_amount_number("317.60"), Some(Amount::from_cents(31760)));
+        assert_eq!(parse_amount_number("99,5"), Some(Amount::from_cents(9950)));
+    }
+
+    #[test]
+    fn test_detect_currency_markers() {
+        let detector = CurrencyDetector::new();
+        assert_eq!(detector.find_first("Total: US$1,200.00"), Some(Money::new(Amount::from_units(1200), Currency::USD)));
+        assert_eq!(detector.find_first("合计 HK$ 88.50"), Some(Money::new(Amount::from_cents(8850), Currency::HKD)));
+        assert_eq!(detector.find_first("Summe 1.234,56 EUR"), Some(Money::new(Amount::from_cents(123456), Currency::EUR)));
+        assert_eq!(detector.find_first("价税合计（小写）¥317.60"), Some(Money::cny(Amount::from_cents(31760))));
+        assert_eq!(detector.find_first("金额 120 美元"), Some(Money::new(Amount::from_units(120), Currency::USD)));
+        assert_eq!(detector.detect("€10 €20 ¥5"), Some(Currency::EUR));
+    }
+
+    #[test]
+    fn test_amount_is_exact() {
+        let total: Amount = std::iter::repeat_n(Amount::parse_decimal("0.10").unwrap(), 1000).sum();
+        assert_eq!(total.to_string(), "100.00");
+        assert_eq!(Amount::parse_decimal("12.345").unwrap().to_string(), "12.35");
+        assert_eq!(Amount::from_cents(-5).to_string(), "-0.05");
+        assert_eq!(Amount::from_cents(1234550).format_with(0), "12346");
+    }
+
+    #[test]
+    fn test_amount_serde_round_trip() {
+        let amount = Amount::from_cents(31760);
+        let json = serde_json::to_string(&amount).unwrap();
+        assert_eq!(json, "\"317.60\"");
+        assert_eq!(serde_json::from_str::<Amount>(&json).unwrap(), amount);
+        assert_eq!(serde_json::from_str::<Amount>("317.6").unwrap(), amount);
+    }
+}
@@ -1,46 +1,137 @@
 use std::path::Path;
+use std::collections::BTreeMap;
+use serde::Serialize;
 use crate::types::{PdfFile, ImageFile, RenamePreview};
 use crate::file_service::FileService;
+use crate::config::{Language, RenameStrategyConfig};
+use crate::document_classifier::DocumentType;
+use crate::expense_category::{UNCATEGORIZED, UNCATEGORIZED_EN};
+use crate::i18n::localized;
+use crate::money::{Amount, Currency, Money};
 use chrono::{DateTime, Utc};
 
+/// 金额未知时 {金额} 的替代文本
+const UNKNOWN_AMOUNT: &str = "未知金额";
+const UNKNOWN_AMOUNT_EN: &str = "Unknown";
+
+/// 英文界面的默认发票文件名模板，如 Invoice_317.60CNY.pdf
+pub const ENGLISH_FILENAME_TEMPLATE: &str = "Invoice_{amount}{currency}.pdf";
+
+/// 图片文件名前缀
+const IMAGE_PREFIX: &str = "支付凭证";
+const IMAGE_PREFIX_EN: &str = "PaymentProof";
+
 pub struct NamingEngine {
     #[allow(dead_code)]
     file_service: FileService,
+    /// 发票文件名模板
+    filename_template: String,
+    /// 生成文件名使用的语言
+    language: Language,
 }
 
 impl NamingEngine {
     pub fn new() -> Self {
         NamingEngine {
             file_service: FileService::new(),
+            filename_template: RenameStrategyConfig::default().filename_template,
+            language: Language::Chinese,
         }
     }
 
-    /// 生成基于金额的文件名
-    /// 格式：{金额}元_发票.pdf
-    pub fn generate_filename(&self, amount: f64) -> String {
-        format!("{:.2}元_发票.pdf", amount)
+    /// 设置生成文件名使用的语言（来自界面配置中的 language）
+    pub fn set_language(&mut self, language: Language) {
+        self.language = language;
+    }
+
+    /// 设置发票文件名模板（来自配置中的 filename_template）
+    pub fn set_filename_template(&mut self, template: &str) {
+        self.filename_template = if template.trim().is_empty() {
+            RenameStrategyConfig::default().filename_template
+        } else {
+            template.to_string()
+        };
+    }
+
+    /// 实际使用的模板：未自定义时（中文或英文默认模板）按语言选择默认模板，
+    /// 英文占位符 {amount}、{currency}、{type}、{category} 换成对应的中文占位符
+    fn template(&self) -> String {
+        let default_template = RenameStrategyConfig::default().filename_template;
+        let template = if self.filename_template == default_template || self.filename_template == ENGLISH_FILENAME_TEMPLATE {
+            match self.language {
+                Language::Chinese => default_template,
+                Language::English => ENGLISH_FILENAME_TEMPLATE.to_string(),
+            }
+        } else {
+            self.filename_template.clone()
+        };
+        template
+            .replace("{amount}", "{金额}")
+            .replace("{currency}", "{币种}")
+            .replace("{type}", "{类型}")
+            .replace("{category}", "{类别}")
+    }
+
+    /// 按模板生成发票文件名
+    /// 支持的占位符：{金额}、{币种}、{类型}、{类别}（或 {amount}、{currency}、{type}、{category}），
+    /// 默认格式：{金额}元_发票.pdf，英文界面为 Invoice_{amount}{currency}.pdf
+    ///
+    /// 外币金额会把模板中的"{金额}元"写成"{金额}{币种代码}"，如 120.00USD_发票.pdf
+    pub fn generate_filename(&self, amount: Option<Money>, document_type: DocumentType, category: Option<&str>) -> String {
+        let filename_template = self.template();
+        let unknown_amount = localized(self.language, UNKNOWN_AMOUNT, UNKNOWN_AMOUNT_EN);
+        let name = match amount {
+            Some(money) => {
+                let template = if money.currency == Currency::CNY {
+                    filename_template
+                } else {
+                    filename_template.replace("{金额}元", "{金额}{币种}")
+                };
+                template
+                    .replace("{金额}", &money.format_amount())
+                    .replace("{币种}", money.currency.code())
+            }
+            // 金额未知时去掉紧随其后的"元"，得到"未知金额_发票.pdf"
+            None => filename_template
+                .replace("{金额}元", unknown_amount)
+                .replace("{金额}", unknown_amount)
+                .replace("{币种}", ""),
+        };
+        let document_type_name = match self.language {
+            Language::Chinese => document_type.display_name(),
+            Language::English => document_type.english_name(),
+        };
+        let name = name
+            .replace("{类型}", document_type_name)
+            .replace("{类别}", category.unwrap_or(localized(self.language, UNCATEGORIZED, UNCATEGORIZED_EN)));
+
+        self.file_service.sanitize_filename(&name)
     }
 
     /// 为图片文件生成建议的文件名
-    /// 格式：支付凭证_{YYYY-MM-DD}_{原文件名}.{扩展名}
+    /// 格式：支付凭证_{YYYY-MM-DD}_{原文件名}.{扩展名}，英文界面为 PaymentProof_…
     pub fn generate_image_filename(&self, original_name: &str, modified: &DateTime<Utc>) -> String {
         let date_str = modified.format("%Y-%m-%d").to_string();
         let (stem, ext) = self.split_filename(original_name);
-        format!("支付凭证_{}_{}.{}", date_str, stem, ext)
+        let prefix = localized(self.language, IMAGE_PREFIX, IMAGE_PREFIX_EN);
+        format!("{}_{}_{}.{}", prefix, date_str, stem, ext)
+    }
+
+    /// 为已配对的发票和支付凭证添加相同的序号前缀，如 001_317.60元_发票.pdf
+    pub fn with_pair_prefix(&self, pair_number: usize, filename: &str) -> String {
+        format!("{:03}_{}", pair_number, filename)
     }
 
     /// 格式化金额显示
     #[allow(dead_code)]
-    pub fn format_amount(&self, amount: f64) -> String {
-        format!("{:.2}", amount)
+    pub fn format_amount(&self, amount: Amount) -> String {
+        amount.to_string()
     }
 
     /// 为PDF文件生成建议的文件名
     pub fn generate_suggested_name(&self, pdf_file: &PdfFile) -> String {
-        match pdf_file.amount {
-            Some(amount) => self.generate_filename(amount),
-            None => "未知金额_发票.pdf".to_string(),
-        }
+        let amount = pdf_file.amount.map(|amount| Money::new(amount, pdf_file.currency));
+        self.generate_filename(amount, pdf_file.document_type, pdf_file.category.as_deref())
     }
 
     /// 批量生成重命名预览
@@ -53,12 +144,13 @@ impl NamingEngine {
                 original_name: file.name.clone(),
                 suggested_name,
                 amount: file.amount,
+                currency: file.currency,
             }
         }).collect()
     }
 
     /// 解决文件名冲突
-    pub fn resolve_naming_conflicts(&self, files: &[PdfFile]) -> Vec<String> {
+    pub fn resolve_naming_conflicts<'a>(&self, files: impl IntoIterator<Item = &'a PdfFile>) -> Vec<String> {
         let mut name_counts = std::collections::HashMap::new();
         let mut resolved_names = Vec::new();
 
@@ -150,7 +242,7 @@ impl NamingEngine {
         if let Some(amount_part) = filename.strip_suffix("_发票.pdf") {
             if amount_part.ends_with("元") {
                 let amount_str = amount_part.strip_suffix("元").unwrap_or("");
-                if amount_str.parse::<f64>().is_ok() {
+                if Amount::parse_decimal(amount_str).is_some() {
                     return self.file_service.validate_filename(filename);
                 }
             }
@@ -199,36 +291,61 @@ impl NamingEngine {
         let files_with_amount = files.iter().filter(|f| f.amount.is_some()).count();
         let files_without_amount = total_files - files_with_amount;
         
-        let total_amount: f64 = files.iter()
-            .filter_map(|f| f.amount)
-            .sum();
+        // 不同币种分别汇总，total_amount 只统计人民币
+        let mut totals_by_currency: BTreeMap<Currency, Amount> = BTreeMap::new();
+        for file in files {
+            if let Some(amount) = file.amount {
+                *totals_by_currency.entry(file.currency).or_insert(Amount::ZERO) += amount;
+            }
+        }
+        let total_amount = totals_by_currency.get(&Currency::CNY).copied().unwrap_or(Amount::ZERO);
         
         RenameStats {
             total_files,
             files_with_amount,
             files_without_amount,
             total_amount,
+            totals_by_currency,
         }
     }
 }
 
 /// 重命名统计信息
 #[allow(dead_code)]
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct RenameStats {
     pub total_files: usize,
     pub files_with_amount: usize,
     pub files_without_amount: usize,
-    pub total_amount: f64,
+    /// 人民币金额合计
+    pub total_amount: Amount,
+    /// 按币种汇总的金额
+    pub totals_by_currency: BTreeMap<Currency, Amount>,
 }
 
 impl RenameStats {
     #[allow(dead_code)]
-    pub fn average_amount(&self) -> f64 {
-        if self.files_with_amount > 0 {
-            self.total_amount / self.files_with_amount as f64
-        } else {
-            0.0
-        }
+    pub fn average_amount(&self) -> Amount {
+        self.total_amount.average(self.files_with_amount)
     }
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_english_default_template() {
+        let mut engine = NamingEngine::new();
+        let amount = Some(Money::new(Amount::from_cents(31760), Currency::CNY));
+        assert_eq!(engine.generate_filename(amount, DocumentType::Unknown, None), "317.60元_发票.pdf");
+
+        engine.set_language(Language::English);
+        assert_eq!(engine.generate_filename(amount, DocumentType::Unknown, None), "Invoice_317.60CNY.pdf");
+        assert_eq!(engine.generate_filename(None, DocumentType::Unknown, None), "Invoice_Unknown.pdf");
+
+        // 自定义模板不随语言切换，英文占位符同样可用
+        engine.set_filename_template("{type}_{amount}元.pdf");
+        assert_eq!(engine.generate_filename(amount, DocumentType::TaxiReceipt, None), "Taxi Receipt_317.60元.pdf");
+    }
+}
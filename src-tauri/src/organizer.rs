@@ -0,0 +1,317 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use chrono::Datelike;
+use crate::config::{ConflictResolution, OrganizeConfig};
+use crate::expense_category::{UNCATEGORIZED, UNCATEGORIZED_EN};
+use crate::file_service::FileService;
+use crate::error::AppError;
+use crate::i18n::{tr, trf};
+use crate::types::{PdfFile, RenameResult};
+
+/// 销售方未知时 {销售方} 的替代文本
+const UNKNOWN_SELLER: &str = "未知销售方";
+const UNKNOWN_SELLER_EN: &str = "Unknown Seller";
+
+/// 归档计划中的单个文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrganizeEntry {
+    pub source_path: String,
+    /// 相对于归档根目录的子目录，如 "2024/03/某某公司"
+    pub target_directory: String,
+    pub target_name: String,
+    pub target_path: String,
+    pub action: OrganizeAction,
+}
+
+/// 归档时对单个文件的处理
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OrganizeAction {
+    /// 直接移动
+    Move,
+    /// 目标已存在，添加序号后移动
+    MoveRenamed,
+    /// 覆盖已存在的文件
+    Overwrite,
+    /// 跳过
+    Skip,
+    /// 目标已存在，等待用户选择处理方式
+    Conflict,
+}
+
+/// 预览用的目录树节点
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DirectoryNode {
+    pub name: String,
+    pub files: Vec<String>,
+    pub children: Vec<DirectoryNode>,
+}
+
+impl DirectoryNode {
+    fn insert(&mut self, directory: &str, file_name: &str) {
+        let mut node = self;
+        for component in directory.split('/').filter(|c| !c.is_empty()) {
+            let index = match node.children.iter().position(|child| child.name == component) {
+                Some(index) => index,
+                None => {
+                    node.children.push(DirectoryNode {
+                        name: component.to_string(),
+                        ..Default::default()
+                    });
+                    node.children.len() - 1
+                }
+            };
+            node = &mut node.children[index];
+        }
+        node.files.push(file_name.to_string());
+    }
+}
+
+/// 归档预览
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OrganizePreview {
+    pub archive_root: String,
+    pub entries: Vec<OrganizeEntry>,
+    /// 归档后的目录树（不含跳过的文件）
+    pub tree: DirectoryNode,
+    /// 存在待确认冲突的目标目录
+    pub conflict_directories: Vec<String>,
+}
+
+/// 按目录模板把发票归档到目录树中
+pub struct Organizer {
+    file_service: FileService,
+    directory_template: String,
+}
+
+impl Organizer {
+    pub fn new() -> Self {
+        Organizer {
+            file_service: FileService::new(),
+            directory_template: OrganizeConfig::default().directory_template,
+        }
+    }
+
+    /// 设置子目录模板（来自配置中的 directory_template）
+    pub fn set_directory_template(&mut self, template: &str) {
+        self.directory_template = if template.trim().is_empty() {
+            OrganizeConfig::default().directory_template
+        } else {
+            template.to_string()
+        };
+    }
+
+    /// 按模板生成文件的目标子目录（使用 `/` 分隔）
+    ///
+    /// 没有开票日期时按文件修改日期归档
+    pub fn target_directory(&self, file: &PdfFile) -> String {
+        let date = file.issue_date.unwrap_or_else(|| file.modified.date_naive());
+        let directory = self.directory_template
+            .replace("{年}", &format!("{:04}", date.year()))
+            .replace("{月}", &format!("{:02}", date.month()))
+            .replace("{日}", &format!("{:02}", date.day()))
+            .replace("{销售方}", file.seller.as_deref().unwrap_or(tr(UNKNOWN_SELLER, UNKNOWN_SELLER_EN)))
+            .replace("{类型}", tr(file.document_type.display_name(), file.document_type.english_name()))
+            .replace("{类别}", file.category.as_deref().unwrap_or(tr(UNCATEGORIZED, UNCATEGORIZED_EN)))
+            .replace("{币种}", file.currency.code());
+
+        // 每一级目录单独清理，避免模板或销售方名称中出现 ".." 等路径
+        directory.split(['/', '\\'])
+            .map(|component| self.file_service.sanitize_filename(component.trim()))
+            .filter(|component| !component.is_empty() && component != "." && component != "..")
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// 生成归档计划
+    ///
+    /// `items` 为文件及其重命名后的文件名；冲突按目标目录分别处理，
+    /// `overrides` 中没有的目录使用 `default_resolution`
+    pub fn plan(
+        &self,
+        archive_root: &str,
+        items: &[(&PdfFile, String)],
+        default_resolution: ConflictResolution,
+        overrides: &HashMap<String, ConflictResolution>,
+    ) -> OrganizePreview {
+        let root = Path::new(archive_root);
+        // 每个目标目录中已被本批文件占用的文件名
+        let mut taken: HashMap<String, HashSet<String>> = HashMap::new();
+        let mut entries = Vec::new();
+        let mut conflict_directories = Vec::new();
+
+        for (file, name) in items {
+            let target_directory = self.target_directory(file);
+            let full_directory = root.join(&target_directory);
+            let resolution = overrides.get(&target_directory).copied().unwrap_or(default_resolution);
+            let taken_names = taken.entry(target_directory.clone()).or_default();
+
+            let in_place = full_directory.join(name) == Path::new(&file.path);
+            let on_disk = full_directory.join(name).exists();
+            let in_batch = taken_names.contains(name);
+
+            let (action, target_name) = if in_place {
+                (OrganizeAction::Skip, name.clone())
+            } else if !on_disk && !in_batch {
+                (OrganizeAction::Move, name.clone())
+            } else {
+                match resolution {
+                    ConflictResolution::Skip => (OrganizeAction::Skip, name.clone()),
+                    ConflictResolution::Ask => (OrganizeAction::Conflict, name.clone()),
+                    // 同一批文件之间不能互相覆盖
+                    ConflictResolution::Overwrite if !in_batch => (OrganizeAction::Overwrite, name.clone()),
+                    _ => (OrganizeAction::MoveRenamed, self.next_free_name(&full_directory, name, taken_names)),
+                }
+            };
+
+            if action == OrganizeAction::Conflict && !conflict_directories.contains(&target_directory) {
+                conflict_directories.push(target_directory.clone());
+            }
+            if action != OrganizeAction::Skip && action != OrganizeAction::Conflict {
+                taken_names.insert(target_name.clone());
+            }
+
+            entries.push(OrganizeEntry {
+                source_path: file.path.clone(),
+                target_path: full_directory.join(&target_name).to_string_lossy().to_string(),
+                target_directory,
+                target_name,
+                action,
+            });
+        }
+
+        let mut tree = DirectoryNode {
+            name: archive_root.to_string(),
+            ..Default::default()
+        };
+        for entry in entries.iter().filter(|entry| entry.action != OrganizeAction::Skip) {
+            tree.insert(&entry.target_directory, &entry.target_name);
+        }
+
+        OrganizePreview {
+            archive_root: archive_root.to_string(),
+            entries,
+            tree,
+            conflict_directories,
+        }
+    }
+
+    /// 添加序号，直到文件名在磁盘和本批文件中都不重复
+    fn next_free_name(&self, directory: &Path, name: &str, taken: &HashSet<String>) -> String {
+        let path = Path::new(name);
+        let stem = path.file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| name.to_string());
+        let extension = path.extension()
+            .map(|e| format!(".{}", e.to_string_lossy()))
+            .unwrap_or_default();
+
+        (1..)
+            .map(|counter| format!("{}_{}{}", stem, counter, extension))
+            .find(|candidate| !taken.contains(candidate) && !directory.join(candidate).exists())
+            .unwrap_or_else(|| name.to_string())
+    }
+
+    /// 按归档计划移动文件
+    pub fn execute(&self, entries: &[OrganizeEntry]) -> RenameResult {
+        let mut success_count = 0;
+        let mut skipped_count = 0;
+        let mut failed_files = Vec::new();
+
+        for entry in entries {
+            let result = match entry.action {
+                OrganizeAction::Skip => {
+                    skipped_count += 1;
+                    continue;
+                }
+                OrganizeAction::Conflict => Err(AppError::file_system_error(tr("目标文件已存在，请先选择处理方式", "Target file already exists, choose how to handle it first"))),
+                OrganizeAction::Overwrite => self.file_service.move_file(&entry.source_path, &entry.target_path, true),
+                OrganizeAction::Move | OrganizeAction::MoveRenamed => {
+                    self.file_service.move_file(&entry.source_path, &entry.target_path, false)
+                }
+            };
+
+            match result {
+                Ok(()) => success_count += 1,
+                Err(e) => failed_files.push(format!("{}: {}", entry.source_path, e)),
+            }
+        }
+
+        let success = failed_files.is_empty();
+        let message = if success {
+            trf("成功归档 {} 个文件，跳过 {} 个文件", "Archived {} files, skipped {}", &[&success_count, &skipped_count])
+        } else {
+            trf("成功归档 {} 个文件，跳过 {} 个文件，失败 {} 个文件", "Archived {} files, skipped {}, {} failed", &[&success_count, &skipped_count, &failed_files.len()])
+        };
+
+        RenameResult {
+            success,
+            message,
+            processed_files: success_count + failed_files.len(),
+            failed_files,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDate, Utc};
+    use crate::document_classifier::DocumentType;
+    use crate::money::Currency;
+    use crate::types::ScanStatus;
+
+    fn pdf_file(path: &str, seller: Option<&str>) -> PdfFile {
+        PdfFile {
+            id: path.to_string(),
+            name: path.to_string(),
+            path: path.to_string(),
+            size: 0,
+            modified: Utc::now(),
+            amount: None,
+            currency: Currency::CNY,
+            document_type: DocumentType::Unknown,
+            seller: seller.map(|s| s.to_string()),
+            issue_date: NaiveDate::from_ymd_opt(2024, 3, 1),
+            invoice_count: 1,
+            suggested_name: None,
+            category: None,
+            validation: Vec::new(),
+            buyer_mismatch: false,
+            status: ScanStatus::Ok,
+            status_detail: None,
+        }
+    }
+
+    #[test]
+    fn test_target_directory() {
+        let organizer = Organizer::new();
+        assert_eq!(organizer.target_directory(&pdf_file("a.pdf", Some("某某/公司"))), "2024/03/某某/公司");
+        assert_eq!(organizer.target_directory(&pdf_file("a.pdf", None)), "2024/03/未知销售方");
+    }
+
+    #[test]
+    fn test_plan_resolves_conflicts_per_directory() {
+        let organizer = Organizer::new();
+        let a = pdf_file("/in/a.pdf", Some("甲公司"));
+        let b = pdf_file("/in/b.pdf", Some("甲公司"));
+        let c = pdf_file("/in/c.pdf", Some("乙公司"));
+        let items = vec![
+            (&a, "100.00元_发票.pdf".to_string()),
+            (&b, "100.00元_发票.pdf".to_string()),
+            (&c, "100.00元_发票.pdf".to_string()),
+        ];
+
+        let mut overrides = HashMap::new();
+        overrides.insert("2024/03/甲公司".to_string(), ConflictResolution::Ask);
+        let preview = organizer.plan("/nonexistent-root", &items, ConflictResolution::AddSuffix, &overrides);
+
+        let actions: Vec<_> = preview.entries.iter().map(|e| e.action).collect();
+        assert_eq!(actions, vec![OrganizeAction::Move, OrganizeAction::Conflict, OrganizeAction::Move]);
+        assert_eq!(preview.conflict_directories, vec!["2024/03/甲公司".to_string()]);
+
+        let preview = organizer.plan("/nonexistent-root", &items, ConflictResolution::AddSuffix, &HashMap::new());
+        assert_eq!(preview.entries[1].target_name, "100.00元_发票_1.pdf");
+        assert_eq!(preview.tree.children[0].children[0].children.len(), 2);
+    }
+}
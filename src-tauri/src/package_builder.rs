@@ -0,0 +1,509 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, NaiveDate, Utc};
+use lopdf::{dictionary, Document, Object, ObjectId};
+use rust_xlsxwriter::{Format, Workbook};
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+use crate::document_classifier::DocumentType;
+use crate::error::{AppError, AppResult};
+use crate::i18n::{tr, trf};
+use crate::ledger::Ledger;
+use crate::money::{Amount, Currency};
+use crate::types::LineItem;
+
+/// 报销包内的固定目录和文件名
+const INVOICE_DIRECTORY: (&str, &str) = ("发票", "Invoices");
+const PROOF_DIRECTORY: (&str, &str) = ("支付凭证", "Payment proofs");
+const SUMMARY_XLSX: (&str, &str) = ("汇总表.xlsx", "Summary.xlsx");
+const SUMMARY_CSV: (&str, &str) = ("汇总表.csv", "Summary.csv");
+const LINE_ITEMS_CSV: (&str, &str) = ("明细表.csv", "Line items.csv");
+const MERGED_PDF: (&str, &str) = ("发票合并打印.pdf", "Invoices for printing.pdf");
+const MANIFEST: &str = "manifest.json";
+
+/// 页面可从上级页面树继承的属性
+const INHERITABLE_PAGE_KEYS: [&[u8]; 4] = [b"Resources", b"MediaBox", b"CropBox", b"Rotate"];
+
+/// 报销包中的文件类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PackageItemKind {
+    Invoice,
+    PaymentProof,
+}
+
+/// 放入报销包的文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageItem {
+    pub source_path: String,
+    /// 包内文件名（重命名后）
+    pub package_name: String,
+    pub kind: PackageItemKind,
+    pub amount: Option<Amount>,
+    pub currency: Currency,
+    pub document_type: DocumentType,
+    pub seller: Option<String>,
+    pub issue_date: Option<NaiveDate>,
+    /// 费用类别
+    pub category: Option<String>,
+    /// 发票明细行
+    pub line_items: Vec<LineItem>,
+}
+
+/// 清单中的单个文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// 包内路径
+    pub path: String,
+    pub sha256: String,
+    pub size: u64,
+}
+
+/// 报销包清单（manifest.json），用于财务收到后校验文件是否完整
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageManifest {
+    pub created_at: DateTime<Utc>,
+    pub invoice_count: usize,
+    pub payment_proof_count: usize,
+    /// 按币种汇总的发票金额
+    pub totals_by_currency: BTreeMap<Currency, Amount>,
+    pub files: Vec<ManifestEntry>,
+}
+
+/// 生成报销包的结果
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PackageResult {
+    pub output_path: String,
+    pub manifest: PackageManifest,
+    /// 未能合并进打印PDF的文件等提示
+    pub warnings: Vec<String>,
+}
+
+/// 报销包生成器
+pub struct PackageBuilder;
+
+impl PackageBuilder {
+    pub fn new() -> Self {
+        PackageBuilder
+    }
+
+    /// 生成报销包ZIP
+    ///
+    /// 包内结构：发票/、支付凭证/、汇总表.xlsx、汇总表.csv、发票合并打印.pdf、manifest.json，
+    /// 识别到发票明细时另有明细表.csv（汇总表.xlsx 中也有"明细"工作表）
+    pub fn build(&self, items: &[PackageItem], output_path: &Path) -> AppResult<PackageResult> {
+        if items.is_empty() {
+            return Err(AppError::validation_error(tr("没有选择任何文件", "No files selected")));
+        }
+
+        // 包内路径，同一目录下重名时添加序号
+        let mut used_paths = HashSet::new();
+        let package_paths: Vec<String> = items.iter()
+            .map(|item| {
+                let (zh, en) = match item.kind {
+                    PackageItemKind::Invoice => INVOICE_DIRECTORY,
+                    PackageItemKind::PaymentProof => PROOF_DIRECTORY,
+                };
+                Self::unique_path(tr(zh, en), &item.package_name, &mut used_paths)
+            })
+            .collect();
+
+        let mut warnings = Vec::new();
+        let mut contents: Vec<(String, Vec<u8>)> = Vec::new();
+
+        for (item, package_path) in items.iter().zip(&package_paths) {
+            let bytes = std::fs::read(&item.source_path)
+                .map_err(|e| AppError::io_error_at(e, tr("读取文件失败", "Failed to read file"), Path::new(&item.source_path)))?;
+            contents.push((package_path.clone(), bytes));
+        }
+
+        contents.push((tr(SUMMARY_XLSX.0, SUMMARY_XLSX.1).to_string(), Self::summary_xlsx(items, &package_paths)?));
+        contents.push((tr(SUMMARY_CSV.0, SUMMARY_CSV.1).to_string(), Self::summary_csv(items, &package_paths)?));
+        if items.iter().any(|item| !item.line_items.is_empty()) {
+            contents.push((tr(LINE_ITEMS_CSV.0, LINE_ITEMS_CSV.1).to_string(), Self::line_items_csv(items, &package_paths)?));
+        }
+
+        let invoice_paths: Vec<&str> = items.iter()
+            .filter(|item| item.kind == PackageItemKind::Invoice)
+            .map(|item| item.source_path.as_str())
+            .collect();
+        if !invoice_paths.is_empty() {
+            match Self::merge_pdfs(&invoice_paths, &mut warnings) {
+                Ok(Some(bytes)) => contents.push((tr(MERGED_PDF.0, MERGED_PDF.1).to_string(), bytes)),
+                Ok(None) => {}
+                Err(e) => warnings.push(trf("生成合并打印PDF失败: {}", "Failed to generate the merged print PDF: {}", &[&e])),
+            }
+        }
+
+        let manifest = PackageManifest {
+            created_at: Utc::now(),
+            invoice_count: items.iter().filter(|item| item.kind == PackageItemKind::Invoice).count(),
+            payment_proof_count: items.iter().filter(|item| item.kind == PackageItemKind::PaymentProof).count(),
+            totals_by_currency: Self::totals_by_currency(items),
+            files: contents.iter()
+                .map(|(path, bytes)| ManifestEntry {
+                    path: path.clone(),
+                    sha256: Ledger::hash_bytes(bytes),
+                    size: bytes.len() as u64,
+                })
+                .collect(),
+        };
+        let manifest_json = serde_json::to_vec_pretty(&manifest)
+            .map_err(|e| AppError::validation_error(&trf("序列化清单失败: {}", "Failed to serialize manifest: {}", &[&e])))?;
+        contents.push((MANIFEST.to_string(), manifest_json));
+
+        Self::write_zip(output_path, &contents).map_err(|e| e.with_path(output_path))?;
+
+        Ok(PackageResult {
+            output_path: output_path.to_string_lossy().to_string(),
+            manifest,
+            warnings,
+        })
+    }
+
+    fn unique_path(directory: &str, name: &str, used: &mut HashSet<String>) -> String {
+        let path = Path::new(name);
+        let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_else(|| name.to_string());
+        let extension = path.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+
+        let mut candidate = format!("{}/{}", directory, name);
+        let mut counter = 1;
+        while used.contains(&candidate) {
+            candidate = format!("{}/{}_{}{}", directory, stem, counter, extension);
+            counter += 1;
+        }
+        used.insert(candidate.clone());
+        candidate
+    }
+
+    fn totals_by_currency(items: &[PackageItem]) -> BTreeMap<Currency, Amount> {
+        let mut totals = BTreeMap::new();
+        for item in items.iter().filter(|item| item.kind == PackageItemKind::Invoice) {
+            if let Some(amount) = item.amount {
+                *totals.entry(item.currency).or_insert(Amount::ZERO) += amount;
+            }
+        }
+        totals
+    }
+
+    /// 汇总表的表头和各行内容
+    fn summary_rows(items: &[PackageItem], package_paths: &[String]) -> (Vec<&'static str>, Vec<Vec<String>>) {
+        let header = vec![
+            tr("序号", "No."), tr("类别", "Kind"), tr("包内文件", "Package file"), tr("票据类型", "Document type"),
+            tr("开票日期", "Issue date"), tr("销售方", "Seller"), tr("金额", "Amount"), tr("币种", "Currency"),
+            tr("原文件", "Source file"), tr("费用类别", "Category"),
+        ];
+        let rows = items.iter().zip(package_paths).enumerate()
+            .map(|(index, (item, package_path))| {
+                vec![
+                    (index + 1).to_string(),
+                    match item.kind {
+                        PackageItemKind::Invoice => tr("发票", "Invoice").to_string(),
+                        PackageItemKind::PaymentProof => tr("支付凭证", "Payment proof").to_string(),
+                    },
+                    package_path.clone(),
+                    match item.kind {
+                        PackageItemKind::Invoice => tr(item.document_type.display_name(), item.document_type.english_name()).to_string(),
+                        PackageItemKind::PaymentProof => String::new(),
+                    },
+                    item.issue_date.map(|date| date.to_string()).unwrap_or_default(),
+                    item.seller.clone().unwrap_or_default(),
+                    item.amount.map(|amount| amount.to_string()).unwrap_or_default(),
+                    if item.amount.is_some() { item.currency.code().to_string() } else { String::new() },
+                    Path::new(&item.source_path).file_name()
+                        .map(|name| name.to_string_lossy().to_string())
+                        .unwrap_or_default(),
+                    item.category.clone().unwrap_or_default(),
+                ]
+            })
+            .collect();
+        (header, rows)
+    }
+
+    fn summary_xlsx(items: &[PackageItem], package_paths: &[String]) -> AppResult<Vec<u8>> {
+        let (header, rows) = Self::summary_rows(items, package_paths);
+        let mut workbook = Workbook::new();
+        let bold = Format::new().set_bold();
+        let money = Format::new().set_num_format("0.00");
+        let amount_column = 6;
+
+        let sheet = workbook.add_worksheet();
+        sheet.set_name(tr("汇总", "Summary"))?;
+        for (col, title) in header.iter().enumerate() {
+            sheet.write_string_with_format(0, col as u16, *title, &bold)?;
+        }
+        for (row_index, (row, item)) in rows.iter().zip(items).enumerate() {
+            let row_number = row_index as u32 + 1;
+            for (col, value) in row.iter().enumerate() {
+                match item.amount {
+                    Some(amount) if col == amount_column => {
+                        sheet.write_number_with_format(row_number, col as u16, amount.cents() as f64 / 100.0, &money)?;
+                    }
+                    _ => {
+                        sheet.write_string(row_number, col as u16, value)?;
+                    }
+                }
+            }
+        }
+
+        // 按币种合计
+        let first_total_row = rows.len() as u32 + 2;
+        for (row_number, (currency, total)) in (first_total_row..).zip(Self::totals_by_currency(items)) {
+            sheet.write_string_with_format(row_number, 5, tr("合计", "Total"), &bold)?;
+            sheet.write_number_with_format(row_number, amount_column as u16, total.cents() as f64 / 100.0, &money)?;
+            sheet.write_string(row_number, 7, currency.code())?;
+        }
+        sheet.autofit();
+
+        // 发票明细单独一个工作表
+        let (header, rows) = Self::line_item_rows(items, package_paths);
+        if !rows.is_empty() {
+            let sheet = workbook.add_worksheet();
+            sheet.set_name(tr("明细", "Line items"))?;
+            for (col, title) in header.iter().enumerate() {
+                sheet.write_string_with_format(0, col as u16, *title, &bold)?;
+            }
+            for (row_number, row) in (1u32..).zip(&rows) {
+                for (col, value) in row.iter().enumerate() {
+                    sheet.write_string(row_number, col as u16, value)?;
+                }
+            }
+            sheet.autofit();
+        }
+
+        Ok(workbook.save_to_buffer()?)
+    }
+
+    fn summary_csv(items: &[PackageItem], package_paths: &[String]) -> AppResult<Vec<u8>> {
+        let (header, rows) = Self::summary_rows(items, package_paths);
+        // 带BOM，Excel 直接打开时不乱码
+        let mut writer = csv::Writer::from_writer(b"\xEF\xBB\xBF".to_vec());
+        writer.write_record(&header)?;
+        for row in &rows {
+            writer.write_record(row)?;
+        }
+        for (currency, total) in Self::totals_by_currency(items) {
+            writer.write_record(["", "", "", "", "", tr("合计", "Total"), &total.to_string(), currency.code(), "", ""])?;
+        }
+        Ok(writer.into_inner()?)
+    }
+
+    /// 明细表的表头和各行（每个明细行一行，带所属发票）
+    fn line_item_rows(items: &[PackageItem], package_paths: &[String]) -> (Vec<&'static str>, Vec<Vec<String>>) {
+        let header = vec![
+            tr("包内文件", "Package file"), tr("项目名称", "Item"), tr("规格型号", "Specification"), tr("单位", "Unit"),
+            tr("数量", "Quantity"), tr("单价", "Unit price"), tr("金额", "Amount"), tr("税率", "Tax rate"), tr("税额", "Tax"),
+        ];
+        let rows = items.iter().zip(package_paths)
+            .flat_map(|(item, package_path)| {
+                item.line_items.iter().map(move |line| vec![
+                    package_path.clone(),
+                    line.name.clone(),
+                    line.specification.clone().unwrap_or_default(),
+                    line.unit.clone().unwrap_or_default(),
+                    line.quantity.clone().unwrap_or_default(),
+                    line.unit_price.clone().unwrap_or_default(),
+                    line.amount.map(|amount| amount.to_string()).unwrap_or_default(),
+                    line.tax_rate.clone().unwrap_or_default(),
+                    line.tax_amount.map(|amount| amount.to_string()).unwrap_or_default(),
+                ])
+            })
+            .collect();
+        (header, rows)
+    }
+
+    fn line_items_csv(items: &[PackageItem], package_paths: &[String]) -> AppResult<Vec<u8>> {
+        let (header, rows) = Self::line_item_rows(items, package_paths);
+        let mut writer = csv::Writer::from_writer(b"\xEF\xBB\xBF".to_vec());
+        writer.write_record(&header)?;
+        for row in &rows {
+            writer.write_record(row)?;
+        }
+        Ok(writer.into_inner()?)
+    }
+
+    /// 把多个PDF按顺序合并为一个，无法读取的文件跳过并记入 warnings
+    fn merge_pdfs(paths: &[&str], warnings: &mut Vec<String>) -> AppResult<Option<Vec<u8>>> {
+        let mut merged = Document::with_version("1.5");
+        let mut page_ids: Vec<ObjectId> = Vec::new();
+
+        for path in paths {
+            let mut document = match Document::load(path) {
+                Ok(document) if !document.is_encrypted() => document,
+                Ok(_) => {
+                    warnings.push(trf("{}: 加密的PDF未合并", "{}: encrypted PDF was not merged", &[path]));
+                    continue;
+                }
+                Err(e) => {
+                    warnings.push(trf("{}: 无法读取，未合并: {}", "{}: unreadable, not merged: {}", &[path, &e]));
+                    continue;
+                }
+            };
+            document.renumber_objects_with(merged.max_id + 1);
+
+            let pages: Vec<ObjectId> = document.get_pages().into_values().collect();
+            // 页面树被替换后，继承自上级节点的属性需要写到页面本身
+            for page_id in &pages {
+                for key in INHERITABLE_PAGE_KEYS {
+                    if let Some(value) = Self::inherited_attribute(&document, *page_id, key) {
+                        if let Ok(page) = document.get_object_mut(*page_id).and_then(Object::as_dict_mut) {
+                            page.set(key, value);
+                        }
+                    }
+                }
+            }
+
+            merged.max_id = document.objects.keys().map(|id| id.0).max().unwrap_or(merged.max_id).max(merged.max_id);
+            page_ids.extend(pages);
+            merged.objects.extend(document.objects);
+        }
+
+        if page_ids.is_empty() {
+            return Ok(None);
+        }
+
+        let pages_id = merged.new_object_id();
+        for page_id in &page_ids {
+            if let Ok(page) = merged.get_object_mut(*page_id).and_then(Object::as_dict_mut) {
+                page.set("Parent", pages_id);
+            }
+        }
+        merged.objects.insert(pages_id, Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => page_ids.iter().map(|id| Object::Reference(*id)).collect::<Vec<_>>(),
+            "Count" => page_ids.len() as i64,
+        }));
+        let catalog_id = merged.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+        });
+        merged.trailer.set("Root", catalog_id);
+
+        // 去掉原文件的目录和页面树等不再引用的对象
+        merged.prune_objects();
+        merged.renumber_objects();
+        merged.compress();
+
+        let mut bytes = Vec::new();
+        merged.save_to(&mut bytes)?;
+        Ok(Some(bytes))
+    }
+
+    /// 页面本身没有而上级页面树节点有的属性
+    fn inherited_attribute(document: &Document, page_id: ObjectId, key: &[u8]) -> Option<Object> {
+        let page = document.get_dictionary(page_id).ok()?;
+        if page.has(key) {
+            return None;
+        }
+
+        let mut parent = page.get(b"Parent").and_then(Object::as_reference).ok();
+        while let Some(parent_id) = parent {
+            let node = document.get_dictionary(parent_id).ok()?;
+            if let Ok(value) = node.get(key) {
+                return Some(value.clone());
+            }
+            parent = node.get(b"Parent").and_then(Object::as_reference).ok();
+        }
+        None
+    }
+
+    fn write_zip(output_path: &Path, contents: &[(String, Vec<u8>)]) -> AppResult<()> {
+        if let Some(parent) = output_path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| AppError::io_error_at(e, tr("无法创建目录", "Unable to create directory"), parent))?;
+        }
+        let file = File::create(output_path)
+            .map_err(|e| AppError::io_error_at(e, tr("无法创建报销包", "Unable to create the reimbursement package"), output_path))?;
+        let mut zip = ZipWriter::new(file);
+        let options = SimpleFileOptions::default();
+
+        for (path, bytes) in contents {
+            zip.start_file(path.as_str(), options)?;
+            zip.write_all(bytes)?;
+        }
+        zip.finish()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn write_pdf(path: &Path, pages: usize) {
+        let mut document = Document::with_version("1.5");
+        let pages_id = document.new_object_id();
+        let kids: Vec<Object> = (0..pages)
+            .map(|_| {
+                let content_id = document.add_object(lopdf::Stream::new(dictionary! {}, b"BT ET".to_vec()));
+                document.add_object(dictionary! {
+                    "Type" => "Page",
+                    "Parent" => pages_id,
+                    "Contents" => content_id,
+                }).into()
+            })
+            .collect();
+        document.objects.insert(pages_id, Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => kids,
+            "Count" => pages as i64,
+            "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+        }));
+        let catalog_id = document.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        document.trailer.set("Root", catalog_id);
+        document.save(path).unwrap();
+    }
+
+    #[test]
+    fn test_build_package() {
+        let dir = std::env::temp_dir().join(format!("invoicepilot-package-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        write_pdf(&dir.join("a.pdf"), 1);
+        write_pdf(&dir.join("b.pdf"), 2);
+        std::fs::write(dir.join("pay.png"), b"png").unwrap();
+
+        let item = |source: &str, name: &str, kind, cents: Option<i64>| PackageItem {
+            source_path: dir.join(source).to_string_lossy().to_string(),
+            package_name: name.to_string(),
+            kind,
+            amount: cents.map(Amount::from_cents),
+            currency: Currency::CNY,
+            document_type: DocumentType::Unknown,
+            seller: None,
+            issue_date: None,
+            category: None,
+            line_items: Vec::new(),
+        };
+        let items = vec![
+            item("a.pdf", "100.00元_发票.pdf", PackageItemKind::Invoice, Some(10000)),
+            item("b.pdf", "100.00元_发票.pdf", PackageItemKind::Invoice, Some(2550)),
+            item("pay.png", "支付凭证.png", PackageItemKind::PaymentProof, None),
+        ];
+
+        let output = dir.join("报销包.zip");
+        let result = PackageBuilder::new().build(&items, &output).unwrap();
+        assert!(result.warnings.is_empty(), "{:?}", result.warnings);
+        assert_eq!(result.manifest.totals_by_currency[&Currency::CNY], Amount::from_cents(12550));
+
+        let mut archive = zip::ZipArchive::new(File::open(&output).unwrap()).unwrap();
+        assert!(archive.by_name("发票/100.00元_发票_1.pdf").is_ok());
+        assert!(archive.by_name("支付凭证/支付凭证.png").is_ok());
+
+        let mut merged = Vec::new();
+        archive.by_name(MERGED_PDF.0).unwrap().read_to_end(&mut merged).unwrap();
+        assert_eq!(Document::load_mem(&merged).unwrap().get_pages().len(), 3);
+
+        // 清单中的哈希与包内文件一致
+        for entry in &result.manifest.files {
+            let mut bytes = Vec::new();
+            archive.by_name(&entry.path).unwrap().read_to_end(&mut bytes).unwrap();
+            assert_eq!(Ledger::hash_bytes(&bytes), entry.sha256);
+        }
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
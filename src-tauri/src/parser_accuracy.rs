@@ -0,0 +1,385 @@
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use chrono::NaiveDate;
+use walkdir::WalkDir;
+use crate::document_classifier::DocumentType;
+use crate::error::{AppError, AppResult};
+use crate::i18n::{tr, trf};
+use crate::money::{Amount, Currency};
+use crate::pdf_service::PdfParser;
+use crate::types::InvoiceInfo;
+
+/// 样本PDF的期望字段，保存在与PDF同名的 .json 文件中
+///
+/// 未填写的字段不参与评估
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExpectedFields {
+    #[serde(default)]
+    pub amount: Option<Amount>,
+    #[serde(default)]
+    pub currency: Option<Currency>,
+    #[serde(default)]
+    pub document_type: Option<DocumentType>,
+    #[serde(default)]
+    pub seller: Option<String>,
+    #[serde(default)]
+    pub buyer_name: Option<String>,
+    #[serde(default)]
+    pub issue_date: Option<NaiveDate>,
+    #[serde(default)]
+    pub invoice_number: Option<String>,
+    #[serde(default)]
+    pub category: Option<String>,
+}
+
+/// 单个字段的识别准确率
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldAccuracy {
+    pub field: String,
+    /// 填写了期望值的样本数
+    pub expected: usize,
+    /// 其中识别出值的样本数
+    pub extracted: usize,
+    /// 识别值与期望值一致的样本数
+    pub correct: usize,
+    /// correct / extracted，没有识别出任何值时为 1
+    pub precision: f64,
+    /// correct / expected，没有期望值时为 1
+    pub recall: f64,
+}
+
+/// 识别值与期望值不一致的字段
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldMismatch {
+    pub file: String,
+    pub field: String,
+    pub expected: String,
+    pub actual: Option<String>,
+}
+
+/// 样本集评估报告
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AccuracyReport {
+    /// 参与评估的样本数
+    pub files_evaluated: usize,
+    /// 没有期望字段文件而跳过的PDF数
+    pub files_skipped: usize,
+    pub fields: Vec<FieldAccuracy>,
+    pub mismatches: Vec<FieldMismatch>,
+    /// 期望字段文件无法读取或PDF无法解析的样本
+    pub errors: Vec<String>,
+}
+
+impl AccuracyReport {
+    /// 按字段名查找准确率
+    #[allow(dead_code)]
+    pub fn field(&self, field: &str) -> Option<&FieldAccuracy> {
+        self.fields.iter().find(|accuracy| accuracy.field == field)
+    }
+}
+
+/// 参与评估的字段：(字段名, 期望值, 识别值)，统一转为文本比较
+fn field_values(expected: &ExpectedFields, info: Option<&InvoiceInfo>) -> Vec<(&'static str, Option<String>, Option<String>)> {
+    vec![
+        ("amount", expected.amount.map(|amount| amount.to_string()), info.and_then(|info| info.amount).map(|amount| amount.to_string())),
+        ("currency", expected.currency.map(|currency| currency.code().to_string()), info.map(|info| info.currency.code().to_string())),
+        (
+            "document_type",
+            expected.document_type.map(|document_type| format!("{:?}", document_type)),
+            info.map(|info| info.document_type)
+                .filter(|document_type| *document_type != DocumentType::Unknown)
+                .map(|document_type| format!("{:?}", document_type)),
+        ),
+        ("seller", expected.seller.clone(), info.and_then(|info| info.seller.clone())),
+        ("buyer_name", expected.buyer_name.clone(), info.and_then(|info| info.vat.buyer_name.clone())),
+        ("issue_date", expected.issue_date.map(|date| date.to_string()), info.and_then(|info| info.issue_date).map(|date| date.to_string())),
+        ("invoice_number", expected.invoice_number.clone(), info.and_then(|info| info.vat.invoice_number.clone())),
+        ("category", expected.category.clone(), info.and_then(|info| info.category.clone())),
+    ]
+}
+
+/// 用样本目录评估 `PdfParser` 的识别准确率
+///
+/// 目录（含子目录）中的每个PDF如有同名 .json 期望字段文件，
+/// 就走完整的 `analyze_pdf` 流程并与期望值比较，按字段统计精确率和召回率。
+pub fn evaluate_corpus(parser: &PdfParser, directory: &Path) -> AppResult<AccuracyReport> {
+    if !directory.is_dir() {
+        return Err(AppError::file_system_error(tr("样本目录不存在", "Sample directory does not exist")).with_path(directory));
+    }
+
+    let mut report = AccuracyReport::default();
+    // (字段名, 期望数, 识别数, 正确数)
+    let mut counts: Vec<(&'static str, usize, usize, usize)> = Vec::new();
+
+    let pdf_paths = WalkDir::new(directory)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.into_path())
+        .filter(|path| path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("pdf")));
+
+    for pdf_path in pdf_paths {
+        let sidecar = pdf_path.with_extension("json");
+        if !sidecar.exists() {
+            report.files_skipped += 1;
+            continue;
+        }
+        let file = pdf_path.strip_prefix(directory).unwrap_or(&pdf_path).to_string_lossy().to_string();
+
+        let expected: ExpectedFields = match std::fs::read_to_string(&sidecar)
+            .map_err(|e| e.to_string())
+            .and_then(|content| serde_json::from_str(&content).map_err(|e| e.to_string()))
+        {
+            Ok(expected) => expected,
+            Err(e) => {
+                report.errors.push(trf("{}: 期望字段文件无效: {}", "{}: invalid expected-fields file: {}", &[&sidecar.display(), &e]));
+                continue;
+            }
+        };
+
+        // 解析失败的样本仍参与评估，所有期望字段都计为未识别
+        let info = match parser.analyze_pdf(&pdf_path.to_string_lossy()) {
+            Ok(info) => Some(info),
+            Err(e) => {
+                report.errors.push(format!("{}: {}", file, e));
+                None
+            }
+        };
+        report.files_evaluated += 1;
+
+        for (field, expected_value, actual) in field_values(&expected, info.as_ref()) {
+            let Some(expected_value) = expected_value else {
+                continue;
+            };
+            let index = match counts.iter().position(|(name, ..)| *name == field) {
+                Some(index) => index,
+                None => {
+                    counts.push((field, 0, 0, 0));
+                    counts.len() - 1
+                }
+            };
+            let count = &mut counts[index];
+            count.1 += 1;
+            if actual.is_some() {
+                count.2 += 1;
+            }
+            if actual.as_deref() == Some(expected_value.as_str()) {
+                count.3 += 1;
+            } else {
+                report.mismatches.push(FieldMismatch {
+                    file: file.clone(),
+                    field: field.to_string(),
+                    expected: expected_value,
+                    actual,
+                });
+            }
+        }
+    }
+
+    let ratio = |numerator: usize, denominator: usize| if denominator == 0 { 1.0 } else { numerator as f64 / denominator as f64 };
+    report.fields = counts.into_iter()
+        .map(|(field, expected, extracted, correct)| FieldAccuracy {
+            field: field.to_string(),
+            expected,
+            extracted,
+            correct,
+            precision: ratio(correct, extracted),
+            recall: ratio(correct, expected),
+        })
+        .collect();
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+    use lopdf::{dictionary, Document, Object, Stream};
+
+    /// 页面上的一段文字：x、距页面顶部的 y、文字
+    type Run<'a> = (f64, f64, &'a str);
+
+    /// 生成使用 Identity-H 编码和 ToUnicode 映射的PDF，中文无需嵌入字体即可提取
+    fn write_invoice_pdf(path: &Path, runs: &[Run]) {
+        let mut document = Document::with_version("1.5");
+        let pages_id = document.new_object_id();
+
+        let chars: BTreeSet<char> = runs.iter().flat_map(|(_, _, text)| text.chars()).collect();
+        let mut cmap = String::from(
+            "/CIDInit /ProcSet findresource begin\n12 dict begin\nbegincmap\n\
+             /CIDSystemInfo << /Registry (Adobe) /Ordering (UCS) /Supplement 0 >> def\n\
+             /CMapName /Adobe-Identity-UCS def\n/CMapType 2 def\n\
+             1 begincodespacerange\n<0000> <FFFF>\nendcodespacerange\n",
+        );
+        let chars: Vec<char> = chars.into_iter().collect();
+        for chunk in chars.chunks(100) {
+            cmap.push_str(&format!("{} beginbfchar\n", chunk.len()));
+            for c in chunk {
+                cmap.push_str(&format!("<{:04X}> <{:04X}>\n", *c as u32, *c as u32));
+            }
+            cmap.push_str("endbfchar\n");
+        }
+        cmap.push_str("endcmap\nCMapName currentdict /CMap defineresource pop\nend\nend\n");
+
+        let to_unicode = document.add_object(Stream::new(dictionary! {}, cmap.into_bytes()));
+        let descriptor = document.add_object(dictionary! {
+            "Type" => "FontDescriptor",
+            "FontName" => "SimSun",
+            "Flags" => 4,
+            "FontBBox" => vec![0.into(), (-120).into(), 1000.into(), 880.into()],
+            "ItalicAngle" => 0,
+            "Ascent" => 880,
+            "Descent" => -120,
+            "CapHeight" => 700,
+            "StemV" => 80,
+        });
+        let descendant = document.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "CIDFontType2",
+            "BaseFont" => "SimSun",
+            "CIDSystemInfo" => dictionary! {
+                "Registry" => Object::string_literal("Adobe"),
+                "Ordering" => Object::string_literal("Identity"),
+                "Supplement" => 0,
+            },
+            "FontDescriptor" => descriptor,
+            "DW" => 1000,
+            "CIDToGIDMap" => "Identity",
+        });
+        let font = document.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type0",
+            "BaseFont" => "SimSun",
+            "Encoding" => "Identity-H",
+            "DescendantFonts" => vec![descendant.into()],
+            "ToUnicode" => to_unicode,
+        });
+
+        let mut content = String::new();
+        for (x, y, text) in runs {
+            let hex: String = text.encode_utf16().map(|unit| format!("{:04X}", unit)).collect();
+            content.push_str(&format!("BT /F1 10 Tf 1 0 0 1 {} {} Tm <{}> Tj ET\n", x, 842.0 - y, hex));
+        }
+        let content_id = document.add_object(Stream::new(dictionary! {}, content.into_bytes()));
+        let page_id = document.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Contents" => content_id,
+            "Resources" => dictionary! { "Font" => dictionary! { "F1" => font } },
+        });
+        document.objects.insert(pages_id, Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => vec![page_id.into()],
+            "Count" => 1,
+            "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+        }));
+        let catalog_id = document.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        document.trailer.set("Root", catalog_id);
+        document.save(path).unwrap();
+    }
+
+    fn write_sample(dir: &Path, name: &str, runs: &[Run], expected: &ExpectedFields) {
+        write_invoice_pdf(&dir.join(format!("{}.pdf", name)), runs);
+        std::fs::write(dir.join(format!("{}.json", name)), serde_json::to_string_pretty(expected).unwrap()).unwrap();
+    }
+
+    /// 生成的样本集：上下排版的普通发票、左右两栏的数电票、铁路电子客票
+    fn write_synthetic_corpus(dir: &Path) {
+        write_sample(dir, "vat-ordinary", &[
+            (200.0, 40.0, "电子发票（普通发票）"),
+            (380.0, 70.0, "发票号码：24310000000000000001"),
+            (380.0, 85.0, "开票日期：2024年03月15日"),
+            (40.0, 120.0, "购买方名称：某某科技有限公司"),
+            (40.0, 135.0, "纳税人识别号：91310000MA1FL8XQ30"),
+            (40.0, 200.0, "项目名称"),
+            (300.0, 200.0, "金额"),
+            (400.0, 200.0, "税率"),
+            (480.0, 200.0, "税额"),
+            (40.0, 215.0, "*餐饮服务*餐费"),
+            (300.0, 215.0, "300.00"),
+            (400.0, 215.0, "6%"),
+            (480.0, 215.0, "18.00"),
+            (40.0, 240.0, "合 计"),
+            (300.0, 240.0, "¥300.00"),
+            (480.0, 240.0, "¥18.00"),
+            (40.0, 260.0, "价税合计（大写）"),
+            (400.0, 260.0, "（小写）¥318.00"),
+            (40.0, 300.0, "销售方名称：上海某某餐饮管理有限公司"),
+        ], &ExpectedFields {
+            amount: Some(Amount::from_cents(31800)),
+            currency: Some(Currency::CNY),
+            seller: Some("上海某某餐饮管理有限公司".to_string()),
+            buyer_name: Some("某某科技有限公司".to_string()),
+            issue_date: NaiveDate::from_ymd_opt(2024, 3, 15),
+            invoice_number: Some("24310000000000000001".to_string()),
+            category: Some("餐饮".to_string()),
+            ..Default::default()
+        });
+
+        write_sample(dir, "two-column", &[
+            (200.0, 40.0, "电子发票（普通发票）"),
+            (380.0, 70.0, "发票号码：24310000000000000002"),
+            (380.0, 85.0, "开票日期：2024年04月01日"),
+            (320.0, 120.0, "名称：中国移动通信集团上海有限公司"),
+            (40.0, 120.0, "名称：某某科技有限公司"),
+            (40.0, 200.0, "项目名称"),
+            (300.0, 200.0, "金额"),
+            (400.0, 200.0, "税率"),
+            (480.0, 200.0, "税额"),
+            (40.0, 215.0, "*电信服务*话费"),
+            (300.0, 215.0, "94.34"),
+            (400.0, 215.0, "6%"),
+            (480.0, 215.0, "5.66"),
+            (40.0, 260.0, "价税合计（大写）"),
+            (400.0, 260.0, "（小写）¥100.00"),
+        ], &ExpectedFields {
+            amount: Some(Amount::from_cents(10000)),
+            seller: Some("中国移动通信集团上海有限公司".to_string()),
+            buyer_name: Some("某某科技有限公司".to_string()),
+            issue_date: NaiveDate::from_ymd_opt(2024, 4, 1),
+            category: Some("通讯".to_string()),
+            ..Default::default()
+        });
+
+        write_sample(dir, "railway", &[
+            (40.0, 40.0, "铁路电子客票"),
+            (40.0, 70.0, "北京南站 G101 上海虹桥站"),
+            (40.0, 90.0, "票价:￥553.00"),
+        ], &ExpectedFields {
+            amount: Some(Amount::from_cents(55300)),
+            document_type: Some(DocumentType::RailwayTicket),
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn test_synthetic_corpus_accuracy() {
+        let dir = std::env::temp_dir().join(format!("invoicepilot-corpus-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        write_synthetic_corpus(&dir);
+        write_invoice_pdf(&dir.join("no-sidecar.pdf"), &[(40.0, 40.0, "说明")]);
+
+        let report = evaluate_corpus(&PdfParser::new(), &dir).unwrap();
+        assert_eq!(report.files_evaluated, 3);
+        assert_eq!(report.files_skipped, 1);
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+        assert!(report.mismatches.is_empty(), "{:#?}", report.mismatches);
+        let amount = report.field("amount").unwrap();
+        assert_eq!((amount.expected, amount.correct), (3, 3));
+        assert_eq!((amount.precision, amount.recall), (1.0, 1.0));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    /// 评估私有样本集：`INVOICEPILOT_CORPUS=<目录> cargo test private_corpus -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn private_corpus() {
+        let Ok(directory) = std::env::var("INVOICEPILOT_CORPUS") else {
+            return;
+        };
+        let report = evaluate_corpus(&PdfParser::new(), Path::new(&directory)).unwrap();
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+    }
+}
@@ -0,0 +1,315 @@
+use std::collections::HashSet;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, NaiveDate};
+use regex::Regex;
+use crate::money::{parse_amount_number, Amount, Currency};
+
+/// 文件名中的金额（必须带两位小数，避免把日期、订单号当成金额）
+const PROOF_AMOUNT_PATTERN: &str = r"(?:^|[^\d.])(\d{1,3}(?:,\d{3})+\.\d{2}|\d+\.\d{2})(?:[^\d]|$)";
+
+/// 文件名中的日期，如 20240301、2024-03-01、Screenshot_2024-03-01-12-30-00
+const PROOF_DATE_PATTERN: &str = r"(20\d{2})[-_.]?(\d{2})[-_.]?(\d{2})";
+
+/// 微信导出图片的毫秒时间戳，如 mmexport1709251234567
+const PROOF_TIMESTAMP_PATTERN: &str = r"mmexport(\d{13})";
+
+/// 提取商户名称时从文件名中去掉的常见词
+const PROOF_NOISE_WORDS: &[&str] = &[
+    "支付凭证", "付款凭证", "支付截图", "截图", "屏幕截图", "微信支付", "支付宝", "微信",
+    "screenshot", "mmexport", "wechat", "alipay", "img", "image", "photo",
+];
+
+/// 商户名称中不参与比较的后缀
+const COMPANY_SUFFIXES: &[&str] = &["股份有限公司", "有限责任公司", "有限公司", "分公司", "公司", "店"];
+
+/// 低于该得分的配对不作为候选
+const MIN_MATCH_SCORE: f64 = 0.4;
+
+/// 日期相差超过该天数时日期得分为0
+const MAX_DATE_DISTANCE_DAYS: i64 = 30;
+
+/// 待匹配的发票
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InvoiceCandidate {
+    pub path: String,
+    pub amount: Option<Amount>,
+    pub currency: Currency,
+    pub date: Option<NaiveDate>,
+    pub seller: Option<String>,
+}
+
+/// 待匹配的支付凭证
+///
+/// 截图本身无法识别，金额、日期、商户来自文件名或用户填写
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaymentProof {
+    pub path: String,
+    pub amount: Option<Amount>,
+    pub date: Option<NaiveDate>,
+    pub merchant: Option<String>,
+}
+
+/// 一对发票和支付凭证的匹配得分
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatchCandidate {
+    pub invoice_path: String,
+    pub proof_path: String,
+    /// 综合得分（0-1）
+    pub score: f64,
+    pub amount_score: Option<f64>,
+    pub date_score: Option<f64>,
+    pub merchant_score: Option<f64>,
+}
+
+/// 一张发票的候选支付凭证（按得分从高到低）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InvoiceMatches {
+    pub invoice_path: String,
+    pub candidates: Vec<MatchCandidate>,
+}
+
+/// 匹配结果
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MatchResult {
+    /// 建议的一一配对
+    pub suggested_pairs: Vec<MatchCandidate>,
+    /// 每张发票的全部候选
+    pub ranked: Vec<InvoiceMatches>,
+    pub unmatched_invoices: Vec<String>,
+    pub unmatched_proofs: Vec<String>,
+}
+
+/// 用户确认的配对
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfirmedPair {
+    pub invoice_path: String,
+    pub proof_path: String,
+}
+
+/// 支付凭证与发票匹配器
+pub struct PaymentMatcher {
+    amount_pattern: Regex,
+    date_pattern: Regex,
+    timestamp_pattern: Regex,
+}
+
+impl PaymentMatcher {
+    pub fn new() -> Self {
+        PaymentMatcher {
+            amount_pattern: Regex::new(PROOF_AMOUNT_PATTERN).unwrap(),
+            date_pattern: Regex::new(PROOF_DATE_PATTERN).unwrap(),
+            timestamp_pattern: Regex::new(PROOF_TIMESTAMP_PATTERN).unwrap(),
+        }
+    }
+
+    /// 从图片文件名中提取金额、日期和商户；没有日期时使用文件修改日期
+    pub fn proof_from_filename(&self, path: &str, modified: Option<NaiveDate>) -> PaymentProof {
+        let stem = Path::new(path).file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let amount = self.amount_pattern.captures(&stem)
+            .and_then(|caps| caps.get(1))
+            .and_then(|m| parse_amount_number(m.as_str()));
+
+        let date = self.date_pattern.captures_iter(&stem)
+            .find_map(|caps| {
+                NaiveDate::from_ymd_opt(caps[1].parse().ok()?, caps[2].parse().ok()?, caps[3].parse().ok()?)
+            })
+            .or_else(|| {
+                let millis: i64 = self.timestamp_pattern.captures(&stem)?[1].parse().ok()?;
+                DateTime::from_timestamp_millis(millis).map(|time| time.date_naive())
+            })
+            .or(modified);
+
+        // 去掉数字和常见词后剩下的文字作为商户名称
+        let mut merchant = stem.to_lowercase();
+        for word in PROOF_NOISE_WORDS {
+            merchant = merchant.replace(word, " ");
+        }
+        let merchant = merchant
+            .split(|c: char| c.is_ascii_digit() || c.is_ascii_punctuation() || c.is_whitespace())
+            .filter(|part| part.chars().count() >= 2)
+            .collect::<Vec<_>>()
+            .join("");
+
+        PaymentProof {
+            path: path.to_string(),
+            amount,
+            date,
+            merchant: if merchant.is_empty() { None } else { Some(merchant) },
+        }
+    }
+
+    /// 计算一对发票和支付凭证的得分，明显不匹配时返回 None
+    pub fn score(&self, invoice: &InvoiceCandidate, proof: &PaymentProof) -> Option<MatchCandidate> {
+        let amount_score = match (invoice.amount, proof.amount) {
+            (Some(expected), Some(paid)) if expected == paid => Some(1.0),
+            // 金额都已知但不同，不可能是同一笔
+            (Some(_), Some(_)) => return None,
+            _ => None,
+        };
+        let date_score = match (invoice.date, proof.date) {
+            (Some(a), Some(b)) => {
+                let days = (a - b).num_days().abs();
+                Some((1.0 - days as f64 / MAX_DATE_DISTANCE_DAYS as f64).max(0.0))
+            }
+            _ => None,
+        };
+        let merchant_score = match (&invoice.seller, &proof.merchant) {
+            (Some(seller), Some(merchant)) => Some(name_similarity(seller, merchant)),
+            _ => None,
+        };
+
+        // 只用双方都有的信息计算加权平均；仅靠日期不足以配对
+        if amount_score.is_none() && merchant_score.is_none() {
+            return None;
+        }
+        let weighted = [(amount_score, 0.6), (date_score, 0.2), (merchant_score, 0.2)];
+        let total_weight: f64 = weighted.iter().filter(|(s, _)| s.is_some()).map(|(_, w)| w).sum();
+        let score = weighted.iter()
+            .filter_map(|(s, w)| s.map(|s| s * w))
+            .sum::<f64>() / total_weight;
+
+        if score < MIN_MATCH_SCORE {
+            return None;
+        }
+
+        Some(MatchCandidate {
+            invoice_path: invoice.path.clone(),
+            proof_path: proof.path.clone(),
+            score,
+            amount_score,
+            date_score,
+            merchant_score,
+        })
+    }
+
+    /// 为每张发票排序候选凭证，并按得分从高到低贪心生成一一配对
+    pub fn match_all(&self, invoices: &[InvoiceCandidate], proofs: &[PaymentProof]) -> MatchResult {
+        let mut ranked = Vec::new();
+        let mut all_candidates = Vec::new();
+
+        for invoice in invoices {
+            let mut candidates: Vec<MatchCandidate> = proofs.iter()
+                .filter_map(|proof| self.score(invoice, proof))
+                .collect();
+            candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+            all_candidates.extend(candidates.iter().cloned());
+            ranked.push(InvoiceMatches {
+                invoice_path: invoice.path.clone(),
+                candidates,
+            });
+        }
+
+        all_candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+        let mut used_invoices = HashSet::new();
+        let mut used_proofs = HashSet::new();
+        let mut suggested_pairs = Vec::new();
+        for candidate in all_candidates {
+            if used_invoices.contains(&candidate.invoice_path) || used_proofs.contains(&candidate.proof_path) {
+                continue;
+            }
+            used_invoices.insert(candidate.invoice_path.clone());
+            used_proofs.insert(candidate.proof_path.clone());
+            suggested_pairs.push(candidate);
+        }
+
+        MatchResult {
+            suggested_pairs,
+            ranked,
+            unmatched_invoices: invoices.iter()
+                .filter(|invoice| !used_invoices.contains(&invoice.path))
+                .map(|invoice| invoice.path.clone())
+                .collect(),
+            unmatched_proofs: proofs.iter()
+                .filter(|proof| !used_proofs.contains(&proof.path))
+                .map(|proof| proof.path.clone())
+                .collect(),
+        }
+    }
+}
+
+/// 商户名称相似度（0-1）：去掉公司后缀后，包含关系记为1，否则按字符二元组计算 Dice 系数
+pub fn name_similarity(a: &str, b: &str) -> f64 {
+    let normalize = |name: &str| {
+        let mut name: String = name.to_lowercase()
+            .chars()
+            .filter(|c| !c.is_whitespace() && !c.is_ascii_punctuation() && !"（）()·".contains(*c))
+            .collect();
+        for suffix in COMPANY_SUFFIXES {
+            if let Some(stripped) = name.strip_suffix(suffix) {
+                name = stripped.to_string();
+            }
+        }
+        name
+    };
+    let (a, b) = (normalize(a), normalize(b));
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    if a.contains(&b) || b.contains(&a) {
+        return 1.0;
+    }
+
+    let bigrams = |s: &str| -> Vec<(char, char)> {
+        let chars: Vec<char> = s.chars().collect();
+        chars.windows(2).map(|w| (w[0], w[1])).collect()
+    };
+    let (a, b) = (bigrams(&a), bigrams(&b));
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let mut remaining = b.clone();
+    let common = a.iter()
+        .filter(|pair| match remaining.iter().position(|other| other == *pair) {
+            Some(index) => {
+                remaining.swap_remove(index);
+                true
+            }
+            None => false,
+        })
+        .count();
+    2.0 * common as f64 / (a.len() + b.len()) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proof_from_filename() {
+        let matcher = PaymentMatcher::new();
+        let proof = matcher.proof_from_filename("/pay/支付宝_20240301_星巴克_317.60.jpg", None);
+        assert_eq!(proof.amount, Some(Amount::from_cents(31760)));
+        assert_eq!(proof.date, NaiveDate::from_ymd_opt(2024, 3, 1));
+        assert_eq!(proof.merchant.as_deref(), Some("星巴克"));
+    }
+
+    #[test]
+    fn test_match_all() {
+        let matcher = PaymentMatcher::new();
+        let invoice = |path: &str, cents: i64, seller: &str| InvoiceCandidate {
+            path: path.to_string(),
+            amount: Some(Amount::from_cents(cents)),
+            currency: Currency::CNY,
+            date: NaiveDate::from_ymd_opt(2024, 3, 1),
+            seller: Some(seller.to_string()),
+        };
+        let invoices = vec![
+            invoice("a.pdf", 31760, "星巴克咖啡（上海）有限公司"),
+            invoice("b.pdf", 5000, "上海某某出租汽车有限公司"),
+        ];
+        let proofs = vec![
+            matcher.proof_from_filename("星巴克_317.60_20240302.jpg", None),
+            matcher.proof_from_filename("截图_12.00.png", None),
+        ];
+
+        let result = matcher.match_all(&invoices, &proofs);
+        assert_eq!(result.suggested_pairs.len(), 1);
+        assert_eq!(result.suggested_pairs[0].invoice_path, "a.pdf");
+        assert_eq!(result.unmatched_invoices, vec!["b.pdf".to_string()]);
+        assert_eq!(result.unmatched_proofs, vec!["截图_12.00.png".to_string()]);
+    }
+}
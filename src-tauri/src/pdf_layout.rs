@@ -0,0 +1,269 @@
+use serde::{Deserialize, Serialize};
+use pdf_extract::{output_doc, Document, MediaBox, OutputDev, OutputError, Transform};
+use crate::i18n::trf;
+
+/// 同一行文字的纵向容差（相对字号）
+const LINE_TOLERANCE: f64 = 0.5;
+
+/// 字间距超过该值（相对字号）时拆成两个文字块，用于区分表格的列
+const RUN_GAP: f64 = 1.0;
+
+/// 字间距超过该值（相对字号）时在文字块内插入空格
+const SPACE_GAP: f64 = 0.1;
+
+/// 查找标签下方的值时最多向下的距离（相对字号）
+const BELOW_DISTANCE: f64 = 4.0;
+
+/// 带位置的一段文字
+///
+/// 坐标原点在页面左上角，`y` 为基线位置，单位为 PDF 点
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextRun {
+    /// 页码（从1开始）
+    pub page: usize,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub font_size: f64,
+    pub text: String,
+}
+
+impl TextRun {
+    /// 右边界
+    pub fn right(&self) -> f64 {
+        self.x + self.width
+    }
+
+    /// 水平中心
+    pub fn center_x(&self) -> f64 {
+        self.x + self.width / 2.0
+    }
+}
+
+/// 一页的文字布局
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageLayout {
+    /// 页码（从1开始）
+    pub page: usize,
+    pub width: f64,
+    pub height: f64,
+    pub runs: Vec<TextRun>,
+}
+
+impl PageLayout {
+    /// 按基线把文字块分成行，行内按 x 排序，行按从上到下排序
+    pub fn lines(&self) -> Vec<Vec<&TextRun>> {
+        let mut runs: Vec<&TextRun> = self.runs.iter().collect();
+        runs.sort_by(|a, b| a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x)));
+
+        let mut lines: Vec<Vec<&TextRun>> = Vec::new();
+        for run in runs {
+            match lines.last_mut() {
+                Some(line) if (line[0].y - run.y).abs() <= line[0].font_size.max(run.font_size) * LINE_TOLERANCE => {
+                    line.push(run);
+                }
+                _ => lines.push(vec![run]),
+            }
+        }
+        for line in &mut lines {
+            line.sort_by(|a, b| a.x.total_cmp(&b.x));
+        }
+        lines
+    }
+
+    /// 包含标签文字的文字块（忽略空白），按行从上到下、行内从左到右排序
+    pub fn find_label(&self, label: &str) -> Vec<&TextRun> {
+        let label = normalize(label);
+        self.lines().into_iter()
+            .flatten()
+            .filter(|run| normalize(&run.text).contains(&label))
+            .collect()
+    }
+
+    /// 与标签同一行、位于其右侧的文字块，按 x 排序
+    pub fn right_of(&self, anchor: &TextRun) -> Vec<&TextRun> {
+        let mut runs: Vec<&TextRun> = self.runs.iter()
+            .filter(|run| !std::ptr::eq(*run, anchor))
+            .filter(|run| (run.y - anchor.y).abs() <= run.font_size.max(anchor.font_size) * LINE_TOLERANCE)
+            .filter(|run| run.x >= anchor.right() - anchor.font_size * SPACE_GAP)
+            .collect();
+        runs.sort_by(|a, b| a.x.total_cmp(&b.x));
+        runs
+    }
+
+    /// 位于标签下方、与标签水平方向有重叠的文字块，按 y 排序
+    pub fn below(&self, anchor: &TextRun) -> Vec<&TextRun> {
+        let max_distance = anchor.font_size * BELOW_DISTANCE;
+        let mut runs: Vec<&TextRun> = self.runs.iter()
+            .filter(|run| run.y - anchor.y > anchor.font_size * LINE_TOLERANCE && run.y - anchor.y <= max_distance)
+            .filter(|run| run.x < anchor.right() && run.right() > anchor.x)
+            .collect();
+        runs.sort_by(|a, b| a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x)));
+        runs
+    }
+}
+
+/// 文字块中标签之后的文字，标签不在其中时返回 `None`
+///
+/// 标签内的空白可与文字块不同，如 "合 计" 与 "合计"
+pub fn text_after_label(run: &TextRun, label: &str) -> Option<String> {
+    let label: Vec<char> = label.chars().filter(|c| !c.is_whitespace()).collect();
+    let first = *label.first()?;
+    run.text.char_indices()
+        .filter(|(_, c)| *c == first)
+        .find_map(|(start, _)| {
+            let mut matched = 0;
+            for (index, c) in run.text[start..].char_indices() {
+                if c.is_whitespace() {
+                    continue;
+                }
+                if c != label[matched] {
+                    return None;
+                }
+                matched += 1;
+                if matched == label.len() {
+                    return Some(run.text[start + index + c.len_utf8()..].to_string());
+                }
+            }
+            None
+        })
+}
+
+fn normalize(text: &str) -> String {
+    text.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+/// 读取已打开（已解密）的PDF每页的文字布局
+pub fn extract_layout(document: &Document) -> Result<Vec<PageLayout>, String> {
+    let mut collector = LayoutCollector::default();
+    output_doc(document, &mut collector)
+        .map_err(|e| trf("PDF解析失败: {}", "Failed to parse PDF: {}", &[&format!("{:?}", e)]))?;
+    Ok(collector.pages)
+}
+
+/// 收集字符位置并合并为文字块
+#[derive(Default)]
+struct LayoutCollector {
+    pages: Vec<PageLayout>,
+    /// 当前文字块
+    current: Option<TextRun>,
+}
+
+impl LayoutCollector {
+    fn flush(&mut self) {
+        if let Some(run) = self.current.take() {
+            if !run.text.trim().is_empty() {
+                if let Some(page) = self.pages.last_mut() {
+                    page.runs.push(TextRun { text: run.text.trim().to_string(), ..run });
+                }
+            }
+        }
+    }
+}
+
+impl OutputDev for LayoutCollector {
+    fn begin_page(&mut self, page_num: u32, media_box: &MediaBox, _art_box: Option<(f64, f64, f64, f64)>) -> Result<(), OutputError> {
+        self.pages.push(PageLayout {
+            page: page_num as usize,
+            width: media_box.urx - media_box.llx,
+            height: media_box.ury - media_box.lly,
+            runs: Vec::new(),
+        });
+        Ok(())
+    }
+
+    fn end_page(&mut self) -> Result<(), OutputError> {
+        self.flush();
+        Ok(())
+    }
+
+    fn output_character(&mut self, trm: &Transform, width: f64, _spacing: f64, font_size: f64, char: &str) -> Result<(), OutputError> {
+        let Some(page) = self.pages.last() else {
+            return Ok(());
+        };
+        // 与 pdf-extract 的纯文本输出相同：翻转 y 轴，按变换后的面积折算字号
+        let x = trm.m31;
+        let y = page.height - trm.m32;
+        let size = ((font_size * (trm.m11 + trm.m21)) * (font_size * (trm.m12 + trm.m22))).abs().sqrt();
+        let char_width = width * size;
+        let page_number = page.page;
+
+        if let Some(run) = self.current.as_mut() {
+            let same_line = (run.y - y).abs() <= run.font_size.max(size) * LINE_TOLERANCE;
+            let gap = x - run.right();
+            if same_line && gap <= size * RUN_GAP && gap >= -size {
+                if gap > size * SPACE_GAP {
+                    run.text.push(' ');
+                }
+                run.text.push_str(char);
+                run.width = (x + char_width - run.x).max(run.width);
+                return Ok(());
+            }
+        }
+
+        self.flush();
+        self.current = Some(TextRun {
+            page: page_number,
+            x,
+            y,
+            width: char_width,
+            font_size: size,
+            text: char.to_string(),
+        });
+        Ok(())
+    }
+
+    fn begin_word(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+
+    fn end_word(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+
+    fn end_line(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(x: f64, y: f64, text: &str) -> TextRun {
+        TextRun {
+            page: 1,
+            x,
+            y,
+            width: text.chars().count() as f64 * 9.0,
+            font_size: 9.0,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_spatial_relations() {
+        let page = PageLayout {
+            page: 1,
+            width: 595.0,
+            height: 842.0,
+            runs: vec![
+                run(20.0, 300.0, "价税合计（大写）"),
+                run(200.0, 300.0, "叁佰壹拾柒圆陆角整"),
+                run(400.0, 300.0, "（小写）¥317.60"),
+                run(20.0, 100.0, "开票日期"),
+                run(22.0, 112.0, "2024年03月15日"),
+                run(200.0, 112.0, "无关文字"),
+            ],
+        };
+
+        let label = page.find_label("价税 合计")[0];
+        let right: Vec<&str> = page.right_of(label).iter().map(|run| run.text.as_str()).collect();
+        assert_eq!(right, vec!["叁佰壹拾柒圆陆角整", "（小写）¥317.60"]);
+        assert_eq!(text_after_label(label, "价税合计").as_deref(), Some("（大写）"));
+
+        let date_label = page.find_label("开票日期")[0];
+        let below: Vec<&str> = page.below(date_label).iter().map(|run| run.text.as_str()).collect();
+        assert_eq!(below, vec!["2024年03月15日"]);
+    }
+}
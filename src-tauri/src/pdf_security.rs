@@ -0,0 +1,103 @@
+use std::path::Path;
+use pdf_extract::Document;
+use crate::error::{AppError, AppResult};
+use crate::i18n::tr;
+
+/// 打开PDF，加密的文件自动解密
+///
+/// 只设置了所有者密码（限制打印、复制）的文件在加载时已用空密码解密；
+/// 需要打开密码的文件依次尝试 `passwords` 返回的密码，都不正确时返回 `AppError::PasswordRequired`。
+/// 密码列表只在需要时才获取。
+pub fn open_document<F>(path: &Path, passwords: F) -> AppResult<Document>
+where
+    F: FnOnce() -> Vec<String>,
+{
+    let mut document = Document::load(path).map_err(|e| match e {
+        lopdf::Error::IO(e) => AppError::io_error_at(e, tr("读取PDF失败", "Failed to read PDF"), path),
+        e => AppError::pdf_parse_error(&format!("{}: {}", tr("PDF解析失败", "PDF parsing failed"), e)).with_path(path),
+    })?;
+    if !document.is_encrypted() {
+        return Ok(document);
+    }
+
+    for password in passwords() {
+        if document.authenticate_password(&password).is_ok() {
+            document.decrypt(&password)
+                .map_err(|e| AppError::pdf_parse_error(&format!("{}: {}", tr("PDF解密失败", "PDF decryption failed"), e)).with_path(path))?;
+            return Ok(document);
+        }
+    }
+    Err(AppError::password_required(&path.to_string_lossy()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::{dictionary, EncryptionState, EncryptionVersion, Object, Permissions, Stream};
+
+    /// 生成一页的PDF，按给定密码加密
+    fn write_encrypted_pdf(path: &Path, owner_password: &str, user_password: &str) {
+        let mut doc = lopdf::Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let font_id = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Helvetica",
+        });
+        let content_id = doc.add_object(Stream::new(dictionary! {}, b"BT /F1 12 Tf 72 720 Td (Total 12.50) Tj ET".to_vec()));
+        let page_id = doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Contents" => content_id,
+            "Resources" => dictionary! { "Font" => dictionary! { "F1" => font_id } },
+            "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+        });
+        doc.objects.insert(pages_id, Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => vec![page_id.into()],
+            "Count" => 1,
+        }));
+        let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        doc.trailer.set("Root", catalog_id);
+        let id = Object::string_literal(b"0123456789abcdef".to_vec());
+        doc.trailer.set("ID", vec![id.clone(), id]);
+
+        let state = EncryptionState::try_from(EncryptionVersion::V2 {
+            document: &doc,
+            owner_password,
+            user_password,
+            key_length: 128,
+            permissions: Permissions::PRINTABLE,
+        }).unwrap();
+        doc.encrypt(&state).unwrap();
+        doc.save(path).unwrap();
+    }
+
+    #[test]
+    fn test_open_encrypted_document() {
+        let dir = std::env::temp_dir().join(format!("invoicepilot-security-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // 只有所有者密码：无需密码即可打开
+        let owner_only = dir.join("owner-only.pdf");
+        write_encrypted_pdf(&owner_only, "owner", "");
+        let document = open_document(&owner_only, Vec::new).unwrap();
+        assert!(!document.is_encrypted());
+        assert_eq!(document.get_pages().len(), 1);
+
+        // 需要打开密码：没有或密码错误时返回单独的错误类型
+        let protected = dir.join("protected.pdf");
+        write_encrypted_pdf(&protected, "owner", "secret");
+        let error = open_document(&protected, || vec!["wrong".to_string()]).unwrap_err();
+        assert!(matches!(error, AppError::PasswordRequired(_)));
+        assert_eq!(error.error_code(), "PDF_PASSWORD_REQUIRED");
+
+        let document = open_document(&protected, || vec!["wrong".to_string(), "secret".to_string()]).unwrap();
+        assert!(!document.is_encrypted());
+        let mut text = String::new();
+        pdf_extract::output_doc(&document, &mut pdf_extract::PlainTextOutput::new(&mut text)).unwrap();
+        assert!(text.contains("Total 12.50"));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -1,195 +1,587 @@
+use std::collections::HashMap;
 use std::path::Path;
 use regex::Regex;
-use pdf_extract::extract_text;
-use crate::types::InvoiceInfo;
+use chrono::NaiveDate;
+use pdf_extract::{output_doc_page, Document, PlainTextOutput};
+use crate::config::PdfPasswordConfig;
+use crate::error::{AppError, AppResult};
+use crate::i18n::{tr, trf};
+use crate::ledger::Ledger;
+use crate::pdf_security::open_document;
+use crate::types::{FieldEvidence, InvoiceInfo, InvoiceSegment, VatDetails};
+use crate::pdf_layout::{extract_layout, text_after_label, PageLayout, TextRun};
+use crate::line_items::extract_line_items;
+use crate::extraction_rules::{RuleEngine, RuleSet};
+use crate::money::{parse_amount_number, Amount, Currency, CurrencyDetector, Money, NUMBER_PATTERN};
+use crate::document_classifier::{DocumentClassifier, DocumentType};
+use crate::expense_category::{CategoryRule, ExpenseCategorizer};
 
-pub struct PdfParser;
+/// 单行金额匹配模式（`{num}` 为金额数字）
+const LINE_AMOUNT_PATTERNS: &[&str] = &[
+    r"¥\s*({num})",
+    r"￥\s*({num})",
+    r"({num})元",
+    r"金额[：:]\s*({num})",
+    r"合计[：:]\s*({num})",
+];
+
+/// 小数金额匹配模式
+const DECIMAL_AMOUNT_PATTERNS: &[&str] = &[
+    r"金额[：:]\s*({num})",
+    r"总计[：:]\s*({num})",
+    r"合计[：:]\s*({num})",
+    r"应付[：:]\s*({num})",
+    r"价税合计[：:]\s*({num})",
+    r"总金额[：:]\s*({num})",
+    r"({num})元",
+];
+
+/// 中文数字金额匹配模式
+const CHINESE_AMOUNT_PATTERNS: &[&str] = &[
+    r"([一二三四五六七八九十百千万]+)元",
+    r"([壹贰叁肆伍陆柒捌玖拾佰仟万]+)元",
+];
+
+/// 发票号码（用于识别多张发票的分界）
+const INVOICE_NUMBER_PATTERN: &str = r"(?:发票号码|电子客票号|(?i:invoice\s*(?:no\.?|number)))[：:\s]*([A-Z0-9]{8,20})";
+
+/// 发票代码
+const INVOICE_CODE_PATTERN: &str = r"发票代码[：:\s]*([0-9A-Z]{6,20})";
+
+/// 价税合计（小写）
+const TOTAL_AMOUNT_PATTERN: &str = r"价税合计[^¥￥\n]*[¥￥]\s*({num})";
+
+/// 合计行的金额和税额，如 "合 计 ¥300.00 ¥18.00"
+const SUBTOTAL_PATTERN: &str = r"合\s*计\s*[¥￥]\s*({num})\s*[¥￥]\s*({num})";
+
+/// 明细中的税率
+const TAX_RATE_PATTERN: &str = r"(\d{1,2}(?:\.\d+)?)\s*%|免税|不征税";
+
+/// 明细项目名称，如 "*餐饮服务*餐费"
+const ITEM_NAME_PATTERN: &str = r"\*[^*\s]+\*[^\s*]*";
+
+/// 纳税人识别号，通常第一个为购买方、第二个为销售方
+const TAX_ID_PATTERN: &str = r"纳税人识别号[：:\s]*([0-9A-Z]{15,20})";
+
+/// 销售方名称匹配模式
+const SELLER_PATTERNS: &[&str] = &[
+    r"销售方名称[：:]\s*([^\s：:]+)",
+    r"销售方[：:]\s*([^\s：:]+)",
+    r"收款单位[：:]\s*([^\s：:]+)",
+    r"(?:商户名称|酒店名称)[：:]\s*([^\s：:]+)",
+];
+
+/// 购买方名称匹配模式
+const BUYER_NAME_PATTERNS: &[&str] = &[
+    r"购买方名称[：:]\s*([^\s：:]+)",
+    r"购方名称[：:]\s*([^\s：:]+)",
+];
+
+/// 发票上的"名称"栏，通常第一个为购买方、第二个为销售方
+const PARTY_NAME_PATTERN: &str = r"名\s*称[：:]\s*([^\s：:]+)";
+
+/// 按位置查找总金额时使用的标签，金额在标签右侧或下方
+const TOTAL_AMOUNT_LABELS: &[&str] = &["价税合计", "总计", "实付金额", "应付金额"];
+
+/// 按位置查找销售方名称时使用的标签
+const SELLER_LABELS: &[&str] = &["销售方名称", "收款单位", "商户名称", "酒店名称"];
+
+/// 按位置查找购买方名称时使用的标签
+const BUYER_LABELS: &[&str] = &["购买方名称", "购方名称"];
+
+/// 开票日期匹配模式（年、月、日三个分组）
+const ISSUE_DATE_PATTERNS: &[&str] = &[
+    r"(?:开票日期|日期)[：:]?\s*(\d{4})\s*[年\-/.]\s*(\d{1,2})\s*[月\-/.]\s*(\d{1,2})",
+    r"(\d{4})年(\d{1,2})月(\d{1,2})日",
+];
+
+/// 按页提取已打开PDF的纯文本，任一页无法解析时整个文件视为解析失败
+///
+/// pdf_extract 遇到损坏的页面时多数情况会 panic，这里一并转换为错误，避免持有锁时 panic
+fn page_texts(document: &Document, path: &Path) -> AppResult<Vec<String>> {
+    let mut pages = Vec::new();
+    for page_num in document.get_pages().into_keys() {
+        let mut text = String::new();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            output_doc_page(document, &mut PlainTextOutput::new(&mut text), page_num).map_err(|e| e.to_string())
+        }));
+        let error = match result {
+            Ok(Ok(())) => None,
+            Ok(Err(e)) => Some(e),
+            Err(_) => Some(tr("页面内容损坏", "Page content is corrupted").to_string()),
+        };
+        if let Some(e) = error {
+            return Err(AppError::pdf_parse_error(&trf("第 {} 页无法解析: {}", "Page {} could not be parsed: {}", &[&page_num, &e])).with_path(path));
+        }
+        pages.push(text);
+    }
+    Ok(pages)
+}
+
+/// 编译内置模式，无效的模式会被忽略
+fn compile_patterns(patterns: &[&str]) -> Vec<Regex> {
+    patterns.iter()
+        .map(|pattern| pattern.replace("{num}", &format!("(?:{})", NUMBER_PATTERN)))
+        .filter_map(|pattern| Regex::new(&pattern).ok())
+        .collect()
+}
+
+pub struct PdfParser {
+    /// 用户可编辑的提取规则（来自 rules.json）
+    rule_engine: RuleEngine,
+    /// 票据类型分类器
+    classifier: DocumentClassifier,
+    /// 费用类别识别
+    categorizer: ExpenseCategorizer,
+    /// 加密PDF的打开密码（来自配置）
+    passwords: PdfPasswordConfig,
+    /// 本次运行中用户输入的打开密码（文件内容哈希 -> 密码）
+    unlocked: HashMap<String, String>,
+    line_patterns: Vec<Regex>,
+    decimal_patterns: Vec<Regex>,
+    chinese_patterns: Vec<Regex>,
+    currency_detector: CurrencyDetector,
+    invoice_number_pattern: Regex,
+    seller_patterns: Vec<Regex>,
+    buyer_name_patterns: Vec<Regex>,
+    party_name_pattern: Regex,
+    issue_date_patterns: Vec<Regex>,
+    invoice_code_pattern: Regex,
+    total_amount_pattern: Regex,
+    subtotal_pattern: Regex,
+    tax_rate_pattern: Regex,
+    tax_id_pattern: Regex,
+    item_name_pattern: Regex,
+}
 
 impl PdfParser {
     pub fn new() -> Self {
-        PdfParser
+        PdfParser {
+            rule_engine: RuleEngine::compile(&RuleSet::default())
+                .unwrap_or_else(|_| RuleEngine::empty()),
+            classifier: DocumentClassifier::new(),
+            categorizer: ExpenseCategorizer::new(),
+            passwords: PdfPasswordConfig::default(),
+            unlocked: HashMap::new(),
+            line_patterns: compile_patterns(LINE_AMOUNT_PATTERNS),
+            decimal_patterns: compile_patterns(DECIMAL_AMOUNT_PATTERNS),
+            chinese_patterns: compile_patterns(CHINESE_AMOUNT_PATTERNS),
+            currency_detector: CurrencyDetector::new(),
+            invoice_number_pattern: Regex::new(INVOICE_NUMBER_PATTERN).unwrap(),
+            seller_patterns: compile_patterns(SELLER_PATTERNS),
+            buyer_name_patterns: compile_patterns(BUYER_NAME_PATTERNS),
+            party_name_pattern: Regex::new(PARTY_NAME_PATTERN).unwrap(),
+            issue_date_patterns: compile_patterns(ISSUE_DATE_PATTERNS),
+            invoice_code_pattern: Regex::new(INVOICE_CODE_PATTERN).unwrap(),
+            total_amount_pattern: compile_patterns(&[TOTAL_AMOUNT_PATTERN]).remove(0),
+            subtotal_pattern: compile_patterns(&[SUBTOTAL_PATTERN]).remove(0),
+            tax_rate_pattern: Regex::new(TAX_RATE_PATTERN).unwrap(),
+            tax_id_pattern: Regex::new(TAX_ID_PATTERN).unwrap(),
+            item_name_pattern: Regex::new(ITEM_NAME_PATTERN).unwrap(),
+        }
+    }
+
+    /// 替换提取规则（规则文件加载或更新后调用）
+    pub fn set_rule_engine(&mut self, rule_engine: RuleEngine) {
+        self.rule_engine = rule_engine;
+    }
+
+    /// 替换费用类别规则（配置加载或更新后调用）
+    pub fn set_category_rules(&mut self, rules: &[CategoryRule]) {
+        self.categorizer.set_rules(rules);
+    }
+
+    /// 替换加密PDF的打开密码（配置加载或更新后调用）
+    pub fn set_pdf_passwords(&mut self, passwords: &PdfPasswordConfig) {
+        self.passwords = passwords.clone();
+    }
+
+    /// 用用户输入的密码解锁PDF，密码正确时本次运行中不再需要输入
+    pub fn unlock_pdf(&mut self, path: &Path, password: &str) -> AppResult<()> {
+        open_document(path, || vec![password.to_string()])?;
+        let content_hash = Ledger::hash_file(path)?;
+        self.unlocked.insert(content_hash, password.to_string());
+        Ok(())
+    }
+
+    /// 打开PDF，需要打开密码时依次尝试用户输入过的密码和配置中的密码
+    ///
+    /// `sender` 为文件的邮件发件人，用于优先尝试该发件人的密码
+    fn open_pdf(&self, path: &Path, sender: Option<&str>) -> AppResult<Document> {
+        open_document(path, || {
+            let unlocked = Ledger::hash_file(path).ok()
+                .and_then(|content_hash| self.unlocked.get(&content_hash).cloned());
+            unlocked.into_iter().chain(self.passwords.passwords_for(sender)).collect()
+        })
     }
 
     /// 从PDF文件中提取文本内容
-    pub fn extract_text_from_pdf(&self, path: &Path) -> Result<String, String> {
-        match extract_text(path) {
-            Ok(text) => Ok(text),
-            Err(e) => Err(format!("PDF解析失败: {}", e)),
+    pub fn extract_text_from_pdf(&self, path: &Path) -> AppResult<String> {
+        Ok(self.extract_pages_from_pdf(path)?.join("\n"))
+    }
+
+    /// 按页提取带位置的文字块（页码、坐标和字号）
+    pub fn extract_layout_from_pdf(&self, path: &Path) -> AppResult<Vec<PageLayout>> {
+        let document = self.open_pdf(path, None)?;
+        extract_layout(&document).map_err(|e| AppError::pdf_parse_error(&e).with_path(path))
+    }
+
+    /// 按页提取PDF文本
+    pub fn extract_pages_from_pdf(&self, path: &Path) -> AppResult<Vec<String>> {
+        let document = self.open_pdf(path, None)?;
+        page_texts(&document, path)
+    }
+
+    /// 识别每张发票所占的页码范围
+    ///
+    /// 发票号码变化的页开始一张新发票；没有号码的页视为上一张的续页，
+    /// 除非上一张已出现合计且本页又有合计。
+    pub fn detect_invoice_segments(&self, pages: &[String]) -> Vec<InvoiceSegment> {
+        // (起始页下标, 结束页下标, 发票号码, 是否已出现合计)
+        let mut ranges: Vec<(usize, usize, Option<String>, bool)> = Vec::new();
+
+        for (index, page) in pages.iter().enumerate() {
+            let number = self.invoice_number_pattern.captures(page)
+                .and_then(|caps| caps.get(1))
+                .map(|m| m.as_str().to_string());
+            let has_total = page.contains("价税合计") || page.contains("合计");
+
+            let starts_new = match ranges.last() {
+                None => true,
+                Some((_, _, current_number, current_has_total)) => match (&number, current_number) {
+                    (Some(number), Some(current)) => number != current,
+                    (Some(_), None) => *current_has_total,
+                    (None, _) => *current_has_total && has_total,
+                },
+            };
+
+            if starts_new {
+                ranges.push((index, index, number, has_total));
+            } else if let Some(current) = ranges.last_mut() {
+                current.1 = index;
+                if current.2.is_none() {
+                    current.2 = number;
+                }
+                current.3 |= has_total;
+            }
         }
+
+        ranges.into_iter()
+            .map(|(start, end, invoice_number, _)| {
+                let text = pages[start..=end].join("\n");
+                let document_type = self.classify_text(&text);
+                let amount = self.extract_amount_from_text(&text, document_type);
+                InvoiceSegment {
+                    start_page: start + 1,
+                    end_page: end + 1,
+                    invoice_number,
+                    amount: amount.map(|money| money.amount),
+                    currency: amount.map(|money| money.currency).unwrap_or_default(),
+                    document_type,
+                }
+            })
+            .collect()
+    }
+
+    /// 识别文本对应的票据类型
+    pub fn classify_text(&self, text: &str) -> DocumentType {
+        self.classifier.classify(text)
+    }
+
+    /// 从文本中提取金额信息（含币种）
+    pub fn extract_amount_from_text(&self, text: &str, document_type: DocumentType) -> Option<Money> {
+        self.extract_amount(text, &[], document_type)
+    }
+
+    /// 从文本和文字布局中提取金额信息，布局为空时只使用文本
+    pub fn extract_amount(&self, text: &str, layout: &[PageLayout], document_type: DocumentType) -> Option<Money> {
+        first_value(self.amount_candidates(text, layout, document_type))
     }
 
-    /// 从文本中提取金额信息
-    pub fn extract_amount_from_text(&self, text: &str) -> Option<f64> {
-        // 首先尝试提取价税合计金额（最重要的金额）
-        if let Some(amount) = self.parse_total_amount(text) {
-            return Some(amount);
+    /// 按优先级列出所有金额候选，第一个即采用的结果
+    fn amount_candidates(&self, text: &str, layout: &[PageLayout], document_type: DocumentType) -> Vec<(Money, FieldEvidence)> {
+        // 文本中没有单独标明币种的金额按全文主要币种处理
+        let default_currency = self.currency_detector.detect(text).unwrap_or_default();
+        let mut candidates = Vec::new();
+
+        // 优先使用规则文件中适用于该票据类型的提取规则
+        if let Some(rule_match) = self.rule_engine.extract_field(text, "amount", document_type) {
+            if let Some(amount) = rule_match.fields.get("amount").and_then(|v| parse_amount_number(v)) {
+                let currency = rule_match.fields.get("currency")
+                    .and_then(|marker| Currency::from_marker(marker))
+                    .or_else(|| self.currency_detector.detect(&rule_match.matched_text))
+                    .unwrap_or(default_currency);
+                let money = Money::new(amount, currency);
+                candidates.push((money, evidence("amount", money_text(money), trf("提取规则 {}", "Extraction rule {}", &[&rule_match.rule_id]), &rule_match.matched_text)));
+            }
         }
-        
+
+        // 其次按位置查找价税合计标签右侧或下方的金额，不受文本顺序影响
+        candidates.extend(self.layout_total_candidates(layout, default_currency));
+
+        // 再从文本中提取价税合计金额（最重要的金额）
+        candidates.extend(self.text_total_candidates(text, default_currency));
+
         // 其次尝试多种金额提取方法
-        if let Some(amount) = self.parse_decimal_amount(text) {
-            return Some(amount);
+        for re in &self.decimal_patterns {
+            for caps in re.captures_iter(text) {
+                if let Some(amount) = caps.get(1).and_then(|m| parse_amount_number(m.as_str())) {
+                    let money = Money::new(amount, default_currency);
+                    candidates.push((money, evidence("amount", money_text(money), tr("文本: 金额关键词", "Text: amount keyword"), &caps[0])));
+                }
+            }
         }
-        
-        if let Some(amount) = self.parse_chinese_amount(text) {
-            return Some(amount);
+
+        if let Some((amount, snippet)) = self.parse_chinese_amount(text) {
+            let money = Money::cny(amount);
+            candidates.push((money, evidence("amount", money_text(money), tr("文本: 中文数字金额", "Text: Chinese numeral amount"), snippet)));
         }
-        
-        if let Some(amount) = self.parse_currency_amount(text) {
-            return Some(amount);
+
+        // 带货币符号或币种代码的金额 (如: ￥1234.56, US$1,234.56, 1.234,56 EUR)
+        for line in text.lines() {
+            if let Some(money) = self.currency_detector.find_first(line) {
+                candidates.push((money, evidence("amount", money_text(money), tr("文本: 货币金额", "Text: currency amount"), line)));
+            }
+        }
+
+        candidates
+    }
+
+    /// 提取购买方名称
+    pub fn extract_buyer_name(&self, text: &str) -> Option<String> {
+        first_value(self.buyer_candidates(text, &[]))
+    }
+
+    /// 销售方名称候选：先按位置，再按文本模式
+    fn seller_candidates(&self, text: &str, layout: &[PageLayout]) -> Vec<(String, FieldEvidence)> {
+        let mut candidates = self.layout_party_candidates(layout, "seller", SELLER_LABELS, 1);
+        candidates.extend(self.text_party_candidates(text, "seller", &self.seller_patterns, 1));
+        candidates
+    }
+
+    /// 购买方名称候选：先按位置，再按文本模式
+    fn buyer_candidates(&self, text: &str, layout: &[PageLayout]) -> Vec<(String, FieldEvidence)> {
+        let mut candidates = self.layout_party_candidates(layout, "buyer_name", BUYER_LABELS, 0);
+        candidates.extend(self.text_party_candidates(text, "buyer_name", &self.buyer_name_patterns, 0));
+        candidates
+    }
+
+    /// 按位置查找单位名称
+    ///
+    /// 优先使用带方向的标签；否则按阅读顺序取第 `index` 个"名称："栏，
+    /// 左右两栏排版时左栏为购买方、右栏为销售方，上下排版时上方为购买方。
+    fn layout_party_candidates(&self, layout: &[PageLayout], field: &str, labels: &[&str], index: usize) -> Vec<(String, FieldEvidence)> {
+        let mut candidates = Vec::new();
+        for label in labels {
+            for page in layout {
+                for anchor in page.find_label(label) {
+                    if let Some(name) = party_name(&value_right_of(page, anchor, label)) {
+                        let item = layout_evidence(field, &name, trf("位置: {}右侧", "Layout: right of {}", &[&label]), page, anchor);
+                        candidates.push((name, item));
+                    }
+                }
+            }
+        }
+
+        let party = layout.iter()
+            .flat_map(|page| {
+                page.find_label("名称").into_iter()
+                    .filter_map(move |anchor| party_name(&value_right_of(page, anchor, "名称")).map(|name| (page, anchor, name)))
+            })
+            .nth(index);
+        if let Some((page, anchor, name)) = party {
+            let item = layout_evidence(field, &name, trf("位置: 第{}个名称栏", "Layout: name field #{}", &[&(index + 1)]), page, anchor);
+            candidates.push((name, item));
+        }
+        candidates
+    }
+
+    /// 按文本模式查找单位名称，最后取第 `index` 个"名称："栏
+    fn text_party_candidates(&self, text: &str, field: &str, patterns: &[Regex], index: usize) -> Vec<(String, FieldEvidence)> {
+        let mut candidates = Vec::new();
+        for (pattern_index, re) in patterns.iter().enumerate() {
+            for caps in re.captures_iter(text) {
+                if let Some(name) = clean_party_name(&caps[1]) {
+                    let item = evidence(field, &name, trf("文本: 名称模式#{}", "Text: name pattern #{}", &[&(pattern_index + 1)]), &caps[0]);
+                    candidates.push((name, item));
+                }
+            }
+        }
+
+        if let Some(caps) = self.party_name_pattern.captures_iter(text).nth(index) {
+            if let Some(name) = clean_party_name(&caps[1]) {
+                let item = evidence(field, &name, trf("文本: 第{}个名称栏", "Text: name field #{}", &[&(index + 1)]), &caps[0]);
+                candidates.push((name, item));
+            }
+        }
+        candidates
+    }
+
+    /// 提取明细中的项目名称（去重，保持出现顺序）
+    pub fn extract_item_names(&self, text: &str) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        for m in self.item_name_pattern.find_iter(text) {
+            if !names.iter().any(|name| name == m.as_str()) {
+                names.push(m.as_str().to_string());
+            }
+        }
+        names
+    }
+
+    /// 按模式顺序列出所有开票日期候选
+    fn issue_date_candidates(&self, text: &str) -> Vec<(NaiveDate, FieldEvidence)> {
+        self.issue_date_patterns.iter()
+            .enumerate()
+            .flat_map(|(index, re)| re.captures_iter(text).map(move |caps| (index, caps)))
+            .filter_map(|(index, caps)| {
+                let year = caps.get(1)?.as_str().parse().ok()?;
+                let month = caps.get(2)?.as_str().parse().ok()?;
+                let day = caps.get(3)?.as_str().parse().ok()?;
+                let date = NaiveDate::from_ymd_opt(year, month, day)?;
+                Some((date, evidence("issue_date", date.to_string(), trf("文本: 日期模式#{}", "Text: date pattern #{}", &[&(index + 1)]), &caps[0])))
+            })
+            .collect()
+    }
+
+    /// 提取增值税发票的代码、号码、金额、税额、税率和纳税人识别号
+    pub fn extract_vat_details(&self, text: &str) -> VatDetails {
+        let capture = |re: &Regex| re.captures(text).and_then(|caps| caps.get(1)).map(|m| m.as_str().to_string());
+
+        // "价税合计" 中也含有 "合计"，跳过紧跟在 "价税" 之后的匹配
+        let subtotal = self.subtotal_pattern.captures_iter(text)
+            .find(|caps| !text[..caps.get(0).unwrap().start()].ends_with("价税"));
+
+        // 税率只在 "税率" 表头之后、价税合计之前查找，避免误取其他百分比
+        let tax_rates = match text.find("税率") {
+            Some(start) => {
+                let rest = &text[start..];
+                let rest = &rest[..rest.find("价税合计").unwrap_or(rest.len())];
+                let mut rates: Vec<String> = Vec::new();
+                for caps in self.tax_rate_pattern.captures_iter(rest) {
+                    let rate = match caps.get(1) {
+                        Some(number) => format!("{}%", number.as_str()),
+                        None => caps[0].to_string(),
+                    };
+                    if !rates.contains(&rate) {
+                        rates.push(rate);
+                    }
+                }
+                rates
+            }
+            None => Vec::new(),
+        };
+
+        let mut tax_ids = self.tax_id_pattern.captures_iter(text).map(|caps| caps[1].to_string());
+
+        VatDetails {
+            invoice_code: capture(&self.invoice_code_pattern),
+            invoice_number: capture(&self.invoice_number_pattern),
+            pre_tax_amount: subtotal.as_ref().and_then(|caps| parse_amount_number(&caps[1])),
+            tax_amount: subtotal.as_ref().and_then(|caps| parse_amount_number(&caps[2])),
+            total_amount: self.total_amount_pattern.captures(text).and_then(|caps| parse_amount_number(&caps[1])),
+            tax_rates,
+            buyer_name: self.extract_buyer_name(text),
+            buyer_tax_id: tax_ids.next(),
+            seller_tax_id: tax_ids.next(),
         }
-        
-        None
     }
 
-    /// 解析价税合计金额（发票总金额）
-    fn parse_total_amount(&self, text: &str) -> Option<f64> {
-        // 首先查找价税合计相关的行
+    /// 从文本中查找价税合计金额（发票总金额）
+    fn text_total_candidates(&self, text: &str, default_currency: Currency) -> Vec<(Money, FieldEvidence)> {
+        let mut candidates = Vec::new();
         let lines: Vec<&str> = text.lines().collect();
-        
+
         for (i, line) in lines.iter().enumerate() {
             // 查找包含"价税合计"、"合计"等关键词的行
             if line.contains("价税合计") || line.contains("合计") {
                 // 在当前行和后续几行中查找金额
-                for j in i..std::cmp::min(i + 3, lines.len()) {
-                    if let Some(amount) = self.extract_amount_from_line(lines[j]) {
-                        return Some(amount);
-                    }
+                let found = lines.iter().skip(i).take(3)
+                    .find_map(|next_line| self.extract_amount_from_line(next_line, default_currency).map(|money| (money, *next_line)));
+                if let Some((money, amount_line)) = found {
+                    candidates.push((money, evidence("amount", money_text(money), tr("文本: 合计行", "Text: total line"), amount_line)));
                 }
             }
         }
-        
-        // 如果没有找到价税合计，查找包含中文大写金额的行
+
+        // 再查找包含中文大写金额的行
         // 通常发票会有"叁佰壹拾柒圆陆角整 ¥317.60"这样的格式
         for line in lines {
             if line.contains("圆") && line.contains("角") && line.contains("整") {
                 // 在这样的行中查找¥符号后的数字
-                if let Some(amount) = self.extract_amount_from_line(line) {
-                    return Some(amount);
+                if let Some(money) = self.extract_amount_from_line(line, default_currency) {
+                    candidates.push((money, evidence("amount", money_text(money), tr("文本: 大写金额行", "Text: amount-in-words line"), line)));
                 }
             }
         }
-        
-        None
-    }
 
-    /// 从单行文本中提取金额
-    fn extract_amount_from_line(&self, line: &str) -> Option<f64> {
-        // 匹配¥符号后的金额
-        let patterns = vec![
-            r"¥(\d+(?:\.\d{2})?)",
-            r"￥(\d+(?:\.\d{2})?)",
-            r"(\d+(?:\.\d{2})?)元",
-            r"金额[：:]\s*(\d+(?:\.\d{2})?)",
-            r"合计[：:]\s*(\d+(?:\.\d{2})?)",
-        ];
+        candidates
+    }
 
-        for pattern in patterns {
-            if let Ok(re) = regex::Regex::new(pattern) {
-                // 找到所有匹配项，取最后一个（通常是总金额）
-                let mut matches: Vec<f64> = Vec::new();
-                for caps in re.captures_iter(line) {
-                    if let Some(amount_str) = caps.get(1) {
-                        if let Ok(amount) = amount_str.as_str().parse::<f64>() {
-                            matches.push(amount);
+    /// 按位置查找总金额：先看标签所在文字块和同一行右侧，再看标签下方
+    fn layout_total_candidates(&self, layout: &[PageLayout], default_currency: Currency) -> Vec<(Money, FieldEvidence)> {
+        let mut candidates = Vec::new();
+        for label in TOTAL_AMOUNT_LABELS {
+            for page in layout {
+                for anchor in page.find_label(label) {
+                    if let Some(money) = self.extract_amount_from_line(&value_right_of(page, anchor, label), default_currency) {
+                        candidates.push((money, layout_evidence("amount", &money_text(money), trf("位置: {}右侧", "Layout: right of {}", &[&label]), page, anchor)));
+                    }
+                    for run in page.below(anchor) {
+                        if let Some(money) = self.extract_amount_from_line(&run.text, default_currency) {
+                            candidates.push((money, layout_evidence("amount", &money_text(money), trf("位置: {}下方", "Layout: below {}", &[&label]), page, run)));
                         }
                     }
                 }
-                
-                if !matches.is_empty() {
-                    // 如果有多个匹配，取最大的那个（通常是含税总额）
-                    return Some(*matches.iter().max_by(|a, b| a.partial_cmp(b).unwrap())?);
-                }
             }
         }
-        
-        None
+        candidates
     }
 
-    /// 解析小数格式的金额 (如: 1234.56)
-    fn parse_decimal_amount(&self, text: &str) -> Option<f64> {
-        // 匹配常见的数字金额格式
-        let patterns = vec![
-            r"金额[：:]\s*(\d+(?:\.\d{2})?)",
-            r"总计[：:]\s*(\d+(?:\.\d{2})?)",
-            r"合计[：:]\s*(\d+(?:\.\d{2})?)",
-            r"应付[：:]\s*(\d+(?:\.\d{2})?)",
-            r"价税合计[：:]\s*(\d+(?:\.\d{2})?)",
-            r"总金额[：:]\s*(\d+(?:\.\d{2})?)",
-            r"(\d{1,6}(?:\.\d{2})?)元",
-            r"(\d+(?:,\d{3})*(?:\.\d{2})?)元",
-        ];
+    /// 从单行文本中提取金额
+    fn extract_amount_from_line(&self, line: &str, default_currency: Currency) -> Option<Money> {
+        let currency = self.currency_detector.detect(line).unwrap_or(default_currency);
 
-        for pattern in patterns {
-            if let Ok(re) = Regex::new(pattern) {
-                if let Some(caps) = re.captures(text) {
-                    if let Some(amount_str) = caps.get(1) {
-                        let clean_amount = amount_str.as_str().replace(",", "");
-                        if let Ok(amount) = clean_amount.parse::<f64>() {
-                            return Some(amount);
-                        }
-                    }
+        // 匹配¥符号后的金额
+        for re in &self.line_patterns {
+            // 找到所有匹配项，取最后一个（通常是总金额）
+            let mut matches: Vec<Amount> = Vec::new();
+            for caps in re.captures_iter(line) {
+                if let Some(amount) = caps.get(1).and_then(|m| parse_amount_number(m.as_str())) {
+                    matches.push(amount);
                 }
             }
-        }
-
-        None
-    }
 
-    /// 解析中文数字金额 (如: 壹万贰仟叁佰肆拾伍元)
-    fn parse_chinese_amount(&self, text: &str) -> Option<f64> {
-        // 简化的中文数字转换
-        let chinese_to_arabic = |_chinese: &str| -> Option<f64> {
-            // 这里实现一个简化的中文数字转换
-            // 在实际应用中可以使用更复杂的中文数字解析库
-            
-            // 查找常见的中文数字模式
-            let patterns = vec![
-                (r"([一二三四五六七八九十百千万]+)元", "chinese_digits"),
-                (r"([壹贰叁肆伍陆柒捌玖拾佰仟万]+)元", "chinese_financial"),
-            ];
-
-            for (pattern, _) in patterns {
-                if let Ok(re) = Regex::new(pattern) {
-                    if let Some(caps) = re.captures(text) {
-                        if let Some(chinese_num) = caps.get(1) {
-                            // 简化处理：如果包含"万"，估算为万级别
-                            let chinese_str = chinese_num.as_str();
-                            if chinese_str.contains("万") {
-                                // 简单估算，实际应用中需要更精确的转换
-                                return Some(10000.0);
-                            } else if chinese_str.contains("千") {
-                                return Some(1000.0);
-                            } else if chinese_str.contains("百") {
-                                return Some(100.0);
-                            }
-                        }
-                    }
-                }
+            if !matches.is_empty() {
+                // 如果有多个匹配，取最大的那个（通常是含税总额）
+                let amount = *matches.iter().max()?;
+                return Some(Money::new(amount, currency));
             }
-            
-            None
-        };
+        }
 
-        chinese_to_arabic(text)
+        // 再查找其他币种标记的金额，如"合计 USD 120.00"
+        self.currency_detector.find_all(line)
+            .into_iter()
+            .max_by_key(|money| money.amount)
     }
 
-    /// 解析带货币符号的金额 (如: ￥1234.56, $1234.56)
-    fn parse_currency_amount(&self, text: &str) -> Option<f64> {
-        let patterns = vec![
-            r"￥(\d+(?:\.\d{2})?)",
-            r"¥(\d+(?:\.\d{2})?)",
-            r"\$(\d+(?:\.\d{2})?)",
-            r"RMB\s*(\d+(?:\.\d{2})?)",
-            r"CNY\s*(\d+(?:\.\d{2})?)",
-        ];
+    /// 解析中文数字金额 (如: 壹万贰仟叁佰肆拾伍元)，同时返回匹配的原文
+    fn parse_chinese_amount<'a>(&self, text: &'a str) -> Option<(Amount, &'a str)> {
+        // 这里实现一个简化的中文数字转换
+        // 在实际应用中可以使用更复杂的中文数字解析库
 
-        for pattern in patterns {
-            if let Ok(re) = Regex::new(pattern) {
-                if let Some(caps) = re.captures(text) {
-                    if let Some(amount_str) = caps.get(1) {
-                        if let Ok(amount) = amount_str.as_str().parse::<f64>() {
-                            return Some(amount);
-                        }
+        // 查找常见的中文数字模式
+        for re in &self.chinese_patterns {
+            if let Some(caps) = re.captures(text) {
+                if let (Some(matched), Some(chinese_num)) = (caps.get(0), caps.get(1)) {
+                    // 简化处理：如果包含"万"，估算为万级别
+                    let chinese_str = chinese_num.as_str();
+                    if chinese_str.contains("万") {
+                        // 简单估算，实际应用中需要更精确的转换
+                        return Some((Amount::from_units(10000), matched.as_str()));
+                    } else if chinese_str.contains("千") {
+                        return Some((Amount::from_units(1000), matched.as_str()));
+                    } else if chinese_str.contains("百") {
+                        return Some((Amount::from_units(100), matched.as_str()));
                     }
                 }
             }
@@ -199,26 +591,353 @@ impl PdfParser {
     }
 
     /// 分析PDF文件并提取发票信息
-    pub fn analyze_pdf(&self, file_path: &str) -> Result<InvoiceInfo, String> {
+    pub fn analyze_pdf(&self, file_path: &str) -> AppResult<InvoiceInfo> {
+        self.analyze_pdf_from_sender(file_path, None)
+    }
+
+    /// 分析邮件导入的PDF，加密时优先尝试该发件人的打开密码
+    pub fn analyze_pdf_from_sender(&self, file_path: &str, sender: Option<&str>) -> AppResult<InvoiceInfo> {
+        self.analyze_with_candidates(file_path, sender).map(|(invoice_info, _)| invoice_info)
+    }
+
+    /// 列出每个字段的全部候选及依据，未采用的候选附带原因
+    pub fn explain_pdf(&self, file_path: &str) -> AppResult<Vec<FieldEvidence>> {
+        self.analyze_with_candidates(file_path, None).map(|(_, candidates)| candidates)
+    }
+
+    fn analyze_with_candidates(&self, file_path: &str, sender: Option<&str>) -> AppResult<(InvoiceInfo, Vec<FieldEvidence>)> {
         let path = Path::new(file_path);
-        let text = self.extract_text_from_pdf(path)?;
-        
-        let amount = self.extract_amount_from_text(&text);
+        let document = self.open_pdf(path, sender)?;
+        let pages = page_texts(&document, path)?;
+        let text = pages.join("\n");
+        // 文字位置用于按标签查找字段和识别明细表，读取失败时退回纯文本
+        let layout = extract_layout(&document).unwrap_or_default();
+        let mut candidates: Vec<FieldEvidence> = Vec::new();
+
+        let segments = self.detect_invoice_segments(&pages);
+        // 多张发票时整份文件的金额和类型取第一张，拆分后再分别识别
+        let (document_type, amount) = match segments.as_slice() {
+            [first, _, ..] => {
+                let first_text = pages[first.start_page - 1..first.end_page].join("\n");
+                (first.document_type, choose(self.amount_candidates(&first_text, &[], first.document_type), &mut candidates))
+            }
+            _ => {
+                let document_type = self.classify_text(&text);
+                (document_type, choose(self.amount_candidates(&text, &layout, document_type), &mut candidates))
+            }
+        };
         let original_filename = path.file_name()
             .and_then(|name| name.to_str())
             .unwrap_or("unknown.pdf")
             .to_string();
-        
-        let suggested_filename = if let Some(amt) = amount {
-            format!("{:.2}元_发票.pdf", amt)
-        } else {
-            "未知金额_发票.pdf".to_string()
-        };
 
-        Ok(InvoiceInfo {
-            amount,
+        let seller = choose(self.seller_candidates(&text, &layout), &mut candidates);
+        let buyer_name = choose(self.buyer_candidates(&text, &layout), &mut candidates);
+        let issue_date = choose(self.issue_date_candidates(&text), &mut candidates);
+
+        let line_items = extract_line_items(&layout);
+        let mut item_names = self.extract_item_names(&text);
+        for item in &line_items {
+            if !item.name.is_empty() && !item_names.contains(&item.name) {
+                item_names.push(item.name.clone());
+            }
+        }
+        let category_candidates = self.categorizer
+            .matching_rules(seller.as_deref(), &item_names, document_type, amount.map(|money| money.amount))
+            .into_iter()
+            .map(|(index, category)| {
+                let snippet = seller.iter().chain(&item_names).cloned().collect::<Vec<_>>().join(" ");
+                let item = evidence("category", &category, trf("类别规则#{}", "Category rule #{}", &[&(index + 1)]), &snippet);
+                (category, item)
+            })
+            .collect();
+        let category = choose(category_candidates, &mut candidates);
+
+        let mut vat = self.extract_vat_details(&text);
+        vat.buyer_name = buyer_name;
+        if let Some(total) = first_value(self.layout_total_candidates(&layout, Currency::CNY)) {
+            vat.total_amount = Some(total.amount);
+        }
+        for (field, re) in [("invoice_code", &self.invoice_code_pattern), ("invoice_number", &self.invoice_number_pattern)] {
+            if let Some(caps) = re.captures(&text) {
+                candidates.push(evidence(field, &caps[1], tr("文本: 号码模式", "Text: number pattern"), &caps[0]));
+            }
+        }
+
+        locate_candidates(&pages, &mut candidates);
+        let evidence = candidates.iter()
+            .filter(|candidate| candidate.rejected.is_none())
+            .cloned()
+            .collect();
+
+        let invoice_info = InvoiceInfo {
+            amount: amount.map(|money| money.amount),
+            currency: amount.map(|money| money.currency).unwrap_or_default(),
+            document_type,
+            seller,
+            issue_date,
             original_filename,
-            suggested_filename,
-        })
+            suggested_filename: String::new(),
+            page_count: pages.len(),
+            segments,
+            vat,
+            item_names,
+            line_items,
+            category,
+            evidence,
+        };
+        Ok((invoice_info, candidates))
+    }
+}
+
+/// 字段识别依据，页码和行号稍后由 `locate_candidates` 填写
+fn evidence(field: &str, value: impl Into<String>, strategy: impl Into<String>, snippet: &str) -> FieldEvidence {
+    FieldEvidence {
+        field: field.to_string(),
+        value: value.into(),
+        strategy: strategy.into(),
+        snippet: snippet.trim().to_string(),
+        page: None,
+        line: None,
+        rejected: None,
+    }
+}
+
+/// 按位置找到的字段依据，原文片段取文字块所在的整行
+fn layout_evidence(field: &str, value: &str, strategy: String, page: &PageLayout, run: &TextRun) -> FieldEvidence {
+    let lines = page.lines();
+    let found = lines.iter()
+        .enumerate()
+        .find(|(_, line)| line.iter().any(|candidate| std::ptr::eq(*candidate, run)));
+    let snippet = match found {
+        Some((_, line)) => line.iter().map(|run| run.text.as_str()).collect::<Vec<_>>().join(" "),
+        None => run.text.clone(),
+    };
+    FieldEvidence {
+        page: Some(run.page),
+        line: found.map(|(index, _)| index + 1),
+        ..evidence(field, value, strategy, &snippet)
+    }
+}
+
+fn money_text(money: Money) -> String {
+    format!("{} {}", money.format_amount(), money.currency.code())
+}
+
+fn first_value<T>(candidates: Vec<(T, FieldEvidence)>) -> Option<T> {
+    candidates.into_iter().next().map(|(value, _)| value)
+}
+
+/// 采用第一个候选，其余候选标记为未采用，依据都追加到 `candidates`
+fn choose<T>(field_candidates: Vec<(T, FieldEvidence)>, candidates: &mut Vec<FieldEvidence>) -> Option<T> {
+    let mut field_candidates = field_candidates.into_iter();
+    let (value, accepted) = field_candidates.next()?;
+    let reason = trf("已采用优先级更高的候选（{}）", "A higher-priority candidate was used ({})", &[&accepted.strategy]);
+    candidates.push(accepted);
+    for (_, mut rejected) in field_candidates {
+        rejected.rejected = Some(reason.clone());
+        candidates.push(rejected);
+    }
+    Some(value)
+}
+
+/// 在各页文本中查找依据的原文片段，填写页码和页内行号
+fn locate_candidates(pages: &[String], candidates: &mut [FieldEvidence]) {
+    for candidate in candidates.iter_mut().filter(|candidate| candidate.page.is_none()) {
+        let Some(needle) = candidate.snippet.lines().map(str::trim).find(|line| !line.is_empty()) else {
+            continue;
+        };
+        let location = pages.iter().enumerate().find_map(|(page_index, page)| {
+            page.lines()
+                .position(|line| line.contains(needle))
+                .map(|line_index| (page_index + 1, line_index + 1))
+        });
+        if let Some((page, line)) = location {
+            candidate.page = Some(page);
+            candidate.line = Some(line);
+        }
+    }
+}
+
+/// 标签之后的文字：标签所在文字块的剩余部分加上同一行右侧的文字块
+fn value_right_of(page: &PageLayout, anchor: &TextRun, label: &str) -> String {
+    let mut value = text_after_label(anchor, label).unwrap_or_default();
+    for run in page.right_of(anchor) {
+        value.push(' ');
+        value.push_str(&run.text);
+    }
+    value
+}
+
+/// 从"名称"栏之后的文字中取出单位名称，要求以冒号开头以排除明细表头
+fn party_name(value: &str) -> Option<String> {
+    let value = value.trim_start().strip_prefix(['：', ':'])?;
+    clean_party_name(value.split_whitespace().next()?)
+        .filter(|name| !name.contains("名称"))
+}
+
+/// 去掉单位名称两端的标点
+fn clean_party_name(name: &str) -> Option<String> {
+    let name = name.trim_matches(|c: char| c.is_ascii_punctuation() || "，。；、".contains(c));
+    if name.is_empty() {
+        None
+    } else {
+        Some(name.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::{dictionary, Object, Stream};
+
+    /// 生成每页一段文字的PDF，`contents` 为各页的内容流
+    fn write_pdf(path: &Path, contents: &[&[u8]]) {
+        let mut document = lopdf::Document::with_version("1.5");
+        let pages_id = document.new_object_id();
+        let font = document.add_object(dictionary! { "Type" => "Font", "Subtype" => "Type1", "BaseFont" => "Helvetica" });
+        let kids: Vec<Object> = contents.iter()
+            .map(|content| {
+                let content_id = document.add_object(Stream::new(dictionary! {}, content.to_vec()));
+                document.add_object(dictionary! { "Type" => "Page", "Parent" => pages_id, "Contents" => content_id }).into()
+            })
+            .collect();
+        document.objects.insert(pages_id, Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => kids,
+            "Count" => contents.len() as i64,
+            "Resources" => dictionary! { "Font" => dictionary! { "F1" => font } },
+            "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+        }));
+        let catalog_id = document.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        document.trailer.set("Root", catalog_id);
+        document.save(path).unwrap();
+    }
+
+    #[test]
+    fn test_truncated_pdf_fails_to_parse() {
+        let dir = std::env::temp_dir().join(format!("invoicepilot-truncated-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let parser = PdfParser::new();
+
+        let path = dir.join("complete.pdf");
+        write_pdf(&path, &[b"BT /F1 12 Tf 72 700 Td (page one) Tj ET", b"BT /F1 12 Tf 72 700 Td (page two) Tj ET"]);
+        assert_eq!(parser.extract_pages_from_pdf(&path).unwrap().len(), 2);
+
+        // 文件被截断
+        let bytes = std::fs::read(&path).unwrap();
+        let truncated = dir.join("truncated.pdf");
+        std::fs::write(&truncated, &bytes[..bytes.len() / 2]).unwrap();
+        assert_eq!(parser.extract_pages_from_pdf(&truncated).unwrap_err().error_code(), "PDF_PARSE_ERROR");
+
+        // 第二页损坏（引用了不存在的字体）时不能只返回第一页
+        let broken_page = dir.join("broken-page.pdf");
+        write_pdf(&broken_page, &[b"BT /F1 12 Tf 72 700 Td (page one) Tj ET", b"BT /F9 12 Tf 72 700 Td (page two) Tj ET"]);
+        let error = parser.extract_pages_from_pdf(&broken_page).unwrap_err();
+        assert_eq!(error.error_code(), "PDF_PARSE_ERROR");
+        assert!(parser.analyze_pdf(&broken_page.to_string_lossy()).is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_detect_invoice_segments() {
+        let parser = PdfParser::new();
+        let pages = vec![
+            "电子发票（普通发票）\n发票号码：24310000000000000001\n价税合计（小写）¥100.00".to_string(),
+            "电子发票（普通发票）\n发票号码：24310000000000000002\n货物明细".to_string(),
+            "续页\n价税合计（小写）¥250.50".to_string(),
+        ];
+
+        let segments = parser.detect_invoice_segments(&pages);
+        assert_eq!(segments.len(), 2);
+        assert_eq!((segments[0].start_page, segments[0].end_page), (1, 1));
+        assert_eq!((segments[1].start_page, segments[1].end_page), (2, 3));
+        assert_eq!(segments[1].invoice_number.as_deref(), Some("24310000000000000002"));
+        assert_eq!(segments[1].amount, Some(Amount::from_cents(25050)));
+    }
+
+    #[test]
+    fn test_extract_seller_and_issue_date() {
+        let parser = PdfParser::new();
+        let text = "开票日期：2024年03月01日\n购 名称：某某科技有限公司\n销 名称：上海某某餐饮管理有限公司\n价税合计 ¥317.60";
+
+        assert_eq!(first_value(parser.seller_candidates(text, &[])).as_deref(), Some("上海某某餐饮管理有限公司"));
+        assert_eq!(parser.extract_buyer_name(text).as_deref(), Some("某某科技有限公司"));
+        assert_eq!(first_value(parser.issue_date_candidates(text)), NaiveDate::from_ymd_opt(2024, 3, 1));
+    }
+
+    #[test]
+    fn test_extract_vat_details() {
+        let parser = PdfParser::new();
+        let text = "发票代码：031002300111\n发票号码：12345678\n购买方 纳税人识别号：91350100M000100Y43\n\
+项目名称 金额 税率 税额\n餐饮服务 300.00 6% 18.00\n合 计 ¥300.00 ¥18.00\n价税合计（大写）叁佰壹拾捌圆整 （小写）¥318.00\n\
+销售方 纳税人识别号：91110000600037341L";
+
+        let vat = parser.extract_vat_details(text);
+        assert_eq!(vat.invoice_code.as_deref(), Some("031002300111"));
+        assert_eq!(vat.invoice_number.as_deref(), Some("12345678"));
+        assert_eq!(vat.pre_tax_amount, Some(Amount::from_cents(30000)));
+        assert_eq!(vat.tax_amount, Some(Amount::from_cents(1800)));
+        assert_eq!(vat.total_amount, Some(Amount::from_cents(31800)));
+        assert_eq!(vat.tax_rates, vec!["6%".to_string()]);
+        assert_eq!(parser.extract_item_names("*餐饮服务*餐费 300.00\n*餐饮服务*餐费"), vec!["*餐饮服务*餐费".to_string()]);
+        assert_eq!(vat.buyer_tax_id.as_deref(), Some("91350100M000100Y43"));
+        assert_eq!(vat.seller_tax_id.as_deref(), Some("91110000600037341L"));
+    }
+
+    #[test]
+    fn test_extract_from_layout() {
+        let parser = PdfParser::new();
+        let run = |x: f64, y: f64, text: &str| TextRun {
+            page: 1,
+            x,
+            y,
+            width: text.chars().count() as f64 * 9.0,
+            font_size: 9.0,
+            text: text.to_string(),
+        };
+        // 左右两栏：左栏购买方、右栏销售方；价税合计的金额在标签右侧
+        let layout = vec![PageLayout {
+            page: 1,
+            width: 595.0,
+            height: 842.0,
+            runs: vec![
+                run(320.0, 100.5, "名称：上海某某餐饮管理有限公司"),
+                run(30.0, 100.0, "名称：某某科技有限公司"),
+                run(30.0, 130.0, "项目名称"),
+                run(200.0, 130.0, "金额"),
+                run(30.0, 300.0, "价税合计（大写）"),
+                run(200.0, 300.0, "叁佰壹拾柒圆陆角整"),
+                run(420.0, 300.0, "（小写）¥317.60"),
+                run(200.0, 320.0, "¥1.00"),
+            ],
+        }];
+
+        assert_eq!(first_value(parser.buyer_candidates("", &layout)).as_deref(), Some("某某科技有限公司"));
+        assert_eq!(first_value(parser.seller_candidates("", &layout)).as_deref(), Some("上海某某餐饮管理有限公司"));
+
+        let amount = parser.extract_amount("合计 ¥1.00", &layout, DocumentType::VatOrdinaryInvoice);
+        assert_eq!(amount, Some(Money::cny(Amount::from_cents(31760))));
+    }
+
+    #[test]
+    fn test_amount_evidence() {
+        let parser = PdfParser::new();
+        let pages = vec!["电子发票（普通发票）\n金额：300.00\n价税合计（小写）¥317.60".to_string()];
+        let candidates = parser.amount_candidates(&pages[0], &[], DocumentType::VatOrdinaryInvoice);
+
+        let mut evidence = Vec::new();
+        let amount = choose(candidates, &mut evidence);
+        locate_candidates(&pages, &mut evidence);
+
+        assert_eq!(amount, Some(Money::cny(Amount::from_cents(31760))));
+        assert_eq!(evidence[0].strategy, "提取规则 vat-total");
+        assert!(evidence[0].snippet.contains("317.60"));
+        assert_eq!((evidence[0].page, evidence[0].line), (Some(1), Some(3)));
+        assert!(evidence[0].rejected.is_none());
+        assert!(evidence.iter().skip(1).all(|item| item.rejected.is_some()));
+        assert!(evidence.iter().any(|item| item.strategy == "文本: 合计行" && item.rejected.is_some()));
+        assert!(evidence.iter().any(|item| item.value == "300.00 CNY"));
     }
-} 
\ No newline at end of file
+}
@@ -0,0 +1,200 @@
+use std::path::Path;
+use lopdf::Document;
+use crate::config::{PdfSplitConfig, SourceHandling};
+use crate::file_service::FileService;
+use crate::error::{AppError, AppResult};
+use crate::i18n::{tr, trf};
+use crate::types::InvoiceSegment;
+
+/// 多发票PDF拆分器
+pub struct PdfSplitter {
+    file_service: FileService,
+}
+
+impl PdfSplitter {
+    pub fn new() -> Self {
+        PdfSplitter {
+            file_service: FileService::new(),
+        }
+    }
+
+    /// 按发票页码范围把PDF拆分为多个文件，返回新文件路径
+    ///
+    /// 新文件与原文件放在同一目录，命名为 `原文件名_第N张.pdf`（英文界面为 `原文件名_partN.pdf`）
+    pub fn split(&self, source_path: &str, segments: &[InvoiceSegment]) -> AppResult<Vec<String>> {
+        let source = Path::new(source_path);
+        let directory = source.parent()
+            .ok_or_else(|| AppError::file_system_error(tr("无法获取文件所在目录", "Unable to determine the file's directory")).with_path(source))?;
+        let stem = source.file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| tr("发票", "invoice").to_string());
+
+        let document = Document::load(source).map_err(|e| match e {
+            lopdf::Error::IO(e) => AppError::io_error_at(e, tr("PDF读取失败", "Failed to read PDF"), source),
+            e => AppError::pdf_parse_error(&trf("PDF读取失败: {}", "Failed to read PDF: {}", &[&e])).with_path(source),
+        })?;
+        if document.is_encrypted() {
+            return Err(AppError::password_required(source_path));
+        }
+        let page_count = document.get_pages().len() as u32;
+
+        // 先检查全部页码范围，避免只拆出一部分文件
+        if let Some(segment) = segments.iter().find(|segment| {
+            segment.start_page == 0 || segment.start_page > segment.end_page || segment.end_page > page_count as usize
+        }) {
+            return Err(AppError::validation_error(&trf(
+                "页码范围无效: {}-{}",
+                "Invalid page range: {}-{}",
+                &[&segment.start_page, &segment.end_page],
+            )).with_path(source));
+        }
+
+        let mut outputs = Vec::new();
+        for (index, segment) in segments.iter().enumerate() {
+            let (start, end) = (segment.start_page as u32, segment.end_page as u32);
+
+            let mut part = document.clone();
+            let other_pages: Vec<u32> = (1..=page_count)
+                .filter(|page| *page < start || *page > end)
+                .collect();
+            part.delete_pages(&other_pages);
+            part.prune_objects();
+            part.compress();
+
+            let filename = self.file_service.resolve_filename_conflict(
+                &directory.to_string_lossy(),
+                &trf("{}_第{}张.pdf", "{}_part{}.pdf", &[&stem, &(index + 1)]),
+            );
+            let output_path = directory.join(filename);
+            part.save(&output_path)
+                .map_err(|e| AppError::io_error_at(e, tr("保存拆分文件失败", "Failed to save split file"), &output_path))?;
+            outputs.push(output_path.to_string_lossy().to_string());
+        }
+
+        Ok(outputs)
+    }
+
+    /// 按配置处理拆分后的原文件，归档时返回归档后的路径
+    pub fn handle_source(&self, source_path: &str, handling: SourceHandling, config: &PdfSplitConfig) -> AppResult<Option<String>> {
+        match handling {
+            SourceHandling::Keep => Ok(None),
+            SourceHandling::Archive => {
+                let source = Path::new(source_path);
+                let directory = source.parent()
+                    .ok_or_else(|| AppError::file_system_error(tr("无法获取文件所在目录", "Unable to determine the file's directory")).with_path(source))?;
+                let archive_directory = directory.join(&config.archive_directory);
+                let filename = source.file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_else(|| "unknown.pdf".to_string());
+                let filename = self.file_service.resolve_filename_conflict(&archive_directory.to_string_lossy(), &filename);
+                let archived_path = archive_directory.join(filename).to_string_lossy().to_string();
+
+                self.file_service.rename_file(source_path, &archived_path)?;
+                Ok(Some(archived_path))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document_classifier::DocumentType;
+    use crate::money::Currency;
+    use lopdf::{dictionary, Object, Stream};
+
+    /// 生成每页内容为 `page N` 的多页PDF
+    fn write_pdf(path: &Path, page_count: usize) {
+        let mut document = Document::with_version("1.5");
+        let pages_id = document.new_object_id();
+        let kids: Vec<Object> = (1..=page_count)
+            .map(|page| {
+                let content = format!("BT /F1 12 Tf 72 700 Td (page {}) Tj ET", page);
+                let content_id = document.add_object(Stream::new(dictionary! {}, content.into_bytes()));
+                document.add_object(dictionary! { "Type" => "Page", "Parent" => pages_id, "Contents" => content_id }).into()
+            })
+            .collect();
+        document.objects.insert(pages_id, Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => kids,
+            "Count" => page_count as i64,
+            "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+        }));
+        let catalog_id = document.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        document.trailer.set("Root", catalog_id);
+        document.save(path).unwrap();
+    }
+
+    fn segment(start_page: usize, end_page: usize) -> InvoiceSegment {
+        InvoiceSegment {
+            start_page,
+            end_page,
+            invoice_number: None,
+            amount: None,
+            currency: Currency::default(),
+            document_type: DocumentType::Unknown,
+        }
+    }
+
+    /// 各页内容中的 `page N` 标记
+    fn page_markers(path: &str) -> Vec<String> {
+        let document = Document::load(path).unwrap();
+        document.get_pages().values()
+            .map(|page_id| {
+                let content = String::from_utf8_lossy(&document.get_page_content(*page_id).unwrap()).to_string();
+                let start = content.find("(page ").unwrap() + 1;
+                content[start..start + content[start..].find(')').unwrap()].to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_split_by_page_ranges() {
+        let dir = std::env::temp_dir().join(format!("invoicepilot-split-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("merged.pdf");
+        write_pdf(&source, 5);
+        // 已有同名文件时不覆盖
+        std::fs::write(dir.join("merged_第1张.pdf"), b"existing").unwrap();
+
+        let splitter = PdfSplitter::new();
+        let outputs = splitter.split(&source.to_string_lossy(), &[segment(1, 2), segment(3, 3), segment(4, 5)]).unwrap();
+        let names: Vec<String> = outputs.iter()
+            .map(|path| Path::new(path).file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, vec!["merged_第1张_1.pdf", "merged_第2张.pdf", "merged_第3张.pdf"]);
+        assert_eq!(std::fs::read(dir.join("merged_第1张.pdf")).unwrap(), b"existing");
+        assert_eq!(page_markers(&outputs[0]), vec!["page 1", "page 2"]);
+        assert_eq!(page_markers(&outputs[1]), vec!["page 3"]);
+        assert_eq!(page_markers(&outputs[2]), vec!["page 4", "page 5"]);
+
+        let archived = splitter.handle_source(&source.to_string_lossy(), SourceHandling::Archive, &PdfSplitConfig::default())
+            .unwrap()
+            .unwrap();
+        assert!(!source.exists());
+        assert_eq!(Path::new(&archived), dir.join(PdfSplitConfig::default().archive_directory).join("merged.pdf"));
+        assert!(Path::new(&archived).exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_invalid_page_ranges_rejected() {
+        let dir = std::env::temp_dir().join(format!("invoicepilot-split-invalid-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("merged.pdf");
+        write_pdf(&source, 3);
+
+        let splitter = PdfSplitter::new();
+        for invalid in [segment(0, 1), segment(3, 2), segment(2, 4)] {
+            let error = splitter.split(&source.to_string_lossy(), &[segment(1, 1), invalid]).unwrap_err();
+            assert_eq!(error.error_code(), "VALIDATION_ERROR");
+        }
+        // 无效范围不会留下拆分文件
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,258 @@
+use std::collections::HashSet;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use chrono::NaiveDate;
+use rust_xlsxwriter::{Format, Workbook};
+use crate::bank_statement::StatementLine;
+use crate::error::{AppError, AppResult};
+use crate::i18n::tr;
+use crate::money::{Amount, Currency};
+use crate::payment_matcher::name_similarity;
+
+/// 默认日期窗口（天）：账单日期与开票日期相差不超过该天数才可对上
+pub const DEFAULT_DATE_WINDOW_DAYS: i64 = 7;
+
+/// 参与对账的发票
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReconcileInvoice {
+    pub path: String,
+    pub file_name: String,
+    pub amount: Option<Amount>,
+    pub currency: Currency,
+    pub issue_date: Option<NaiveDate>,
+    pub seller: Option<String>,
+    /// 费用类别
+    pub category: Option<String>,
+}
+
+/// 对上的一组发票和账单记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReconciledPair {
+    pub invoice: ReconcileInvoice,
+    pub line: StatementLine,
+    /// 账单日期减开票日期的天数，开票日期未知时为空
+    pub date_difference_days: Option<i64>,
+    pub counterparty_similarity: f64,
+}
+
+/// 对账结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReconciliationReport {
+    pub date_window_days: i64,
+    pub matched: Vec<ReconciledPair>,
+    /// 没有对应付款记录的发票
+    pub invoices_without_payment: Vec<ReconcileInvoice>,
+    /// 没有对应发票的付款
+    pub payments_without_invoice: Vec<StatementLine>,
+}
+
+/// 账单与发票对账
+pub struct Reconciler;
+
+impl Reconciler {
+    pub fn new() -> Self {
+        Reconciler
+    }
+
+    /// 按金额（同币种、金额相等）和日期窗口对账
+    ///
+    /// 候选按日期差从小到大、交易对方与销售方的相似度从高到低排序后贪心一一配对
+    pub fn reconcile(&self, invoices: &[ReconcileInvoice], lines: &[StatementLine], date_window_days: i64) -> ReconciliationReport {
+        let mut candidates = Vec::new();
+        for (invoice_index, invoice) in invoices.iter().enumerate() {
+            let Some(amount) = invoice.amount else {
+                continue;
+            };
+            for (line_index, line) in lines.iter().enumerate() {
+                if line.amount != amount || line.currency != invoice.currency {
+                    continue;
+                }
+                let difference = invoice.issue_date.map(|date| (line.date - date).num_days());
+                if difference.is_some_and(|days| days.abs() > date_window_days) {
+                    continue;
+                }
+                let similarity = invoice.seller.as_deref()
+                    .map(|seller| name_similarity(seller, &line.counterparty))
+                    .unwrap_or(0.0);
+                // 开票日期未知时排在日期已知的候选之后
+                let distance = difference.map(i64::abs).unwrap_or(date_window_days + 1);
+                candidates.push((distance, similarity, invoice_index, line_index, difference));
+            }
+        }
+        candidates.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.total_cmp(&a.1)));
+
+        let mut used_invoices = HashSet::new();
+        let mut used_lines = HashSet::new();
+        let mut matched = Vec::new();
+        for (_, similarity, invoice_index, line_index, difference) in candidates {
+            if used_invoices.contains(&invoice_index) || used_lines.contains(&line_index) {
+                continue;
+            }
+            used_invoices.insert(invoice_index);
+            used_lines.insert(line_index);
+            matched.push(ReconciledPair {
+                invoice: invoices[invoice_index].clone(),
+                line: lines[line_index].clone(),
+                date_difference_days: difference,
+                counterparty_similarity: similarity,
+            });
+        }
+        matched.sort_by_key(|pair| pair.line.date);
+
+        ReconciliationReport {
+            date_window_days,
+            matched,
+            invoices_without_payment: invoices.iter().enumerate()
+                .filter(|(index, _)| !used_invoices.contains(index))
+                .map(|(_, invoice)| invoice.clone())
+                .collect(),
+            payments_without_invoice: lines.iter().enumerate()
+                .filter(|(index, _)| !used_lines.contains(index))
+                .map(|(_, line)| line.clone())
+                .collect(),
+        }
+    }
+
+    /// 导出对账报告，按扩展名生成 .xlsx（三个工作表）或 .csv（带状态列）
+    pub fn export(&self, report: &ReconciliationReport, output_path: &Path) -> AppResult<()> {
+        let extension = output_path.extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let bytes = match extension.as_str() {
+            "xlsx" => Self::report_xlsx(report)?,
+            "csv" => Self::report_csv(report)?,
+            _ => return Err(AppError::validation_error(tr("对账报告只支持导出为 .xlsx 或 .csv", "Reconciliation reports can only be exported as .xlsx or .csv")).with_path(output_path)),
+        };
+        std::fs::write(output_path, bytes)
+            .map_err(|e| AppError::io_error_at(e, tr("保存对账报告失败", "Failed to save reconciliation report"), output_path))
+    }
+
+    fn header() -> [&'static str; 11] {
+        [
+            tr("状态", "Status"), tr("发票文件", "Invoice file"), tr("发票金额", "Invoice amount"),
+            tr("币种", "Currency"), tr("开票日期", "Issue date"), tr("销售方", "Seller"), tr("费用类别", "Category"),
+            tr("交易日期", "Transaction date"), tr("交易金额", "Transaction amount"),
+            tr("交易对方", "Counterparty"), tr("交易说明", "Description"),
+        ]
+    }
+
+    /// 已匹配、发票无付款、付款无发票三种状态的名称
+    fn statuses() -> [&'static str; 3] {
+        [
+            tr("已匹配", "Matched"),
+            tr("发票无付款", "Invoices without payment"),
+            tr("付款无发票", "Payments without invoice"),
+        ]
+    }
+
+    /// 报告的全部行，第一列为状态
+    fn report_rows(report: &ReconciliationReport) -> Vec<Vec<String>> {
+        let invoice_cells = |invoice: &ReconcileInvoice| vec![
+            invoice.file_name.clone(),
+            invoice.amount.map(|amount| amount.to_string()).unwrap_or_default(),
+            invoice.currency.code().to_string(),
+            invoice.issue_date.map(|date| date.to_string()).unwrap_or_default(),
+            invoice.seller.clone().unwrap_or_default(),
+            invoice.category.clone().unwrap_or_default(),
+        ];
+        let line_cells = |line: &StatementLine| vec![
+            line.date.to_string(),
+            line.amount.to_string(),
+            line.counterparty.clone(),
+            line.description.clone(),
+        ];
+
+        let [matched, without_payment, without_invoice] = Self::statuses();
+        let mut rows = Vec::new();
+        for pair in &report.matched {
+            let mut row = vec![matched.to_string()];
+            row.extend(invoice_cells(&pair.invoice));
+            row.extend(line_cells(&pair.line));
+            rows.push(row);
+        }
+        for invoice in &report.invoices_without_payment {
+            let mut row = vec![without_payment.to_string()];
+            row.extend(invoice_cells(invoice));
+            row.extend(vec![String::new(); 4]);
+            rows.push(row);
+        }
+        for line in &report.payments_without_invoice {
+            let mut row = vec![without_invoice.to_string()];
+            row.extend([String::new(), String::new(), line.currency.code().to_string()]);
+            row.extend(vec![String::new(); 3]);
+            row.extend(line_cells(line));
+            rows.push(row);
+        }
+        rows
+    }
+
+    fn report_csv(report: &ReconciliationReport) -> AppResult<Vec<u8>> {
+        // 带BOM，Excel 直接打开时不乱码
+        let mut writer = csv::Writer::from_writer(b"\xEF\xBB\xBF".to_vec());
+        writer.write_record(Self::header())?;
+        for row in Self::report_rows(report) {
+            writer.write_record(&row)?;
+        }
+        Ok(writer.into_inner()?)
+    }
+
+    fn report_xlsx(report: &ReconciliationReport) -> AppResult<Vec<u8>> {
+        let rows = Self::report_rows(report);
+        let mut workbook = Workbook::new();
+        let bold = Format::new().set_bold();
+
+        // 每种状态一个工作表，状态即表名
+        for status in Self::statuses() {
+            let sheet = workbook.add_worksheet();
+            sheet.set_name(status)?;
+            for (col, title) in Self::header().iter().enumerate().skip(1) {
+                sheet.write_string_with_format(0, col as u16 - 1, *title, &bold)?;
+            }
+            for (row_number, row) in (1u32..).zip(rows.iter().filter(|row| row[0] == status)) {
+                for (col, value) in row.iter().enumerate().skip(1) {
+                    sheet.write_string(row_number, col as u16 - 1, value)?;
+                }
+            }
+            sheet.autofit();
+        }
+
+        Ok(workbook.save_to_buffer()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reconcile() {
+        let date = |day| NaiveDate::from_ymd_opt(2024, 3, day).unwrap();
+        let invoice = |path: &str, cents, day| ReconcileInvoice {
+            path: path.to_string(),
+            file_name: path.to_string(),
+            amount: Some(Amount::from_cents(cents)),
+            currency: Currency::CNY,
+            issue_date: Some(date(day)),
+            seller: Some("星巴克咖啡有限公司".to_string()),
+            category: None,
+        };
+        let line = |cents, day| StatementLine {
+            date: date(day),
+            amount: Amount::from_cents(cents),
+            currency: Currency::CNY,
+            counterparty: "星巴克".to_string(),
+            description: String::new(),
+            reference: None,
+        };
+        let invoices = vec![invoice("a.pdf", 31760, 1), invoice("b.pdf", 31760, 20), invoice("c.pdf", 5000, 1)];
+        let lines = vec![line(31760, 3), line(31760, 28), line(1200, 5)];
+
+        let report = Reconciler::new().reconcile(&invoices, &lines, DEFAULT_DATE_WINDOW_DAYS);
+        assert_eq!(report.matched.len(), 1);
+        assert_eq!(report.matched[0].invoice.path, "a.pdf");
+        assert_eq!(report.matched[0].date_difference_days, Some(2));
+        assert_eq!(report.invoices_without_payment.len(), 2);
+        assert_eq!(report.payments_without_invoice.len(), 2);
+        assert_eq!(Reconciler::report_rows(&report).len(), 5);
+    }
+}
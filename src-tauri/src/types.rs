@@ -1,5 +1,9 @@
 use serde::{Deserialize, Serialize};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
+use crate::document_classifier::DocumentType;
+use crate::error::AppError;
+use crate::invoice_validator::ValidationResult;
+use crate::money::{Amount, Currency};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PdfFile {
@@ -8,8 +12,58 @@ pub struct PdfFile {
     pub path: String,
     pub size: u64,
     pub modified: DateTime<Utc>,
-    pub amount: Option<f64>,
+    pub amount: Option<Amount>,
+    pub currency: Currency,
+    pub document_type: DocumentType,
+    /// 销售方名称
+    pub seller: Option<String>,
+    /// 开票日期
+    pub issue_date: Option<NaiveDate>,
+    /// 文件中识别到的发票张数（大于1时可拆分）
+    pub invoice_count: usize,
     pub suggested_name: Option<String>,
+    /// 费用类别
+    pub category: Option<String>,
+    /// 增值税发票的逐条校验结果
+    pub validation: Vec<ValidationResult>,
+    /// 购买方与当前公司抬头不符（默认不参与批量重命名）
+    pub buyer_mismatch: bool,
+    /// 扫描状态，非 `Ok` 时说明文件为何使用兜底文件名
+    #[serde(default)]
+    pub status: ScanStatus,
+    /// 状态的详细说明（错误信息、匹配的排除规则等）
+    #[serde(default)]
+    pub status_detail: Option<String>,
+}
+
+/// 单个文件的扫描状态
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScanStatus {
+    #[default]
+    Ok,
+    /// PDF解析失败
+    ParseFailed,
+    /// 解析成功但没有识别到金额
+    NoAmount,
+    /// 超过最大文件大小，未解析
+    TooLarge,
+    /// 文件名匹配排除规则，未解析
+    Excluded,
+    /// 没有读取权限
+    PermissionDenied,
+    /// PDF已加密且没有可用的打开密码，需要用户输入
+    PasswordRequired,
+}
+
+impl ScanStatus {
+    /// 按分析PDF时的错误种类确定状态
+    pub fn from_error(error: &AppError) -> Self {
+        match error.kind() {
+            AppError::PasswordRequired(_) => ScanStatus::PasswordRequired,
+            AppError::PermissionError(_) => ScanStatus::PermissionDenied,
+            _ => ScanStatus::ParseFailed,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -22,25 +76,119 @@ pub struct ImageFile {
     pub suggested_name: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct InvoiceInfo {
-    pub amount: Option<f64>,
+    pub amount: Option<Amount>,
+    pub currency: Currency,
+    pub document_type: DocumentType,
+    pub seller: Option<String>,
+    pub issue_date: Option<NaiveDate>,
     pub original_filename: String,
+    /// 建议文件名，由命名引擎按文件名模板生成（解析结果中为空）
     pub suggested_filename: String,
+    /// PDF总页数
+    pub page_count: usize,
+    /// 每张发票所占的页码范围
+    pub segments: Vec<InvoiceSegment>,
+    /// 增值税发票的票面字段
+    pub vat: VatDetails,
+    /// 明细中的项目名称，如 "*餐饮服务*餐费"
+    pub item_names: Vec<String>,
+    /// 按文字位置识别的明细行
+    pub line_items: Vec<LineItem>,
+    /// 费用类别
+    pub category: Option<String>,
+    /// 各字段采用的识别依据
+    #[serde(default)]
+    pub evidence: Vec<FieldEvidence>,
+}
+
+/// 字段的识别依据
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldEvidence {
+    /// 字段名，如 amount、seller、issue_date
+    pub field: String,
+    /// 识别出的值
+    pub value: String,
+    /// 命中的策略或规则
+    pub strategy: String,
+    /// 命中的原文片段
+    pub snippet: String,
+    /// 所在页码和页内行号（从1开始），无法定位时为空
+    pub page: Option<usize>,
+    pub line: Option<usize>,
+    /// 未采用的原因，采用的候选为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rejected: Option<String>,
+}
+
+/// 发票明细表（货物或应税劳务、服务名称）中的一行
+///
+/// 数量和单价常有多位小数，保留票面原文
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LineItem {
+    pub name: String,
+    /// 规格型号
+    pub specification: Option<String>,
+    pub unit: Option<String>,
+    pub quantity: Option<String>,
+    pub unit_price: Option<String>,
+    pub amount: Option<Amount>,
+    pub tax_rate: Option<String>,
+    pub tax_amount: Option<Amount>,
+}
+
+/// 增值税发票票面上用于校验的字段
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VatDetails {
+    pub invoice_code: Option<String>,
+    pub invoice_number: Option<String>,
+    /// 合计金额（不含税）
+    pub pre_tax_amount: Option<Amount>,
+    /// 合计税额
+    pub tax_amount: Option<Amount>,
+    /// 价税合计
+    pub total_amount: Option<Amount>,
+    /// 明细中出现的税率，如 "6%"、"免税"
+    pub tax_rates: Vec<String>,
+    pub buyer_name: Option<String>,
+    pub buyer_tax_id: Option<String>,
+    pub seller_tax_id: Option<String>,
+}
+
+/// 多发票PDF中的一张发票（页码从1开始）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InvoiceSegment {
+    pub start_page: usize,
+    pub end_page: usize,
+    pub invoice_number: Option<String>,
+    pub amount: Option<Amount>,
+    pub currency: Currency,
+    pub document_type: DocumentType,
+}
+
+/// 拆分多发票PDF的结果
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SplitResult {
+    pub source_path: String,
+    /// 原文件归档后的路径（保留原文件时为空）
+    pub archived_path: Option<String>,
+    pub files: Vec<PdfFile>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RenamePreview {
     pub original_name: String,
     pub suggested_name: String,
-    pub amount: Option<f64>,
+    pub amount: Option<Amount>,
+    pub currency: Currency,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RenameOperation {
     pub old_path: String,
     pub new_path: String,
-    pub amount: Option<f64>,
+    pub amount: Option<Amount>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -0,0 +1,236 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use notify_debouncer_mini::notify::{self, RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, Debouncer};
+use crate::config::{WatchConfig, WatchFolder};
+use crate::document_classifier::DocumentType;
+use crate::error::{AppError, AppResult};
+use crate::i18n::tr;
+use crate::file_service::IMAGE_EXTENSIONS;
+use crate::money::{Amount, Currency};
+use crate::logger;
+
+/// 新文件加入待确认队列
+pub const EVENT_FILE_QUEUED: &str = "watch:queued";
+/// 新文件已自动重命名
+pub const EVENT_FILE_RENAMED: &str = "watch:renamed";
+/// 新文件处理失败
+pub const EVENT_FILE_FAILED: &str = "watch:failed";
+
+/// 监控到的文件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WatchedFileKind {
+    Pdf,
+    Image,
+}
+
+/// 监控到的新文件及其识别结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchedFile {
+    pub path: String,
+    /// 所属的监控文件夹
+    pub folder: String,
+    pub kind: WatchedFileKind,
+    pub amount: Option<Amount>,
+    pub currency: Currency,
+    pub document_type: DocumentType,
+    pub suggested_name: String,
+    /// 购买方与当前公司抬头不符
+    pub buyer_mismatch: bool,
+    /// 自动重命名后的路径
+    pub renamed_path: Option<String>,
+    /// 处理失败的原因
+    pub error: Option<String>,
+}
+
+/// 待确认队列
+pub struct WatchQueue {
+    files: Vec<WatchedFile>,
+    /// 由本程序重命名产生的路径，收到它们的事件时忽略
+    produced_paths: HashSet<PathBuf>,
+}
+
+impl WatchQueue {
+    pub fn new() -> Self {
+        WatchQueue {
+            files: Vec::new(),
+            produced_paths: HashSet::new(),
+        }
+    }
+
+    /// 加入队列（同一路径只保留最新结果）
+    pub fn push(&mut self, file: WatchedFile) {
+        self.files.retain(|queued| queued.path != file.path);
+        self.files.push(file);
+    }
+
+    pub fn files(&self) -> &[WatchedFile] {
+        &self.files
+    }
+
+    /// 从队列中取出指定路径的文件
+    pub fn take(&mut self, paths: &[String]) -> Vec<WatchedFile> {
+        let (taken, kept) = std::mem::take(&mut self.files)
+            .into_iter()
+            .partition(|file| paths.contains(&file.path));
+        self.files = kept;
+        taken
+    }
+
+    /// 记录由本程序产生的文件路径
+    pub fn mark_produced(&mut self, path: &Path) {
+        self.produced_paths.insert(path.to_path_buf());
+    }
+
+    /// 事件是否由本程序重命名引起（判断后即清除记录）
+    pub fn is_produced(&mut self, path: &Path) -> bool {
+        self.produced_paths.remove(path)
+    }
+}
+
+/// 文件夹监控器
+pub struct FolderWatcher {
+    debouncer: Option<Debouncer<RecommendedWatcher>>,
+}
+
+impl FolderWatcher {
+    pub fn new() -> Self {
+        FolderWatcher { debouncer: None }
+    }
+
+    /// 按配置开始监控，已在监控时先停止
+    ///
+    /// 文件停止变化 `debounce_ms` 毫秒后，对每个新的 PDF（及按配置的图片）调用 `handler`
+    pub fn start<F>(&mut self, config: &WatchConfig, handler: F) -> AppResult<()>
+    where
+        F: Fn(&WatchFolder, &Path) + Send + 'static,
+    {
+        self.stop();
+
+        let folders = config.folders.clone();
+        let watched_folders = folders.clone();
+        let mut debouncer = new_debouncer(
+            Duration::from_millis(config.debounce_ms),
+            move |result: DebounceEventResult| match result {
+                Ok(events) => {
+                    for event in events {
+                        if let Some(folder) = Self::matching_folder(&watched_folders, &event.path) {
+                            handler(folder, &event.path);
+                        }
+                    }
+                }
+                Err(e) => logger::error("文件夹监控出错", &[("error", &e)]),
+            },
+        )
+        .map_err(|e| AppError::file_system_error(&format!("{}: {}", tr("无法创建文件夹监控", "Unable to create folder watcher"), e)))?;
+
+        for folder in &folders {
+            let mode = if folder.recursive {
+                RecursiveMode::Recursive
+            } else {
+                RecursiveMode::NonRecursive
+            };
+            debouncer.watcher()
+                .watch(Path::new(&folder.path), mode)
+                .map_err(|e| Self::watch_error(e, Path::new(&folder.path)))?;
+        }
+
+        self.debouncer = Some(debouncer);
+        Ok(())
+    }
+
+    /// 监控文件夹的错误，IO错误按种类归类
+    fn watch_error(error: notify::Error, path: &Path) -> AppError {
+        let context = tr("无法监控文件夹", "Unable to watch folder");
+        match error.kind {
+            notify::ErrorKind::Io(e) => AppError::io_error_at(e, context, path),
+            _ => AppError::file_system_error(&format!("{}: {}", context, error)).with_path(path),
+        }
+    }
+
+    /// 停止监控
+    pub fn stop(&mut self) {
+        self.debouncer = None;
+    }
+
+    /// 找到文件所属的监控文件夹，并过滤掉不需要处理的文件
+    fn matching_folder<'a>(folders: &'a [WatchFolder], path: &Path) -> Option<&'a WatchFolder> {
+        // 删除、移走的文件和隐藏/临时文件不处理
+        if !path.is_file() {
+            return None;
+        }
+        let file_name = path.file_name()?.to_string_lossy();
+        if file_name.starts_with('.') || file_name.starts_with('~') {
+            return None;
+        }
+
+        let kind = Self::file_kind(path)?;
+        folders.iter()
+            .filter(|folder| kind == WatchedFileKind::Pdf || folder.include_images)
+            .filter(|folder| match path.parent() {
+                Some(parent) if folder.recursive => parent.starts_with(&folder.path),
+                Some(parent) => parent == Path::new(&folder.path),
+                None => false,
+            })
+            // 嵌套监控时取最具体的文件夹
+            .max_by_key(|folder| folder.path.len())
+    }
+
+    /// 按扩展名判断文件类型，不支持的返回 None
+    pub fn file_kind(path: &Path) -> Option<WatchedFileKind> {
+        let extension = path.extension()?.to_string_lossy().to_lowercase();
+        if extension == "pdf" {
+            Some(WatchedFileKind::Pdf)
+        } else if IMAGE_EXTENSIONS.contains(&extension.as_str()) {
+            Some(WatchedFileKind::Image)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn watched_file(path: &str) -> WatchedFile {
+        WatchedFile {
+            path: path.to_string(),
+            folder: "/inbox".to_string(),
+            kind: WatchedFileKind::Pdf,
+            amount: None,
+            currency: Currency::CNY,
+            document_type: DocumentType::Unknown,
+            suggested_name: "未知金额_发票.pdf".to_string(),
+            buyer_mismatch: false,
+            renamed_path: None,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_queue_take_and_produced_paths() {
+        let mut queue = WatchQueue::new();
+        queue.push(watched_file("/inbox/a.pdf"));
+        queue.push(watched_file("/inbox/b.pdf"));
+        queue.push(watched_file("/inbox/a.pdf"));
+        assert_eq!(queue.files().len(), 2);
+
+        let taken = queue.take(&["/inbox/a.pdf".to_string()]);
+        assert_eq!(taken.len(), 1);
+        assert_eq!(queue.files()[0].path, "/inbox/b.pdf");
+
+        queue.mark_produced(Path::new("/inbox/100.00元_发票.pdf"));
+        assert!(queue.is_produced(Path::new("/inbox/100.00元_发票.pdf")));
+        assert!(!queue.is_produced(Path::new("/inbox/100.00元_发票.pdf")));
+    }
+
+    #[test]
+    fn test_file_kind() {
+        assert_eq!(FolderWatcher::file_kind(Path::new("a.PDF")), Some(WatchedFileKind::Pdf));
+        assert_eq!(FolderWatcher::file_kind(Path::new("a.jpeg")), Some(WatchedFileKind::Image));
+        assert_eq!(FolderWatcher::file_kind(Path::new("a.crdownload")), None);
+    }
+}
@@ -1,5 +1,6 @@
 // 导入Tauri API
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 
 // 文件接口定义
 interface FileItem {
@@ -8,11 +9,105 @@ interface FileItem {
   path: string;
   size: number;
   modified: string;
-  amount?: number;
+  // 金额以字符串传递（如 "317.60"），保证精确到分
+  amount?: string;
+  currency?: string;
+  seller?: string;
+  // 开票日期（YYYY-MM-DD）
+  issue_date?: string;
+  // 文件中的发票张数，大于1时可拆分
+  invoice_count?: number;
+  // 费用类别
+  category?: string;
   suggested_name?: string;
+  // 增值税发票的逐条校验结果
+  validation?: ValidationResult[];
+  // 购买方与当前公司抬头不符，默认不参与批量重命名
+  buyer_mismatch?: boolean;
+  // 扫描状态，非 Ok 的文件没有建议文件名，不能选择重命名
+  status?: ScanStatus;
+  status_detail?: string;
   selected: boolean;
 }
 
+type ScanStatus = 'Ok' | 'ParseFailed' | 'NoAmount' | 'TooLarge' | 'Excluded' | 'PermissionDenied' | 'PasswordRequired';
+
+// 界面语言（配置中的 ui_config.language），决定标记的显示文字
+type Language = 'Chinese' | 'English';
+let uiLanguage: Language = 'Chinese';
+
+// 读取界面语言，失败时保持中文
+async function loadLanguage() {
+  try {
+    const config = await invoke<{ ui_config: { language: Language } }>('get_config');
+    uiLanguage = config.ui_config.language;
+  } catch (error) {
+    console.error('❌ 读取界面语言失败:', error);
+  }
+}
+
+// 扫描状态的显示名称
+const SCAN_STATUS_LABELS: Record<Language, Record<ScanStatus, string>> = {
+  Chinese: {
+    Ok: '正常',
+    ParseFailed: '解析失败',
+    NoAmount: '未识别金额',
+    TooLarge: '文件过大',
+    Excluded: '已排除',
+    PermissionDenied: '无权限',
+    PasswordRequired: '需要密码',
+  },
+  English: {
+    Ok: 'OK',
+    ParseFailed: 'Parse failed',
+    NoAmount: 'No amount',
+    TooLarge: 'Too large',
+    Excluded: 'Excluded',
+    PermissionDenied: 'Permission denied',
+    PasswordRequired: 'Password required',
+  },
+};
+
+// 扫描状态正常的文件才能选择（图片文件没有扫描状态）
+function isSelectable(file: FileItem): boolean {
+  return !file.status || file.status === 'Ok';
+}
+
+// 发票校验结果
+interface ValidationResult {
+  rule: 'InvoiceCode' | 'InvoiceNumber' | 'AmountSum' | 'TaxRate' | 'BuyerName' | 'BuyerTaxId' | 'TaxIdChecksum';
+  status: 'Passed' | 'Failed' | 'Skipped';
+  message: string;
+}
+
+// 校验规则的显示名称
+const VALIDATION_RULE_LABELS: Record<Language, Record<ValidationResult['rule'], string>> = {
+  Chinese: {
+    InvoiceCode: '发票代码',
+    InvoiceNumber: '发票号码',
+    AmountSum: '价税合计',
+    TaxRate: '税率',
+    BuyerName: '购买方名称',
+    BuyerTaxId: '购买方税号',
+    TaxIdChecksum: '税号校验位',
+  },
+  English: {
+    InvoiceCode: 'Invoice code',
+    InvoiceNumber: 'Invoice number',
+    AmountSum: 'Total',
+    TaxRate: 'Tax rate',
+    BuyerName: 'Buyer name',
+    BuyerTaxId: 'Buyer tax ID',
+    TaxIdChecksum: 'Tax ID checksum',
+  },
+};
+
+// 购买方与当前公司抬头不符的标记
+const BUYER_MISMATCH_LABELS: Record<Language, string> = {
+  Chinese: '抬头不符',
+  English: 'Buyer mismatch',
+};
+
 // 图片文件接口定义
 interface ImageFile {
   id: string;
@@ -28,7 +123,7 @@ interface ImageFile {
 interface RenameOperation {
   old_path: string;
   new_path: string;
-  amount?: number;
+  amount?: string;
 }
 
 // 重命名结果接口
@@ -39,6 +134,26 @@ interface RenameResult {
   failed_files: string[];
 }
 
+// 后端命令返回的错误
+interface AppError {
+  code: string;
+  message: string;
+  user_message: string;
+  // 相关的文件或目录
+  path?: string;
+  retryable: boolean;
+}
+
+// 错误的提示文字，可重试的错误附带提示
+function describeError(error: unknown): string {
+  const appError = error as Partial<AppError>;
+  if (typeof appError?.user_message !== 'string') {
+    return String(error);
+  }
+  const location = appError.path ? `（${appError.path}）` : '';
+  return appError.user_message + location + (appError.retryable ? '，请重试' : '');
+}
+
 // Tab管理器
 class TabManager {
   private currentTab: string = 'pdf';
@@ -160,7 +275,7 @@ class AppState {
   // 切换文件选择状态
   toggleFileSelection(fileId: string) {
     const file = this.files.find(f => f.id === fileId);
-    if (file) {
+    if (file && isSelectable(file)) {
       file.selected = !file.selected;
       if (file.selected) {
         this.selectedFiles.add(fileId);
@@ -172,8 +287,9 @@ class AppState {
 
   // 全选/取消全选
   toggleSelectAll() {
-    const allSelected = this.files.every(f => f.selected);
-    this.files.forEach(file => {
+    const selectable = this.files.filter(isSelectable);
+    const allSelected = selectable.every(f => f.selected);
+    selectable.forEach(file => {
       file.selected = !allSelected;
       if (file.selected) {
         this.selectedFiles.add(file.id);
@@ -188,9 +304,9 @@ class AppState {
     return this.selectedFiles.size;
   }
 
-  // 获取总文件数
+  // 获取可选择的文件数
   getTotalCount() {
-    return this.files.length;
+    return this.files.filter(isSelectable).length;
   }
 
   // 获取选中的文件列表
@@ -305,7 +421,8 @@ class UIManager {
 
     this.appState.files.forEach(file => {
       const fileItemElement = document.createElement("div");
-      fileItemElement.className = `file-item ${file.selected ? "selected" : ""}`;
+      const selectable = isSelectable(file);
+      fileItemElement.className = `file-item ${file.selected ? "selected" : ""} ${selectable ? "" : "disabled"}`;
       
       // 格式化文件大小
       const formattedSize = this.formatFileSize(file.size);
@@ -314,13 +431,25 @@ class UIManager {
       
       // 根据当前tab显示不同的图标
       const fileIcon = this.appState.currentTab === 'pdf' ? '📄' : '🖼️';
+
+      // 每条校验规则显示为一个标记，鼠标悬停显示说明
+      const validationHTML = (file.validation ?? []).map(result => {
+        const mark = result.status === 'Passed' ? '✓' : result.status === 'Failed' ? '✗' : '–';
+        return `<span class="validation-badge ${result.status.toLowerCase()}" title="${result.message}">${mark} ${VALIDATION_RULE_LABELS[uiLanguage][result.rule]}</span>`;
+      }).join('');
+      
+      // 非正常状态显示为一个标记，鼠标悬停显示详细原因
+      const statusHTML = file.status && file.status !== 'Ok'
+        ? `<span class="scan-status" title="${file.status_detail ?? ''}">${SCAN_STATUS_LABELS[uiLanguage][file.status]}</span> `
+        : '';
       
       fileItemElement.innerHTML = `
-        <input type="checkbox" class="checkbox file-checkbox" data-file-id="${file.id}" ${file.selected ? "checked" : ""}>
+        <input type="checkbox" class="checkbox file-checkbox" data-file-id="${file.id}" ${file.selected ? "checked" : ""} ${selectable ? "" : "disabled"}>
         <div class="file-icon">${fileIcon}</div>
         <div class="file-info">
-          <div class="file-name" title="${file.name}">${file.name}</div>
-          <div class="file-meta">${formattedSize} • ${formattedDate}</div>
+          <div class="file-name" title="${file.name}">${statusHTML}${file.buyer_mismatch ? `<span class="buyer-mismatch">${BUYER_MISMATCH_LABELS[uiLanguage]}</span> ` : ''}${file.name}</div>
+          <div class="file-meta">${formattedSize} • ${formattedDate}${file.category ? ` • ${file.category}` : ''}</div>
+          ${validationHTML ? `<div class="file-validation">${validationHTML}</div>` : ''}
         </div>
       `;
 
@@ -436,7 +565,8 @@ class UIManager {
     
     if (this.appState.currentTab === 'pdf') {
       if (file.amount) {
-        return `${file.amount.toFixed(2)}元_发票.pdf`;
+        const unit = !file.currency || file.currency === 'CNY' ? '元' : file.currency;
+        return `${file.amount}${unit}_发票.pdf`;
       }
       return "未知金额_发票.pdf";
     } else {
@@ -534,6 +664,13 @@ class UIManager {
     const selectedFiles = this.appState.getSelectedFiles();
     if (selectedFiles.length === 0) return;
 
+    // 抬头不符的发票默认跳过，用户确认后才一起重命名
+    const mismatched = selectedFiles.filter(file => file.buyer_mismatch);
+    let allowBuyerMismatch: string[] = [];
+    if (mismatched.length > 0 && confirm(`${mismatched.length} 张发票的购买方与当前公司抬头不符，是否仍然重命名？\n选择"取消"将跳过这些发票。`)) {
+      allowBuyerMismatch = mismatched.map(file => file.path);
+    }
+
     try {
       this.startRenameButton.disabled = true;
       this.startRenameButton.textContent = "重命名中...";
@@ -548,7 +685,7 @@ class UIManager {
         // 根据当前tab类型生成不同的默认文件名
         let newFileName: string;
         if (this.appState.currentTab === 'pdf') {
-          newFileName = file.suggested_name || `${file.amount?.toFixed(2) || '未知金额'}元_发票.pdf`;
+          newFileName = file.suggested_name || `${file.amount || '未知金额'}元_发票.pdf`;
         } else {
           // 图片文件使用suggested_name，如果没有则保持原名
           newFileName = file.suggested_name || file.name;
@@ -565,7 +702,8 @@ class UIManager {
       
       // 执行重命名
       const result = await invoke<RenameResult>('execute_rename', { 
-        renames: renameOperations 
+        renames: renameOperations,
+        allowBuyerMismatch
       });
       
       if (result.success) {
@@ -587,7 +725,7 @@ class UIManager {
       
     } catch (error) {
       console.error("重命名失败:", error);
-      alert("重命名失败，请检查文件权限或重试");
+      alert(`重命名失败：${describeError(error)}`);
     } finally {
       this.startRenameButton.disabled = false;
       const selectedCount = this.appState.getSelectedCount();
@@ -648,6 +786,7 @@ class UIManager {
 window.addEventListener("DOMContentLoaded", async () => {
   try {
     const appState = new AppState();
+    await loadLanguage();
     
     // 等待异步初始化完成
     await appState.initializeFiles();
@@ -669,6 +808,13 @@ window.addEventListener("DOMContentLoaded", async () => {
       }
     });
     
+    // 监控文件夹中出现新文件时刷新列表
+    for (const event of ['watch:queued', 'watch:renamed']) {
+      await listen(event, () => {
+        uiManager.refresh();
+      });
+    }
+    
     // 将管理器保存到全局作用域以供调试使用
     (window as any).uiManager = uiManager;
     (window as any).tabManager = tabManager;